@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS accounts (
+    id              TEXT    PRIMARY KEY NOT NULL,
+    name            VARCHAR(100)        NOT NULL,
+    kind            VARCHAR(20)         NOT NULL,
+    opening_balance CHARACTER(50)       NOT NULL,
+    opening_date    DATETIME            NOT NULL,
+    currency        CHARACTER(3)        NOT NULL
+);
+
+ALTER TABLE transactions ADD COLUMN account_id TEXT REFERENCES accounts (id);
+
+CREATE INDEX IF NOT EXISTS transactions_account_id ON transactions (account_id);
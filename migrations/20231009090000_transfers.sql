@@ -0,0 +1,5 @@
+ALTER TABLE transactions ADD COLUMN kind VARCHAR(20) NOT NULL DEFAULT 'regular';
+ALTER TABLE transactions ADD COLUMN transfer_id TEXT;
+ALTER TABLE transactions ADD COLUMN report_id TEXT;
+
+CREATE INDEX IF NOT EXISTS transactions_report_id ON transactions (report_id);
@@ -0,0 +1,21 @@
+INSERT OR IGNORE INTO accounts (id, name, kind, opening_balance, opening_date, currency) VALUES
+    ('00000000-0000-0000-0000-000000000001', 'Opening Balance Equity', 'equity',  '0', '1970-01-01', 'USD'),
+    ('00000000-0000-0000-0000-000000000002', 'Uncategorized Income',   'income',  '0', '1970-01-01', 'USD'),
+    ('00000000-0000-0000-0000-000000000003', 'Uncategorized Expenses', 'expense', '0', '1970-01-01', 'USD'),
+    ('00000000-0000-0000-0000-000000000004', 'Unassigned',             'asset',   '0', '1970-01-01', 'USD'),
+    ('00000000-0000-0000-0000-000000000005', 'Transfer Clearing',      'asset',   '0', '1970-01-01', 'USD');
+
+CREATE TABLE IF NOT EXISTS journal_entries (
+    id            TEXT    PRIMARY KEY NOT NULL,
+    date          DATETIME            NOT NULL,
+    memo          VARCHAR(100)        NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS postings (
+    id               TEXT    PRIMARY KEY NOT NULL,
+    journal_entry_id TEXT                NOT NULL REFERENCES journal_entries (id),
+    account_id       TEXT                NOT NULL REFERENCES accounts (id),
+    amount           CHARACTER(50)       NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS postings_journal_entry_id ON postings (journal_entry_id);
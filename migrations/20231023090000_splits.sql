@@ -0,0 +1,12 @@
+ALTER TABLE transactions ADD COLUMN category VARCHAR(100);
+
+CREATE TABLE IF NOT EXISTS transaction_splits (
+    id             TEXT    PRIMARY KEY NOT NULL,
+    transaction_id TEXT                NOT NULL REFERENCES transactions (id),
+    position       INTEGER             NOT NULL,
+    amount         CHARACTER(50)       NOT NULL,
+    category       VARCHAR(100),
+    memo           VARCHAR(100)        NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS transaction_splits_transaction_id ON transaction_splits (transaction_id);
@@ -0,0 +1,9 @@
+ALTER TABLE transactions ADD COLUMN currency CHARACTER(3) NOT NULL DEFAULT 'USD';
+
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    date          DATETIME            NOT NULL,
+    base          CHARACTER(3)        NOT NULL,
+    quote         CHARACTER(3)        NOT NULL,
+    rate          CHARACTER(50)       NOT NULL,
+    PRIMARY KEY (date, base, quote)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS reconciliations (
+    id              TEXT    PRIMARY KEY NOT NULL,
+    account_id      TEXT                NOT NULL REFERENCES accounts (id),
+    statement_date  DATETIME            NOT NULL,
+    closing_balance CHARACTER(50)       NOT NULL,
+    status          VARCHAR(20)         NOT NULL
+);
+
+ALTER TABLE transactions ADD COLUMN cleared BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE transactions ADD COLUMN reconciliation_id TEXT REFERENCES reconciliations (id);
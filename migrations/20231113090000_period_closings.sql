@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS period_closings (
+    id             TEXT    PRIMARY KEY NOT NULL,
+    closed_through DATETIME,
+    reason         TEXT,
+    recorded_at    VARCHAR(40)         NOT NULL
+);
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS schedules (
+    id            TEXT    PRIMARY KEY NOT NULL,
+    account_id    TEXT                REFERENCES accounts (id),
+    amount        CHARACTER(50)       NOT NULL,
+    memo          TEXT                NOT NULL,
+    category      TEXT,
+    frequency     VARCHAR(20)         NOT NULL,
+    interval      INTEGER             NOT NULL,
+    day_of_month  INTEGER,
+    start_date    DATETIME            NOT NULL,
+    end_date      DATETIME,
+    count         INTEGER
+);
+
+ALTER TABLE transactions ADD COLUMN schedule_id TEXT REFERENCES schedules (id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS transactions_schedule_occurrence
+    ON transactions (schedule_id, date) WHERE schedule_id IS NOT NULL;
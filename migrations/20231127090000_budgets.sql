@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS budgets (
+    id            TEXT    PRIMARY KEY NOT NULL,
+    category      TEXT                NOT NULL,
+    start_date    DATETIME            NOT NULL,
+    end_date      DATETIME            NOT NULL,
+    amount        CHARACTER(50)       NOT NULL,
+    rollover      BOOLEAN             NOT NULL
+);
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS transactions_account_date ON transactions (account_id, date);
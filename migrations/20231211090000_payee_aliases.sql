@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS payee_aliases (
+    memo          TEXT    PRIMARY KEY NOT NULL,
+    payee         TEXT                NOT NULL
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS trips (
+    id              TEXT    PRIMARY KEY NOT NULL,
+    date            DATETIME            NOT NULL,
+    start_odometer  CHARACTER(50),
+    end_odometer    CHARACTER(50),
+    distance        CHARACTER(50)       NOT NULL,
+    purpose         TEXT                NOT NULL,
+    transaction_id  TEXT                REFERENCES transactions (id)
+);
+
+CREATE INDEX IF NOT EXISTS trips_date ON trips (date);
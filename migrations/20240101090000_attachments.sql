@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS attachment_contents (
+    hash     TEXT    PRIMARY KEY NOT NULL,
+    content  BLOB                NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS attachments (
+    id              TEXT    PRIMARY KEY NOT NULL,
+    transaction_id  TEXT                NOT NULL REFERENCES transactions (id),
+    file_name       TEXT                NOT NULL,
+    mime_type       TEXT                NOT NULL,
+    size            INTEGER             NOT NULL,
+    hash            TEXT                NOT NULL REFERENCES attachment_contents (hash),
+    uploaded_at     TEXT                NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS attachments_transaction ON attachments (transaction_id);
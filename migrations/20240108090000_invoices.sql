@@ -0,0 +1,34 @@
+CREATE TABLE IF NOT EXISTS customers (
+    id     TEXT    PRIMARY KEY NOT NULL,
+    name   TEXT                NOT NULL,
+    email  TEXT
+);
+
+CREATE TABLE IF NOT EXISTS invoices (
+    id           TEXT        PRIMARY KEY NOT NULL,
+    customer_id  TEXT                    NOT NULL REFERENCES customers (id),
+    number       TEXT                    NOT NULL UNIQUE,
+    issue_date   DATETIME                NOT NULL,
+    due_date     DATETIME                NOT NULL,
+    status       VARCHAR(20)             NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS invoice_lines (
+    id           TEXT        PRIMARY KEY NOT NULL,
+    invoice_id   TEXT                    NOT NULL REFERENCES invoices (id),
+    position     INTEGER                 NOT NULL,
+    description  TEXT                    NOT NULL,
+    quantity     CHARACTER(50)           NOT NULL,
+    unit_price   CHARACTER(50)           NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS invoice_payments (
+    id              TEXT            PRIMARY KEY NOT NULL,
+    invoice_id      TEXT                        NOT NULL REFERENCES invoices (id),
+    transaction_id  TEXT                        NOT NULL REFERENCES transactions (id),
+    amount          CHARACTER(50)               NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS invoice_lines_invoice ON invoice_lines (invoice_id);
+CREATE INDEX IF NOT EXISTS invoice_payments_invoice ON invoice_payments (invoice_id);
+CREATE INDEX IF NOT EXISTS invoice_payments_transaction ON invoice_payments (transaction_id);
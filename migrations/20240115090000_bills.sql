@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS vendors (
+    id     TEXT    PRIMARY KEY NOT NULL,
+    name   TEXT                NOT NULL,
+    email  TEXT
+);
+
+CREATE TABLE IF NOT EXISTS bills (
+    id          TEXT            PRIMARY KEY NOT NULL,
+    vendor_id   TEXT                        NOT NULL REFERENCES vendors (id),
+    number      TEXT                        NOT NULL,
+    issue_date  DATETIME                    NOT NULL,
+    due_date    DATETIME                    NOT NULL,
+    amount      CHARACTER(50)               NOT NULL,
+    memo        TEXT                        NOT NULL,
+    UNIQUE (vendor_id, number)
+);
+
+CREATE TABLE IF NOT EXISTS bill_payments (
+    id              TEXT            PRIMARY KEY NOT NULL,
+    bill_id         TEXT                        NOT NULL REFERENCES bills (id),
+    transaction_id  TEXT                        NOT NULL REFERENCES transactions (id),
+    amount          CHARACTER(50)               NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS bill_payments_bill ON bill_payments (bill_id);
+CREATE INDEX IF NOT EXISTS bill_payments_transaction ON bill_payments (transaction_id);
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS audit_log (
+    sequence     INTEGER     PRIMARY KEY AUTOINCREMENT,
+    recorded_at  TEXT                    NOT NULL,
+    actor        TEXT                    NOT NULL,
+    request_id   TEXT,
+    entity       TEXT                    NOT NULL,
+    entity_id    TEXT                    NOT NULL,
+    action       VARCHAR(20)             NOT NULL,
+    before       TEXT,
+    after        TEXT
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_entity ON audit_log (entity, entity_id);
+CREATE INDEX IF NOT EXISTS audit_log_recorded_at ON audit_log (recorded_at);
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'the audit log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'the audit log is append-only');
+END;
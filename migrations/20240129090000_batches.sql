@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS batches (
+    sequence       INTEGER     PRIMARY KEY,
+    report_id      TEXT                    NOT NULL UNIQUE,
+    previous_hash  TEXT,
+    hash           TEXT                    NOT NULL,
+    recorded_at    TEXT                    NOT NULL
+);
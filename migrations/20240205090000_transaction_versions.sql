@@ -0,0 +1,12 @@
+ALTER TABLE transactions ADD COLUMN valid_from TEXT;
+ALTER TABLE transactions ADD COLUMN valid_to TEXT;
+
+CREATE TABLE IF NOT EXISTS transaction_versions (
+    id              TEXT        PRIMARY KEY     NOT NULL,
+    transaction_id  TEXT                        NOT NULL,
+    valid_from      TEXT,
+    valid_to        TEXT                        NOT NULL,
+    data            TEXT                        NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS transaction_versions_transaction_id ON transaction_versions (transaction_id);
@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS events (
+    sequence        INTEGER     PRIMARY KEY AUTOINCREMENT,
+    recorded_at     TEXT                    NOT NULL,
+    kind            VARCHAR(40)             NOT NULL,
+    transaction_id  TEXT                    NOT NULL,
+    report_id       TEXT,
+    data            TEXT                    NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS events_transaction_id ON events (transaction_id);
+CREATE INDEX IF NOT EXISTS events_report_id ON events (report_id);
+
+CREATE TRIGGER IF NOT EXISTS events_no_update BEFORE UPDATE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'the event store is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS events_no_delete BEFORE DELETE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'the event store is append-only');
+END;
+
+CREATE TABLE IF NOT EXISTS projection_cursors (
+    name        TEXT        PRIMARY KEY     NOT NULL,
+    sequence    INTEGER                     NOT NULL
+);
@@ -0,0 +1,13 @@
+ALTER TABLE journal_entries ADD COLUMN created_at TEXT;
+
+UPDATE journal_entries SET created_at = (
+    SELECT MIN(recorded_at) FROM audit_log
+    WHERE entity = 'journal_entry' AND action = 'create' AND entity_id = journal_entries.id
+);
+
+UPDATE transactions SET valid_from = COALESCE(
+    (SELECT recorded_at FROM batches WHERE batches.report_id = transactions.report_id),
+    (SELECT MIN(recorded_at) FROM audit_log
+     WHERE entity = 'transaction' AND action = 'create' AND entity_id = transactions.id)
+)
+WHERE valid_from IS NULL;
@@ -0,0 +1,52 @@
+ALTER TABLE journal_entries ADD COLUMN source VARCHAR(20);
+
+-- Imported transactions and opening balances are posted when they are stored,
+-- each as an entry sharing its id; post those stored before.
+INSERT INTO journal_entries (id, date, memo, created_at, source)
+SELECT id, date, memo, valid_from, 'transaction'
+FROM transactions
+WHERE valid_to IS NULL;
+
+INSERT INTO journal_entries (id, date, memo, created_at, source)
+SELECT id, opening_date, 'Opening balance of ' || name, NULL, 'opening_balance'
+FROM accounts
+WHERE CAST(opening_balance AS REAL) <> 0;
+
+INSERT INTO postings (id, journal_entry_id, account_id, amount)
+SELECT
+    lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4'
+        || substr(lower(hex(randomblob(2))), 2) || '-'
+        || substr('89ab', 1 + (abs(random()) % 4), 1) || substr(lower(hex(randomblob(2))), 2)
+        || '-' || lower(hex(randomblob(6))),
+    journal_entry_id,
+    account_id,
+    amount
+FROM (
+    SELECT id AS journal_entry_id,
+        COALESCE(account_id, '00000000-0000-0000-0000-000000000004') AS account_id,
+        amount
+    FROM transactions
+    WHERE valid_to IS NULL
+    UNION ALL
+    SELECT id,
+        CASE
+            WHEN kind = 'transfer' THEN '00000000-0000-0000-0000-000000000005'
+            WHEN CAST(amount AS REAL) > 0 THEN '00000000-0000-0000-0000-000000000002'
+            ELSE '00000000-0000-0000-0000-000000000003'
+        END,
+        CASE WHEN amount LIKE '-%' THEN substr(amount, 2) ELSE '-' || amount END
+    FROM transactions
+    WHERE valid_to IS NULL
+    UNION ALL
+    SELECT id, id, opening_balance
+    FROM accounts
+    WHERE CAST(opening_balance AS REAL) <> 0
+    UNION ALL
+    SELECT id,
+        '00000000-0000-0000-0000-000000000001',
+        CASE WHEN opening_balance LIKE '-%' THEN substr(opening_balance, 2) ELSE '-' || opening_balance END
+    FROM accounts
+    WHERE CAST(opening_balance AS REAL) <> 0
+);
+
+CREATE INDEX IF NOT EXISTS journal_entries_source ON journal_entries (source);
@@ -0,0 +1,101 @@
+-- The transactions committed before the event store have no import event, so
+-- the projection could not rebuild the reports of their uploads. Record one
+-- for each, holding the transaction as it was before its first recorded
+-- event, and a deletion for those deleted before the event store. These come
+-- before the events already recorded, so the otherwise append-only store is
+-- rebuilt in that order and the projections replay from the first event.
+DROP TRIGGER IF EXISTS events_no_update;
+DROP TRIGGER IF EXISTS events_no_delete;
+
+CREATE TEMPORARY TABLE recorded_events AS SELECT * FROM events;
+DELETE FROM events;
+DELETE FROM sqlite_sequence WHERE name = 'events';
+
+INSERT INTO events (recorded_at, kind, transaction_id, report_id, data)
+SELECT
+    COALESCE(
+        (SELECT recorded_at FROM batches WHERE batches.report_id = t.report_id),
+        t.valid_from,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    ),
+    'TransactionImported',
+    t.id,
+    t.report_id,
+    json_object(
+        'type', 'TransactionImported',
+        'transaction_id', t.id,
+        'transaction', COALESCE(
+            (SELECT json(v.data) FROM transaction_versions v
+             WHERE v.transaction_id = t.id
+                 AND first.kind IN ('TransactionEdited', 'CategoryAssigned')
+                 AND v.valid_to <= first.recorded_at
+             ORDER BY v.valid_to DESC
+             LIMIT 1),
+            json_object(
+                'date', t.date,
+                'amount', t.amount,
+                'memo', t.memo,
+                'account_id', t.account_id,
+                'kind', t.kind,
+                'transfer_id', t.transfer_id,
+                'category', t.category,
+                'splits', (
+                    SELECT json_group_array(json_object(
+                        'amount', s.amount,
+                        'category', s.category,
+                        'memo', s.memo
+                    ))
+                    FROM (SELECT * FROM transaction_splits
+                          WHERE transaction_id = t.id
+                          ORDER BY position) s
+                ),
+                'currency', t.currency,
+                'cleared', json(CASE WHEN t.cleared THEN 'true' ELSE 'false' END),
+                'reconciliation_id', t.reconciliation_id,
+                'schedule_id', t.schedule_id,
+                'tax_code', t.tax_code
+            )
+        )
+    )
+FROM transactions t
+LEFT JOIN recorded_events first
+    ON first.sequence = (SELECT MIN(sequence) FROM recorded_events WHERE transaction_id = t.id)
+WHERE NOT EXISTS (
+    SELECT 1 FROM recorded_events
+    WHERE transaction_id = t.id AND kind = 'TransactionImported'
+)
+ORDER BY t.rowid;
+
+INSERT INTO events (recorded_at, kind, transaction_id, report_id, data)
+SELECT
+    t.valid_to,
+    'TransactionDeleted',
+    t.id,
+    t.report_id,
+    json_object('type', 'TransactionDeleted', 'transaction_id', t.id)
+FROM transactions t
+WHERE t.valid_to IS NOT NULL
+    AND NOT EXISTS (
+        SELECT 1 FROM recorded_events
+        WHERE transaction_id = t.id AND kind = 'TransactionDeleted'
+    )
+ORDER BY t.rowid;
+
+INSERT INTO events (recorded_at, kind, transaction_id, report_id, data)
+SELECT recorded_at, kind, transaction_id, report_id, data
+FROM recorded_events
+ORDER BY sequence;
+
+DROP TABLE recorded_events;
+
+CREATE TRIGGER IF NOT EXISTS events_no_update BEFORE UPDATE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'the event store is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS events_no_delete BEFORE DELETE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'the event store is append-only');
+END;
+
+DELETE FROM projection_cursors;
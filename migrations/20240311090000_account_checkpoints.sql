@@ -0,0 +1,32 @@
+-- The sum of the live transactions of an account dated from `opening_date`
+-- and before `date`, recorded while paging through its statement so that the
+-- next pages only sum the transactions since. Any change of a transaction of
+-- the account dated before `date`, through the application or not, removes
+-- the checkpoint.
+CREATE TABLE IF NOT EXISTS account_checkpoints (
+    account_id      TEXT            NOT NULL,
+    opening_date    TEXT            NOT NULL,
+    date            TEXT            NOT NULL,
+    total           CHARACTER(50)   NOT NULL,
+    PRIMARY KEY (account_id, opening_date, date)
+);
+
+CREATE TRIGGER IF NOT EXISTS account_checkpoints_insert AFTER INSERT ON transactions
+BEGIN
+    DELETE FROM account_checkpoints
+    WHERE account_id = NEW.account_id AND date > NEW.date;
+END;
+
+CREATE TRIGGER IF NOT EXISTS account_checkpoints_update
+AFTER UPDATE OF date, amount, account_id, valid_to ON transactions
+BEGIN
+    DELETE FROM account_checkpoints
+    WHERE (account_id = OLD.account_id AND date > OLD.date)
+        OR (account_id = NEW.account_id AND date > NEW.date);
+END;
+
+CREATE TRIGGER IF NOT EXISTS account_checkpoints_delete AFTER DELETE ON transactions
+BEGIN
+    DELETE FROM account_checkpoints
+    WHERE account_id = OLD.account_id AND date > OLD.date;
+END;
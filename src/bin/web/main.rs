@@ -1,29 +1,108 @@
 #![warn(clippy::pedantic)]
 
-use std::{net::SocketAddr, str::FromStr, time::Duration};
+use std::{convert::Infallible, net::SocketAddr, str::FromStr, sync::Arc, time::Duration};
 
 use axum::{
-    extract::{Multipart, State},
-    http::StatusCode,
-    routing::{get, post},
+    async_trait,
+    extract::{DefaultBodyLimit, FromRef, FromRequestParts, Multipart, Path, Query, State},
+    http::{header, request::Parts, StatusCode},
+    response::IntoResponse,
+    routing::{delete, get, post, put},
     Json, Router,
 };
+use chrono::{DateTime, NaiveDate, Utc};
 use error::Error;
 use futures::stream::StreamExt;
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use serde_json::Value;
 use sqlx::{
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
     SqlitePool,
 };
 use tracing::{instrument, Level};
+use uuid::Uuid;
 use weblib::{
-    entity::Transaction,
+    config::Config,
+    entity::{
+        Account, AuditContext, AuditFilter, Bill, Budget, Customer, ExchangeRate, ForecastOptions,
+        Invoice, JournalEntry, PayeeAlias, Reconciliation, Schedule, Split, Transaction, Trip,
+        TripEntry, Vendor,
+    },
+    ledger::Ledger,
     logic::{CSVReader, Model},
     query::SqliteStore,
 };
 
 mod error;
 
+/// How far ahead upcoming bills are listed unless asked otherwise.
+const UPCOMING_DAYS: u64 = 30;
+/// How far ahead upcoming bills can be listed at most.
+const MAX_UPCOMING_DAYS: u64 = 366;
+/// How often due occurrences of the recurring schedules are committed.
+const SCHEDULE_PERIOD: Duration = Duration::from_hours(1);
+/// How often the projections catch up with the recorded events.
+const PROJECTION_PERIOD: Duration = Duration::from_mins(1);
+const PREVIEW_COUNT: usize = 12;
+const PAGE_SIZE: u64 = 100;
+/// Room left for the multipart boundaries and headers around an attachment.
+const MULTIPART_OVERHEAD: usize = 16 * 1024;
+const ACTOR_HEADER: &str = "x-actor";
+const REQUEST_ID_HEADER: &str = "x-request-id";
+/// The actor of the requests which do not name one.
+const ANONYMOUS_ACTOR: &str = "anonymous";
+
+#[derive(Clone)]
+struct AppState {
+    pool: SqlitePool,
+    config: Arc<Config>,
+}
+
+impl FromRef<AppState> for SqlitePool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+/// The audit context of a request, taken from its `x-actor` and
+/// `x-request-id` headers. Requests without an id are given a new one.
+#[derive(Debug)]
+struct Auditor(AuditContext);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Auditor {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = |name| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|x| x.to_str().ok())
+                .map(ToOwned::to_owned)
+        };
+        let actor = header(ACTOR_HEADER).unwrap_or_else(|| ANONYMOUS_ACTOR.to_owned());
+        let request_id = header(REQUEST_ID_HEADER).unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        Ok(Auditor(AuditContext::new(actor, Some(request_id))))
+    }
+}
+
+fn load_config() -> Config {
+    let path = project_root::get_project_root()
+        .map(|r| r.join("config.json"))
+        .unwrap();
+
+    Config::load(&path).expect("cannot load config")
+}
+
 async fn setup_database() -> SqlitePool {
     let root = project_root::get_project_root()
         .map(|r| r.join("sqlite@localhost/sqlite.db"))
@@ -50,11 +129,82 @@ async fn setup_database() -> SqlitePool {
     pool
 }
 
-fn application(pool: SqlitePool) -> Router {
+fn application(pool: SqlitePool, config: Config) -> Router {
+    let attachment_limit = config.max_attachment_size() + MULTIPART_OVERHEAD;
     Router::new()
         .route("/report", get(report))
+        .route("/report/currencies", get(currency_reports))
+        .route("/report/categories", get(category_report))
+        .route("/report/converted", get(converted_report))
+        .route("/report/compare", get(comparison_report))
+        .route("/report/payees", get(payee_report))
+        .route("/report/tax", get(tax_report))
+        .route("/report/vat", get(vat_return))
+        .route("/report/mileage", get(mileage_report))
+        .route("/report/receipts", get(missing_receipts))
+        .route("/report/receivables", get(receivables_aging))
+        .route("/customers", get(customers).post(create_customer))
+        .route("/invoices", get(invoices).post(create_invoice))
+        .route("/invoices/:id", get(invoice))
+        .route("/invoices/:id/send", post(send_invoice))
+        .route("/invoices/:id/payments", post(pay_invoice))
+        .route("/report/payables", get(payables_aging))
+        .route("/vendors", get(vendors).post(create_vendor))
+        .route("/bills", get(bills).post(create_bill))
+        .route("/bills/upcoming", get(upcoming_bills))
+        .route("/bills/:id", get(bill))
+        .route("/bills/:id/payments", post(pay_bill))
+        .route("/payees", get(payees))
+        .route("/payees/aliases", get(payee_aliases).put(set_payee_alias))
+        .route("/exchange-rates", post(exchange_rates))
         .route("/transactions", post(transactions))
-        .with_state(pool)
+        .route("/transactions/:id", delete(delete_transaction))
+        .route("/transactions/:id/versions", get(transaction_versions))
+        .route("/transactions/:id/category", put(set_category))
+        .route("/transactions/:id/splits", put(set_splits))
+        .route("/transactions/:id/tax-code", put(set_tax_code))
+        .route(
+            "/transactions/:id/attachments",
+            get(attachments)
+                .post(upload_attachment)
+                .layer(DefaultBodyLimit::max(attachment_limit)),
+        )
+        .route("/attachments/:id", get(download_attachment))
+        .route("/accounts", get(accounts).post(create_account))
+        .route("/accounts/:id/balance", get(account_balance))
+        .route("/accounts/:id/report", get(account_report))
+        .route("/accounts/:id/forecast", get(account_forecast))
+        .route("/accounts/:id/transactions", get(account_transactions))
+        .route("/journal", get(journal_entries).post(create_journal_entry))
+        .route("/ledger/trial-balance", get(trial_balance))
+        .route("/ledger/balance-sheet", get(balance_sheet))
+        .route("/ledger/income-statement", get(income_statement))
+        .route("/transfers", post(link_transfer))
+        .route("/transfers/candidates", get(transfer_candidates))
+        .route("/transfers/detect", post(detect_transfers))
+        .route("/trips", get(trips).post(create_trip))
+        .route("/trips/import", post(import_trips))
+        .route("/trips/:id", get(trip).put(update_trip).delete(delete_trip))
+        .route("/budgets", get(budgets).post(create_budget))
+        .route("/budgets/report", get(budget_report))
+        .route("/schedules", get(schedules).post(create_schedule))
+        .route("/schedules/:id/preview", get(preview_schedule))
+        .route("/periods", get(period_closings))
+        .route("/periods/close", post(close_period))
+        .route("/periods/reopen", post(reopen_period))
+        .route("/reconciliations", post(start_reconciliation))
+        .route("/reconciliations/:id", get(reconciliation))
+        .route("/reconciliations/:id/cleared", put(set_cleared))
+        .route("/reconciliations/:id/finish", post(finish_reconciliation))
+        .route("/audit", get(audit_log))
+        .route("/changes", get(changes))
+        .route("/chain/verify", get(verify_chain))
+        .route("/report/check", get(check_reports))
+        .route("/report/repair", post(repair_reports))
+        .with_state(AppState {
+            pool,
+            config: Arc::new(config),
+        })
 }
 
 #[tokio::main]
@@ -63,8 +213,14 @@ async fn main() {
         .with_max_level(Level::DEBUG)
         .init();
 
+    let config = load_config();
     let pool = setup_database().await;
-    let app = application(pool);
+    if let Some(command) = std::env::args().nth(1) {
+        std::process::exit(run_command(&command, pool).await);
+    }
+    tokio::spawn(materialize_schedules(pool.clone()));
+    tokio::spawn(catch_up_projections(pool.clone()));
+    let app = application(pool, config);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 5000));
     axum::Server::bind(&addr)
@@ -75,20 +231,495 @@ async fn main() {
     tracing::debug!("listening on {}", addr);
 }
 
+/// Runs a maintenance command instead of the server and returns its exit
+/// code.
+async fn run_command(command: &str, pool: SqlitePool) -> i32 {
+    match command {
+        "verify-chain" => verify_chain_command(pool).await,
+        "check-reports" => check_reports_command(false, pool).await,
+        "repair-reports" => check_reports_command(true, pool).await,
+        "replay-projections" => projections_command(true, pool).await,
+        "catch-up-projections" => projections_command(false, pool).await,
+        _ => {
+            eprintln!("unknown command {command}");
+            2
+        }
+    }
+}
+
+/// Prints the verification of the hash chain over the uploads and fails if a
+/// link is broken.
+async fn verify_chain_command(pool: SqlitePool) -> i32 {
+    let result = async {
+        let tx = pool.begin().await?;
+        let mut store = SqliteStore::from_sqlite_transaction(tx);
+        Model::verify_chain(&mut store).await
+    }
+    .await;
+
+    match result {
+        Ok(verification) => {
+            println!("{}", serde_json::to_string_pretty(&verification).unwrap());
+            i32::from(!verification.is_intact())
+        }
+        Err(error) => {
+            eprintln!("cannot verify the chain: {error}");
+            2
+        }
+    }
+}
+
+/// Prints the reports that differ from their transactions, repairing them
+/// with `repair`, and fails if any differed.
+async fn check_reports_command(repair: bool, pool: SqlitePool) -> i32 {
+    let result = async {
+        let tx = pool.begin().await?;
+        let store = SqliteStore::from_sqlite_transaction(tx);
+        Model::check_reports(repair, store).await
+    }
+    .await;
+
+    match result {
+        Ok(check) => {
+            println!("{}", serde_json::to_string_pretty(&check).unwrap());
+            i32::from(!check.is_consistent())
+        }
+        Err(error) => {
+            eprintln!("cannot check the reports: {error}");
+            2
+        }
+    }
+}
+
+/// Rebuilds the projections from the first event with `replay`, or applies
+/// the events since their cursor, and prints what was projected.
+async fn projections_command(replay: bool, pool: SqlitePool) -> i32 {
+    let result = async {
+        let tx = pool.begin().await?;
+        let store = SqliteStore::from_sqlite_transaction(tx);
+        if replay {
+            Model::replay_projections(store).await
+        } else {
+            Model::catch_up_projections(store).await
+        }
+    }
+    .await;
+
+    match result {
+        Ok(run) => {
+            println!("{}", serde_json::to_string_pretty(&run).unwrap());
+            0
+        }
+        Err(error) => {
+            eprintln!("cannot project the events: {error}");
+            2
+        }
+    }
+}
+
+/// Applies the recorded events to the projections at startup and then
+/// periodically.
+async fn catch_up_projections(pool: SqlitePool) {
+    let mut interval = tokio::time::interval(PROJECTION_PERIOD);
+    loop {
+        interval.tick().await;
+        let result = async {
+            let tx = pool.begin().await?;
+            let store = SqliteStore::from_sqlite_transaction(tx);
+            Model::catch_up_projections(store).await
+        }
+        .await;
+
+        match result {
+            Ok(run) => tracing::debug!("projected {:?}", run),
+            Err(error) => tracing::error!("cannot project the events: {}", error),
+        }
+    }
+}
+
+/// Commits the due occurrences of the schedules at startup and then
+/// periodically. Occurrences that are already transactions are skipped, so a
+/// restart does not duplicate them.
+async fn materialize_schedules(pool: SqlitePool) {
+    let mut interval = tokio::time::interval(SCHEDULE_PERIOD);
+    loop {
+        interval.tick().await;
+        let result = async {
+            let tx = pool.begin().await?;
+            let store = SqliteStore::from_sqlite_transaction(tx);
+            Model::materialize_schedules(Utc::now().date_naive(), store).await
+        }
+        .await;
+
+        match result {
+            Ok(transactions) => tracing::debug!("materialized {} occurrences", transactions.len()),
+            Err(error) => tracing::error!("cannot materialize schedules: {}", error),
+        }
+    }
+}
+
+#[instrument(skip(pool))]
+async fn report(
+    State(pool): State<SqlitePool>,
+    Query(snapshot): Query<Snapshot>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let report = Model::get_report(snapshot.as_of, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(report).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn currency_reports(
+    State(pool): State<SqlitePool>,
+    Query(snapshot): Query<Snapshot>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let reports = Model::get_global_report(snapshot.as_of, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(reports).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn accounts(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let accounts = Model::get_accounts(&mut store).await?;
+
+    Ok(Json(serde_json::to_value(accounts).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_account(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(account): Json<Account>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let account = Model::create_account(account, store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(account).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn account_balance(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let balance = Model::get_account_balance(id, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(balance).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn account_report(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let report = Model::get_account_report(id, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(report).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct Page {
+    offset: Option<u64>,
+    limit: Option<u64>,
+}
+
+/// The transaction to resume a statement after, by default its start.
+#[derive(Debug, Deserialize)]
+struct StatementPage {
+    after: Option<Uuid>,
+    limit: Option<u64>,
+}
+
+#[instrument(skip(pool))]
+async fn account_transactions(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+    Query(page): Query<StatementPage>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx);
+    let lines =
+        Model::get_account_statement(id, page.after, page.limit.unwrap_or(PAGE_SIZE), store)
+            .await?;
+
+    Ok(Json(serde_json::to_value(lines).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn account_forecast(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+    Query(options): Query<ForecastOptions>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let forecast = Model::get_forecast(id, Utc::now().date_naive(), &options, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(forecast).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct AsOf {
+    as_of: Option<NaiveDate>,
+}
+
+/// The time to reproduce transaction reports at, instead of now.
+#[derive(Debug, Deserialize)]
+struct Snapshot {
+    as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Period {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+#[instrument(skip(pool))]
+async fn journal_entries(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let entries = store.get_journal_entries(None).await?;
+
+    Ok(Json(serde_json::to_value(entries).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_journal_entry(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(entry): Json<JournalEntry>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let entry = Ledger::create_journal_entry(entry, store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(entry).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn trial_balance(
+    State(pool): State<SqlitePool>,
+    Query(date): Query<AsOf>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let (accounts, entries) = Ledger::load(&mut store).await?;
+    let trial_balance = Ledger::trial_balance(&accounts, &entries, date.as_of);
+
+    Ok(Json(serde_json::to_value(trial_balance).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn balance_sheet(
+    State(pool): State<SqlitePool>,
+    Query(date): Query<AsOf>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let (accounts, entries) = Ledger::load(&mut store).await?;
+    let balance_sheet = Ledger::balance_sheet(&accounts, &entries, date.as_of);
+
+    Ok(Json(serde_json::to_value(balance_sheet).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn income_statement(
+    State(pool): State<SqlitePool>,
+    Query(period): Query<Period>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let (accounts, entries) = Ledger::load(&mut store).await?;
+    let income_statement = Ledger::income_statement(&accounts, &entries, period.from, period.to);
+
+    Ok(Json(serde_json::to_value(income_statement).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn category_report(
+    State(pool): State<SqlitePool>,
+    Query(period): Query<Period>,
+    Query(snapshot): Query<Snapshot>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let reports =
+        Model::get_category_reports(period.from, period.to, snapshot.as_of, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(reports).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct Periods {
+    from: NaiveDate,
+    to: NaiveDate,
+    previous_from: NaiveDate,
+    previous_to: NaiveDate,
+}
+
+#[instrument(skip(pool))]
+async fn comparison_report(
+    State(pool): State<SqlitePool>,
+    Query(periods): Query<Periods>,
+    Query(snapshot): Query<Snapshot>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let comparison = Model::get_comparison(
+        (periods.previous_from, periods.previous_to),
+        (periods.from, periods.to),
+        snapshot.as_of,
+        &mut store,
+    )
+    .await?;
+
+    Ok(Json(serde_json::to_value(comparison).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn payee_report(
+    State(pool): State<SqlitePool>,
+    Query(period): Query<Period>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let ranking = Model::get_payee_ranking(period.from, period.to, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(ranking).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn payees(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let payees = Model::get_payees(&mut store).await?;
+
+    Ok(Json(serde_json::to_value(payees).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn payee_aliases(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let aliases = store.get_payee_aliases().await?;
+
+    Ok(Json(serde_json::to_value(aliases).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn set_payee_alias(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(alias): Json<PayeeAlias>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let alias = Model::set_payee_alias(alias, store).await?;
+
+    Ok(Json(serde_json::to_value(alias).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct TaxYear {
+    year: i32,
+}
+
+#[instrument(skip(pool, config))]
+async fn tax_report(
+    State(pool): State<SqlitePool>,
+    State(config): State<Arc<Config>>,
+    Query(tax_year): Query<TaxYear>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let report = Model::get_tax_report(tax_year.year, &config, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(report).unwrap()))
+}
+
+#[instrument(skip(pool, config))]
+async fn vat_return(
+    State(pool): State<SqlitePool>,
+    State(config): State<Arc<Config>>,
+    Query(period): Query<Periods>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let vat_return = Model::get_vat_return(period.from, period.to, &config, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(vat_return).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct Conversion {
+    currency: String,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
 #[instrument(skip(pool))]
-async fn report(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+async fn converted_report(
+    State(pool): State<SqlitePool>,
+    Query(conversion): Query<Conversion>,
+) -> Result<Json<Value>, Error> {
     let tx = pool.begin().await?;
 
     let mut store = SqliteStore::from_sqlite_transaction(tx);
-    let reports = store.get_reports().await?;
-    let report = Model::calculate_total_report(reports.iter());
+    let report = Model::get_converted_report(
+        &conversion.currency,
+        conversion.from,
+        conversion.to,
+        &mut store,
+    )
+    .await?;
 
     Ok(Json(serde_json::to_value(report).unwrap()))
 }
 
+/// Imports daily exchange rates from a CSV sent in the *data* field.
 #[instrument(skip(pool, multipart))]
-async fn transactions(
+async fn exchange_rates(
     State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
     mut multipart: Multipart,
 ) -> Result<StatusCode, Error> {
     const KEY: &str = "data";
@@ -97,12 +728,14 @@ async fn transactions(
         match name {
             Some(name) if name == KEY => {
                 let data = field.bytes().await?;
-                let transactions = CSVReader::read_transaction_from_csv_bytes(data.as_ref());
-                let transactions: Vec<Transaction> = transactions.collect().await;
+                let rates: Vec<ExchangeRate> =
+                    CSVReader::read_exchange_rates_from_csv_bytes(data.as_ref())
+                        .collect()
+                        .await;
                 let tx = pool.begin().await?;
-                tracing::debug!("entering critical section");
-                let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
-                Model::commit_transactions(&transactions, sqlite_store).await?;
+                let sqlite_store =
+                    SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+                Model::commit_exchange_rates(&rates, sqlite_store).await?;
                 return Ok(StatusCode::CREATED);
             }
             _ => (),
@@ -115,23 +748,968 @@ async fn transactions(
     )))
 }
 
-#[cfg(test)]
-mod tests {
-    use axum::{body::Body, http::Request};
-    use sqlx::SqlitePool;
-    use weblib::entity::Report;
+#[instrument(skip(pool))]
+async fn delete_transaction(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let tx = pool.begin().await?;
 
-    use crate::application;
-    use tower::ServiceExt;
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    Model::delete_transaction(id, store).await?;
 
-    #[sqlx::test]
-    async fn get_report(pool: SqlitePool) -> Result<(), super::error::Error> {
-        let app = application(pool);
+    Ok(StatusCode::NO_CONTENT)
+}
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/report")
+#[instrument(skip(pool))]
+async fn transaction_versions(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let versions = store.get_transaction_versions(id).await?;
+
+    Ok(Json(serde_json::to_value(versions).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct Category {
+    category: Option<String>,
+}
+
+#[instrument(skip(pool))]
+async fn set_category(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+    Json(category): Json<Category>,
+) -> Result<StatusCode, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    Model::set_category(id, category.category, store).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+struct TaxCode {
+    tax_code: Option<String>,
+}
+
+#[instrument(skip(pool, config))]
+async fn set_tax_code(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    State(config): State<Arc<Config>>,
+    Path(id): Path<Uuid>,
+    Json(tax_code): Json<TaxCode>,
+) -> Result<StatusCode, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    Model::set_tax_code(id, tax_code.tax_code, &config, store).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(pool))]
+async fn set_splits(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+    Json(splits): Json<Vec<Split>>,
+) -> Result<StatusCode, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    Model::set_splits(id, splits, store).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+struct TransferWindow {
+    days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransferLegs {
+    outgoing: Uuid,
+    incoming: Uuid,
+}
+
+#[instrument(skip(pool))]
+async fn transfer_candidates(
+    State(pool): State<SqlitePool>,
+    Query(window): Query<TransferWindow>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let transactions = store.get_transactions().await?;
+    let candidates = Model::find_transfer_candidates(
+        &transactions,
+        window.days.unwrap_or(Model::TRANSFER_WINDOW_DAYS),
+    );
+
+    Ok(Json(serde_json::to_value(candidates).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn detect_transfers(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Query(window): Query<TransferWindow>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let linked =
+        Model::link_detected_transfers(window.days.unwrap_or(Model::TRANSFER_WINDOW_DAYS), store)
+            .await?;
+
+    Ok(Json(serde_json::to_value(linked).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn link_transfer(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(legs): Json<TransferLegs>,
+) -> Result<StatusCode, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    Model::link_transfer(legs.outgoing, legs.incoming, store).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(pool))]
+async fn budgets(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let budgets = store.get_budgets().await?;
+
+    Ok(Json(serde_json::to_value(budgets).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_budget(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(budget): Json<Budget>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let budget = Model::create_budget(budget, store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(budget).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn budget_report(
+    State(pool): State<SqlitePool>,
+    Query(period): Query<Period>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let lines = Model::get_budget_report(period.from, period.to, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(lines).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct Preview {
+    count: Option<usize>,
+}
+
+#[instrument(skip(pool))]
+async fn schedules(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let schedules = store.get_schedules().await?;
+
+    Ok(Json(serde_json::to_value(schedules).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_schedule(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(schedule): Json<Schedule>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let schedule = Model::create_schedule(schedule, store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(schedule).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn preview_schedule(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+    Query(preview): Query<Preview>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let dates = Model::get_schedule_preview(
+        id,
+        Utc::now().date_naive(),
+        preview.count.unwrap_or(PREVIEW_COUNT),
+        &mut store,
+    )
+    .await?;
+
+    Ok(Json(serde_json::to_value(dates).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct Closing {
+    through: NaiveDate,
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Reopening {
+    through: Option<NaiveDate>,
+    reason: String,
+}
+
+#[instrument(skip(pool))]
+async fn period_closings(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let closings = store.get_period_closings().await?;
+
+    Ok(Json(serde_json::to_value(closings).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn close_period(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(closing): Json<Closing>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let closing = Model::close_period(closing.through, closing.reason, store).await?;
+
+    Ok(Json(serde_json::to_value(closing).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn reopen_period(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(reopening): Json<Reopening>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let closing = Model::reopen_period(reopening.through, reopening.reason, store).await?;
+
+    Ok(Json(serde_json::to_value(closing).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct Cleared {
+    transactions: Vec<Uuid>,
+    cleared: bool,
+}
+
+#[instrument(skip(pool))]
+async fn start_reconciliation(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(reconciliation): Json<Reconciliation>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let summary = Model::start_reconciliation(reconciliation, store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(summary).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn reconciliation(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let summary = Model::get_reconciliation(id, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(summary).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn set_cleared(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+    Json(cleared): Json<Cleared>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let summary = Model::set_cleared(id, &cleared.transactions, cleared.cleared, store).await?;
+
+    Ok(Json(serde_json::to_value(summary).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn finish_reconciliation(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let summary = Model::finish_reconciliation(id, store).await?;
+
+    Ok(Json(serde_json::to_value(summary).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn audit_log(
+    State(pool): State<SqlitePool>,
+    Query(filter): Query<AuditFilter>,
+    Query(page): Query<Page>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let entries = store
+        .get_audit_entries(
+            &filter,
+            page.offset.unwrap_or(0),
+            page.limit.unwrap_or(PAGE_SIZE),
+        )
+        .await?;
+
+    Ok(Json(serde_json::to_value(entries).unwrap()))
+}
+
+/// The cursor to resume the change feed after, by default its start.
+#[derive(Debug, Deserialize)]
+struct ChangesSince {
+    since: Option<i64>,
+    limit: Option<u64>,
+}
+
+#[instrument(skip(pool))]
+async fn changes(
+    State(pool): State<SqlitePool>,
+    Query(since): Query<ChangesSince>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let feed = Model::get_changes(
+        since.since.unwrap_or(0),
+        since.limit.unwrap_or(PAGE_SIZE),
+        &mut store,
+    )
+    .await?;
+
+    Ok(Json(serde_json::to_value(feed).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn verify_chain(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let verification = Model::verify_chain(&mut store).await?;
+
+    Ok(Json(serde_json::to_value(verification).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn check_reports(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx);
+    let check = Model::check_reports(false, store).await?;
+
+    Ok(Json(serde_json::to_value(check).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn repair_reports(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let check = Model::check_reports(true, store).await?;
+
+    Ok(Json(serde_json::to_value(check).unwrap()))
+}
+
+#[instrument(skip(pool, config))]
+async fn mileage_report(
+    State(pool): State<SqlitePool>,
+    State(config): State<Arc<Config>>,
+    Query(period): Query<Period>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let report = Model::get_mileage_report(period.from, period.to, &config, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(report).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn attachments(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let attachments = store.get_attachments(id).await?;
+
+    Ok(Json(serde_json::to_value(attachments).unwrap()))
+}
+
+/// Attaches the file sent in the *file* field to the transaction.
+#[instrument(skip(pool, config, multipart))]
+async fn upload_attachment(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    State(config): State<Arc<Config>>,
+    Path(id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    const KEY: &str = "file";
+    while let Some(field) = multipart.next_field().await? {
+        let name = field.name();
+        match name {
+            Some(name) if name == KEY => {
+                let file_name = field.file_name().unwrap_or_default().to_owned();
+                let data = field.bytes().await?;
+                let tx = pool.begin().await?;
+                let sqlite_store =
+                    SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+                let attachment =
+                    Model::add_attachment(id, &file_name, data.to_vec(), &config, sqlite_store)
+                        .await?;
+                return Ok((
+                    StatusCode::CREATED,
+                    Json(serde_json::to_value(attachment).unwrap()),
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    Err(Error(anyhow::anyhow!(
+        "no file with key field *{}* inside POST",
+        KEY
+    )))
+}
+
+#[instrument(skip(pool))]
+async fn download_attachment(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let (attachment, content) = Model::get_attachment_file(id, &mut store).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.mime_type().to_owned()),
+            (
+                header::CONTENT_DISPOSITION,
+                attachment.content_disposition(),
+            ),
+        ],
+        content,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct Receipts {
+    #[serde(default)]
+    threshold: Decimal,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+#[instrument(skip(pool))]
+async fn missing_receipts(
+    State(pool): State<SqlitePool>,
+    Query(receipts): Query<Receipts>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let report =
+        Model::get_missing_receipts(receipts.threshold, receipts.from, receipts.to, &mut store)
+            .await?;
+
+    Ok(Json(serde_json::to_value(report).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn customers(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let customers = store.get_customers().await?;
+
+    Ok(Json(serde_json::to_value(customers).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_customer(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(customer): Json<Customer>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let customer = Model::create_customer(customer, store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(customer).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn invoices(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let invoices = Model::get_invoices(Utc::now().date_naive(), &mut store).await?;
+
+    Ok(Json(serde_json::to_value(invoices).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn invoice(
+    State(pool): State<SqlitePool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let invoice = Model::get_invoice(id, Utc::now().date_naive(), &mut store).await?;
+
+    Ok(Json(serde_json::to_value(invoice).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_invoice(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(invoice): Json<Invoice>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let invoice = Model::create_invoice(invoice, Utc::now().date_naive(), store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(invoice).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn send_invoice(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let invoice = Model::send_invoice(id, Utc::now().date_naive(), store).await?;
+
+    Ok(Json(serde_json::to_value(invoice).unwrap()))
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentRequest {
+    transaction_id: Uuid,
+    amount: Option<Decimal>,
+}
+
+#[instrument(skip(pool))]
+async fn pay_invoice(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+    Json(payment): Json<PaymentRequest>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let invoice = Model::pay_invoice(
+        id,
+        payment.transaction_id,
+        payment.amount,
+        Utc::now().date_naive(),
+        store,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(invoice).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn receivables_aging(
+    State(pool): State<SqlitePool>,
+    Query(date): Query<AsOf>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let as_of = date.as_of.unwrap_or_else(|| Utc::now().date_naive());
+    let report = Model::get_receivables_aging(as_of, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(report).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn vendors(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let vendors = store.get_vendors().await?;
+
+    Ok(Json(serde_json::to_value(vendors).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_vendor(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(vendor): Json<Vendor>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let vendor = Model::create_vendor(vendor, store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(vendor).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn bills(State(pool): State<SqlitePool>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let bills = Model::get_bills(Utc::now().date_naive(), &mut store).await?;
+
+    Ok(Json(serde_json::to_value(bills).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn bill(State(pool): State<SqlitePool>, Path(id): Path<Uuid>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let bill = Model::get_bill(id, Utc::now().date_naive(), &mut store).await?;
+
+    Ok(Json(serde_json::to_value(bill).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_bill(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(bill): Json<Bill>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let bill = Model::create_bill(bill, Utc::now().date_naive(), store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(bill).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn pay_bill(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+    Json(payment): Json<PaymentRequest>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let bill = Model::pay_bill(
+        id,
+        payment.transaction_id,
+        payment.amount,
+        Utc::now().date_naive(),
+        store,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(bill).unwrap()),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct Upcoming {
+    days: Option<u64>,
+}
+
+#[instrument(skip(pool))]
+async fn upcoming_bills(
+    State(pool): State<SqlitePool>,
+    Query(upcoming): Query<Upcoming>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let bills = Model::get_upcoming_bills(
+        Utc::now().date_naive(),
+        upcoming
+            .days
+            .unwrap_or(UPCOMING_DAYS)
+            .min(MAX_UPCOMING_DAYS),
+        &mut store,
+    )
+    .await?;
+
+    Ok(Json(serde_json::to_value(bills).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn payables_aging(
+    State(pool): State<SqlitePool>,
+    Query(date): Query<AsOf>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let as_of = date.as_of.unwrap_or_else(|| Utc::now().date_naive());
+    let report = Model::get_payables_aging(as_of, &mut store).await?;
+
+    Ok(Json(serde_json::to_value(report).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn trips(
+    State(pool): State<SqlitePool>,
+    Query(period): Query<Period>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let trips = store.get_trips(period.from, period.to).await?;
+
+    Ok(Json(serde_json::to_value(trips).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn trip(State(pool): State<SqlitePool>, Path(id): Path<Uuid>) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let mut store = SqliteStore::from_sqlite_transaction(tx);
+    let trip = store.get_trip(id).await?;
+
+    Ok(Json(serde_json::to_value(trip).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn create_trip(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Json(entry): Json<TripEntry>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let trip = Model::create_trip(entry, store).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::to_value(trip).unwrap()),
+    ))
+}
+
+#[instrument(skip(pool))]
+async fn update_trip(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+    Json(entry): Json<TripEntry>,
+) -> Result<Json<Value>, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    let trip = Model::update_trip(id, entry, store).await?;
+
+    Ok(Json(serde_json::to_value(trip).unwrap()))
+}
+
+#[instrument(skip(pool))]
+async fn delete_trip(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let tx = pool.begin().await?;
+
+    let store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+    Model::delete_trip(id, store).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Imports trips from a CSV sent in the *data* field.
+#[instrument(skip(pool, multipart))]
+async fn import_trips(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    const KEY: &str = "data";
+    while let Some(field) = multipart.next_field().await? {
+        let name = field.name();
+        match name {
+            Some(name) if name == KEY => {
+                let data = field.bytes().await?;
+                let trips: Vec<Trip> = CSVReader::read_trips_from_csv_bytes(data.as_ref())
+                    .collect()
+                    .await;
+                let tx = pool.begin().await?;
+                let sqlite_store =
+                    SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+                let trips = Model::create_trips(trips, sqlite_store).await?;
+                return Ok((
+                    StatusCode::CREATED,
+                    Json(serde_json::to_value(trips).unwrap()),
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    Err(Error(anyhow::anyhow!(
+        "no valid CSV with key field *{}* inside POST",
+        KEY
+    )))
+}
+
+/// Imports a CSV sent in the *data* field. An optional *account* field,
+/// which must precede *data*, assigns every imported transaction to that
+/// account.
+#[instrument(skip(pool, multipart))]
+async fn transactions(
+    State(pool): State<SqlitePool>,
+    Auditor(context): Auditor,
+    mut multipart: Multipart,
+) -> Result<StatusCode, Error> {
+    const KEY: &str = "data";
+    const ACCOUNT_KEY: &str = "account";
+    let mut account = None;
+    while let Some(field) = multipart.next_field().await? {
+        let name = field.name();
+        match name {
+            Some(name) if name == ACCOUNT_KEY => {
+                account = Some(Uuid::from_str(field.text().await?.trim())?);
+            }
+            Some(name) if name == KEY => {
+                let data = field.bytes().await?;
+                let transactions = CSVReader::read_transaction_from_csv_bytes(data.as_ref());
+                let transactions: Vec<Transaction> = transactions.collect().await;
+                let tx = pool.begin().await?;
+                tracing::debug!("entering critical section");
+                let sqlite_store =
+                    SqliteStore::from_sqlite_transaction(tx).with_audit_context(context);
+                match account {
+                    Some(account) => {
+                        Model::commit_account_transactions(account, transactions, sqlite_store)
+                            .await?;
+                    }
+                    None => {
+                        Model::commit_transactions(&transactions, sqlite_store).await?;
+                    }
+                }
+                return Ok(StatusCode::CREATED);
+            }
+            _ => (),
+        }
+    }
+
+    Err(Error(anyhow::anyhow!(
+        "no valid CSV with key field *{}* inside POST",
+        KEY
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use serde_json::Value;
+    use sqlx::SqlitePool;
+    use weblib::{config::Config, entity::Report};
+
+    use crate::application;
+    use tower::ServiceExt;
+
+    #[sqlx::test]
+    async fn get_report(pool: SqlitePool) -> Result<(), super::error::Error> {
+        let app = application(pool, Config::default());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/report")
                     .body(Body::empty())
                     .unwrap(),
             )
@@ -145,4 +1723,47 @@ mod tests {
         assert_eq!(expected_report, report);
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn audit_requests(pool: SqlitePool) -> Result<(), super::error::Error> {
+        let app = application(pool, Config::default());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/accounts")
+                    .header("content-type", "application/json")
+                    .header("x-actor", "alice")
+                    .header("x-request-id", "req-1")
+                    .body(Body::from(
+                        r#"{"name":"Checking","type":"checking","opening_balance":"100","opening_date":"2023-01-01","currency":"USD"}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::CREATED, response.status());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/audit?entity=account&actor=alice")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let entries: Vec<Value> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(1, entries.len());
+        assert_eq!("req-1", entries[0]["request_id"]);
+        assert_eq!("create", entries[0]["action"]);
+        assert_eq!(Value::Null, entries[0]["before"]);
+        assert_eq!("Checking", entries[0]["after"]["name"]);
+        Ok(())
+    }
 }
@@ -0,0 +1,304 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::NaiveDate;
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::error;
+
+/// Settings of the server, read from a JSON file.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub(crate) tax: TaxConfig,
+    #[serde(default)]
+    pub(crate) vat: VatConfig,
+    #[serde(default)]
+    pub(crate) mileage: MileageConfig,
+    #[serde(default)]
+    pub(crate) attachments: AttachmentConfig,
+}
+
+impl Config {
+    /// Reads the configuration, falling back to the defaults when the file
+    /// does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`error::Error::InvalidConfig`] if the file cannot be read or
+    /// parsed, or holds invalid settings.
+    pub fn load(path: &Path) -> Result<Config, error::Error> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .map_err(|x| error::Error::InvalidConfig(x.to_string()))?;
+        let config: Config = serde_json::from_str(&contents)
+            .map_err(|x| error::Error::InvalidConfig(x.to_string()))?;
+
+        if !config.tax.rates.is_valid() {
+            return Err(error::Error::InvalidConfig(
+                "tax brackets must have increasing thresholds, starting from zero".to_owned(),
+            ));
+        }
+        if !config.vat.is_valid() {
+            return Err(error::Error::InvalidConfig(
+                "tax codes must be unique, with non-overlapping date ranges".to_owned(),
+            ));
+        }
+        if config.mileage.rates.values().any(|x| *x < dec!(0)) {
+            return Err(error::Error::InvalidConfig(
+                "mileage rates cannot be negative".to_owned(),
+            ));
+        }
+        Ok(config)
+    }
+
+    #[must_use]
+    pub fn max_attachment_size(&self) -> usize {
+        self.attachments.max_size
+    }
+}
+
+/// The rates of the estimated tax, and the categories of expenses that are
+/// deducted from the income before computing it.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct TaxConfig {
+    #[serde(default)]
+    pub(crate) rates: TaxRates,
+    #[serde(default)]
+    pub(crate) deductible_categories: Vec<String>,
+}
+
+/// The rate applies to the part of the income above the threshold, up to the
+/// threshold of the next bracket.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct TaxBracket {
+    pub(crate) threshold: Decimal,
+    pub(crate) rate: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxRates {
+    Flat(Decimal),
+    Brackets(Vec<TaxBracket>),
+}
+
+impl Default for TaxRates {
+    fn default() -> Self {
+        TaxRates::Flat(dec!(0))
+    }
+}
+
+impl TaxRates {
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        match self {
+            TaxRates::Flat(_) => true,
+            TaxRates::Brackets(brackets) => {
+                brackets.first().is_none_or(|x| x.threshold == dec!(0))
+                    && brackets.windows(2).all(|x| x[0].threshold < x[1].threshold)
+            }
+        }
+    }
+
+    /// The tax on the income, nothing being owed on a loss.
+    #[must_use]
+    pub fn tax_on(&self, income: Decimal) -> Decimal {
+        if income <= dec!(0) {
+            return dec!(0);
+        }
+        match self {
+            TaxRates::Flat(rate) => income * rate,
+            TaxRates::Brackets(brackets) => brackets
+                .iter()
+                .enumerate()
+                .map(|(i, bracket)| {
+                    let top = brackets
+                        .get(i + 1)
+                        .map_or(income, |x| x.threshold.min(income));
+                    (top - bracket.threshold).max(dec!(0)) * bracket.rate
+                })
+                .sum(),
+        }
+    }
+}
+
+/// The rate of a tax code from its first date to its last date, both
+/// included and open-ended when missing.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct VatRate {
+    pub(crate) rate: Decimal,
+    #[serde(default)]
+    pub(crate) from: Option<NaiveDate>,
+    #[serde(default)]
+    pub(crate) to: Option<NaiveDate>,
+}
+
+impl VatRate {
+    fn applies_on(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|x| x <= date) && self.to.is_none_or(|x| date <= x)
+    }
+
+    fn overlaps(&self, other: &VatRate) -> bool {
+        let starts_before_end = |a: &VatRate, b: &VatRate| match (a.from, b.to) {
+            (Some(from), Some(to)) => from <= to,
+            _ => true,
+        };
+        starts_before_end(self, other) && starts_before_end(other, self)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct VatCode {
+    pub(crate) code: String,
+    pub(crate) rates: Vec<VatRate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct VatConfig {
+    #[serde(default)]
+    pub(crate) codes: Vec<VatCode>,
+}
+
+impl VatConfig {
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.codes.iter().enumerate().all(|(i, code)| {
+            self.codes[i + 1..].iter().all(|x| x.code != code.code)
+                && code
+                    .rates
+                    .iter()
+                    .enumerate()
+                    .all(|(j, rate)| code.rates[j + 1..].iter().all(|x| !x.overlaps(rate)))
+        })
+    }
+
+    #[must_use]
+    pub fn has_code(&self, code: &str) -> bool {
+        self.codes.iter().any(|x| x.code == code)
+    }
+
+    /// The rate of the tax code on the given date.
+    #[must_use]
+    pub fn rate(&self, code: &str, date: NaiveDate) -> Option<Decimal> {
+        self.codes
+            .iter()
+            .find(|x| x.code == code)?
+            .rates
+            .iter()
+            .find(|x| x.applies_on(date))
+            .map(|x| x.rate)
+    }
+
+    /// Splits a gross amount, which includes the tax, into its net and tax
+    /// portions. The tax is rounded half away from zero to 2 decimal places
+    /// and the net portion is what remains, so both always add up to the
+    /// gross amount.
+    #[must_use]
+    pub fn split(gross: Decimal, rate: Decimal) -> (Decimal, Decimal) {
+        let tax = (gross * rate / (dec!(1) + rate))
+            .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+        (gross - tax, tax)
+    }
+}
+
+/// The deductible amount per unit of distance for every year, and the
+/// categories or memos of the expenses of the vehicle.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct MileageConfig {
+    #[serde(default)]
+    pub(crate) rates: BTreeMap<i32, Decimal>,
+    #[serde(default)]
+    pub(crate) vehicle_expenses: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct AttachmentConfig {
+    /// The largest file accepted, in bytes.
+    pub(crate) max_size: usize,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 5 * 1024 * 1024,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use chrono::NaiveDate;
+
+    use super::{TaxBracket, TaxRates, VatCode, VatConfig, VatRate};
+
+    #[test]
+    fn tax_on_brackets() {
+        let rates = TaxRates::Brackets(vec![
+            TaxBracket {
+                threshold: dec!(0),
+                rate: dec!(0.10),
+            },
+            TaxBracket {
+                threshold: dec!(10000),
+                rate: dec!(0.20),
+            },
+        ]);
+
+        assert!(rates.is_valid());
+        assert_eq!(rates.tax_on(dec!(-500.00)), dec!(0));
+        assert_eq!(rates.tax_on(dec!(8000.00)), dec!(800.00));
+        assert_eq!(rates.tax_on(dec!(15000.00)), dec!(2000.00));
+        assert_eq!(TaxRates::Flat(dec!(0.25)).tax_on(dec!(100)), dec!(25.00));
+    }
+
+    #[test]
+    fn vat_rates() {
+        let change = NaiveDate::from_ymd_opt(2011, 1, 1).unwrap();
+        let mut config = VatConfig {
+            codes: vec![VatCode {
+                code: "STD".to_owned(),
+                rates: vec![
+                    VatRate {
+                        rate: dec!(0.175),
+                        from: None,
+                        to: change.pred_opt(),
+                    },
+                    VatRate {
+                        rate: dec!(0.20),
+                        from: Some(change),
+                        to: None,
+                    },
+                ],
+            }],
+        };
+
+        assert!(config.is_valid());
+        assert_eq!(
+            config.rate("STD", change.pred_opt().unwrap()),
+            Some(dec!(0.175))
+        );
+        assert_eq!(config.rate("STD", change), Some(dec!(0.20)));
+        assert_eq!(config.rate("RED", change), None);
+        assert_eq!(
+            VatConfig::split(dec!(120.00), dec!(0.20)),
+            (dec!(100.00), dec!(20.00))
+        );
+        assert_eq!(
+            VatConfig::split(dec!(-10.00), dec!(0.20)),
+            (dec!(-8.33), dec!(-1.67))
+        );
+        assert_eq!(
+            VatConfig::split(dec!(0.21), dec!(0.05)),
+            (dec!(0.20), dec!(0.01))
+        );
+
+        config.codes[0].rates[1].from = None;
+        assert!(!config.is_valid());
+    }
+}
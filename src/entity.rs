@@ -1,8 +1,8 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
-use chrono::NaiveDate;
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc};
 use derive_builder::Builder;
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 use sqlx::{sqlite::SqliteRow, FromRow, Row};
@@ -10,7 +10,7 @@ use uuid::Uuid;
 
 use crate::error;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct WithId<T> {
     pub(crate) id: Uuid,
     pub(crate) data: T,
@@ -42,6 +42,31 @@ impl<T: Default> WithId<T> {
     }
 }
 
+fn parse_column<T>(row: &SqliteRow, column: &str) -> Result<T, sqlx::Error>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    T::from_str(row.try_get(column)?).map_err(|x| sqlx::Error::ColumnDecode {
+        index: column.to_owned(),
+        source: Box::new(x),
+    })
+}
+
+fn parse_optional_column<T>(row: &SqliteRow, column: &str) -> Result<Option<T>, sqlx::Error>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    row.try_get::<Option<&str>, _>(column)?
+        .map(T::from_str)
+        .transpose()
+        .map_err(|x| sqlx::Error::ColumnDecode {
+            index: column.to_owned(),
+            source: Box::new(x),
+        })
+}
+
 impl<'a, T: FromRow<'a, SqliteRow>> FromRow<'a, SqliteRow> for WithId<T> {
     fn from_row(row: &'a SqliteRow) -> Result<Self, sqlx::Error> {
         let id = Uuid::from_str(row.try_get(WithId::<T>::ID_COL_NAME)?).map_err(|x| {
@@ -101,9 +126,14 @@ impl FromRow<'_, SqliteRow> for Report {
 }
 
 impl Report {
+    /// Transfers only move money between accounts and are left out of the
+    /// report.
     #[must_use]
     pub fn add_transaction(report: &Report, transaction: &Transaction) -> Report {
         let mut r = *report;
+        if transaction.kind == TransactionKind::Transfer {
+            return r;
+        }
         if transaction.amount > dec!(0) {
             r.gross_revenue += transaction.amount;
         } else {
@@ -144,13 +174,212 @@ pub struct TransactionFromCSV {
     income: String,
     amount: Decimal,
     memo: String,
+    #[serde(default)]
+    category: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    #[default]
+    Regular,
+    Transfer,
+}
+
+impl TransactionKind {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionKind::Regular => "regular",
+            TransactionKind::Transfer => "transfer",
+        }
+    }
+}
+
+impl FromStr for TransactionKind {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "regular" => Ok(TransactionKind::Regular),
+            "transfer" => Ok(TransactionKind::Transfer),
+            _ => Err(error::Error::InvalidTransactionKind(s.to_owned())),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Builder, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Builder, PartialEq, Clone)]
 pub struct Transaction {
     pub(crate) date: NaiveDate,
     pub(crate) amount: Decimal,
     pub(crate) memo: String,
+    #[builder(default)]
+    pub(crate) account_id: Option<Uuid>,
+    #[builder(default)]
+    pub(crate) kind: TransactionKind,
+    /// The other leg of a transfer.
+    #[builder(default)]
+    pub(crate) transfer_id: Option<Uuid>,
+    #[builder(default)]
+    pub(crate) category: Option<String>,
+    /// When present, the amounts of the splits sum to the amount of the
+    /// transaction and replace it in category reports.
+    #[builder(default)]
+    pub(crate) splits: Vec<Split>,
+    /// The currency of the account, or [`Transaction::DEFAULT_CURRENCY`] for
+    /// transactions without an account.
+    #[builder(default = "Transaction::DEFAULT_CURRENCY.to_owned()")]
+    pub(crate) currency: String,
+    /// Marked as matching the bank statement during a reconciliation.
+    #[builder(default)]
+    pub(crate) cleared: bool,
+    /// The finished reconciliation the transaction belongs to. Reconciled
+    /// transactions can no longer be edited.
+    #[builder(default)]
+    pub(crate) reconciliation_id: Option<Uuid>,
+    /// The recurring schedule the transaction is an occurrence of.
+    #[builder(default)]
+    pub(crate) schedule_id: Option<Uuid>,
+    /// The tax code whose rate is included in the amount.
+    #[builder(default)]
+    pub(crate) tax_code: Option<String>,
+}
+
+impl Default for Transaction {
+    fn default() -> Self {
+        Self {
+            date: NaiveDate::default(),
+            amount: dec!(0),
+            memo: String::new(),
+            account_id: None,
+            kind: TransactionKind::default(),
+            transfer_id: None,
+            category: None,
+            splits: vec![],
+            currency: Transaction::DEFAULT_CURRENCY.to_owned(),
+            cleared: false,
+            reconciliation_id: None,
+            schedule_id: None,
+            tax_code: None,
+        }
+    }
+}
+
+impl Transaction {
+    const DATE_COL_NAME: &'static str = "date";
+    const AMOUNT_COL_NAME: &'static str = "amount";
+    const MEMO_COL_NAME: &'static str = "memo";
+    const ACCOUNT_ID_COL_NAME: &'static str = "account_id";
+    const KIND_COL_NAME: &'static str = "kind";
+    const TRANSFER_ID_COL_NAME: &'static str = "transfer_id";
+    const CATEGORY_COL_NAME: &'static str = "category";
+    const CURRENCY_COL_NAME: &'static str = "currency";
+    const CLEARED_COL_NAME: &'static str = "cleared";
+    const RECONCILIATION_ID_COL_NAME: &'static str = "reconciliation_id";
+    const SCHEDULE_ID_COL_NAME: &'static str = "schedule_id";
+    const TAX_CODE_COL_NAME: &'static str = "tax_code";
+
+    pub const DEFAULT_CURRENCY: &'static str = "USD";
+
+    /// # Errors
+    ///
+    /// Returns [`error::Error::SplitSumMismatch`] when the splits do not sum
+    /// to the amount of the transaction.
+    pub fn validate_splits(&self) -> Result<(), error::Error> {
+        if self.splits.is_empty() {
+            return Ok(());
+        }
+        let sum: Decimal = self.splits.iter().map(|x| x.amount).sum();
+        if sum != self.amount {
+            return Err(error::Error::SplitSumMismatch {
+                expected: self.amount,
+                actual: sum,
+            });
+        }
+        Ok(())
+    }
+
+    /// The transaction as seen by category reports: either itself or one
+    /// transaction per split, each carrying the category of the split.
+    #[must_use]
+    pub fn category_parts(&self) -> Vec<Transaction> {
+        if self.splits.is_empty() {
+            return vec![Transaction {
+                splits: vec![],
+                ..self.clone()
+            }];
+        }
+        self.splits
+            .iter()
+            .map(|split| Transaction {
+                amount: split.amount,
+                memo: split.memo.clone(),
+                category: split.category.clone(),
+                splits: vec![],
+                ..self.clone()
+            })
+            .collect()
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Transaction {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            date: parse_column(row, Transaction::DATE_COL_NAME)?,
+            amount: parse_column(row, Transaction::AMOUNT_COL_NAME)?,
+            memo: row.try_get(Transaction::MEMO_COL_NAME)?,
+            account_id: parse_optional_column(row, Transaction::ACCOUNT_ID_COL_NAME)?,
+            kind: parse_column(row, Transaction::KIND_COL_NAME)?,
+            transfer_id: parse_optional_column(row, Transaction::TRANSFER_ID_COL_NAME)?,
+            category: row.try_get(Transaction::CATEGORY_COL_NAME)?,
+            splits: vec![],
+            currency: row.try_get(Transaction::CURRENCY_COL_NAME)?,
+            cleared: row.try_get(Transaction::CLEARED_COL_NAME)?,
+            reconciliation_id: parse_optional_column(row, Transaction::RECONCILIATION_ID_COL_NAME)?,
+            schedule_id: parse_optional_column(row, Transaction::SCHEDULE_ID_COL_NAME)?,
+            tax_code: row.try_get(Transaction::TAX_CODE_COL_NAME)?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Split {
+    pub(crate) amount: Decimal,
+    pub(crate) category: Option<String>,
+    pub(crate) memo: String,
+}
+
+impl Split {
+    const TRANSACTION_ID_COL_NAME: &'static str = "transaction_id";
+    const AMOUNT_COL_NAME: &'static str = "amount";
+    const CATEGORY_COL_NAME: &'static str = "category";
+    const MEMO_COL_NAME: &'static str = "memo";
+}
+
+/// A [`Split`] together with the id of its transaction.
+#[derive(Debug)]
+pub struct SplitOfTransaction {
+    pub(crate) transaction_id: Uuid,
+    pub(crate) split: Split,
+}
+
+impl FromRow<'_, SqliteRow> for SplitOfTransaction {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            transaction_id: parse_column(row, Split::TRANSACTION_ID_COL_NAME)?,
+            split: Split {
+                amount: parse_column(row, Split::AMOUNT_COL_NAME)?,
+                category: row.try_get(Split::CATEGORY_COL_NAME)?,
+                memo: row.try_get(Split::MEMO_COL_NAME)?,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct CategoryReport {
+    pub(crate) category: Option<String>,
+    pub(crate) report: Report,
 }
 
 impl TryFrom<TransactionFromCSV> for Transaction {
@@ -172,99 +401,2308 @@ impl TryFrom<TransactionFromCSV> for Transaction {
             .date(value.date)
             .amount(amount)
             .memo(value.memo)
+            .category(value.category)
             .build()
             .expect("incorrect initialization of transaction"))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use chrono::NaiveDate;
-    use rust_decimal_macros::dec;
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountKind {
+    Checking,
+    Savings,
+    CreditCard,
+    Cash,
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
 
-    use crate::error;
+impl AccountKind {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountKind::Checking => "checking",
+            AccountKind::Savings => "savings",
+            AccountKind::CreditCard => "credit_card",
+            AccountKind::Cash => "cash",
+            AccountKind::Asset => "asset",
+            AccountKind::Liability => "liability",
+            AccountKind::Equity => "equity",
+            AccountKind::Income => "income",
+            AccountKind::Expense => "expense",
+        }
+    }
 
-    use super::{Report, Transaction, TransactionFromCSV};
+    #[must_use]
+    pub fn class(&self) -> AccountClass {
+        match self {
+            AccountKind::Checking
+            | AccountKind::Savings
+            | AccountKind::Cash
+            | AccountKind::Asset => AccountClass::Asset,
+            AccountKind::CreditCard | AccountKind::Liability => AccountClass::Liability,
+            AccountKind::Equity => AccountClass::Equity,
+            AccountKind::Income => AccountClass::Income,
+            AccountKind::Expense => AccountClass::Expense,
+        }
+    }
+}
 
-    #[test]
-    fn from_valid_csv_transaction() {
-        let transaction_from_csv = TransactionFromCSV {
-            date: NaiveDate::from_ymd_opt(2021, 7, 20).unwrap(),
-            income: "Income".to_string(),
-            amount: dec!(12.11),
-            memo: "first".to_string(),
-        };
-        let expected_transaction = Transaction {
-            date: NaiveDate::from_ymd_opt(2021, 7, 20).unwrap(),
-            amount: dec!(12.11),
-            memo: "first".to_string(),
-        };
+impl FromStr for AccountKind {
+    type Err = error::Error;
 
-        let transaction: Transaction = TryFrom::try_from(transaction_from_csv).unwrap();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checking" => Ok(AccountKind::Checking),
+            "savings" => Ok(AccountKind::Savings),
+            "credit_card" => Ok(AccountKind::CreditCard),
+            "cash" => Ok(AccountKind::Cash),
+            "asset" => Ok(AccountKind::Asset),
+            "liability" => Ok(AccountKind::Liability),
+            "equity" => Ok(AccountKind::Equity),
+            "income" => Ok(AccountKind::Income),
+            "expense" => Ok(AccountKind::Expense),
+            _ => Err(error::Error::InvalidAccountKind(s.to_owned())),
+        }
+    }
+}
 
-        assert_eq!(transaction, expected_transaction)
+#[derive(Debug, Serialize, Deserialize, Builder, PartialEq, Eq, Clone)]
+pub struct Account {
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    pub(crate) kind: AccountKind,
+    pub(crate) opening_balance: Decimal,
+    pub(crate) opening_date: NaiveDate,
+    pub(crate) currency: String,
+}
+
+impl Account {
+    /// Counterpart of opening balances.
+    pub const OPENING_BALANCE_EQUITY_ID: Uuid = Uuid::from_u128(1);
+    /// Counterpart of incoming transactions.
+    pub const UNCATEGORIZED_INCOME_ID: Uuid = Uuid::from_u128(2);
+    /// Counterpart of outgoing transactions.
+    pub const UNCATEGORIZED_EXPENSES_ID: Uuid = Uuid::from_u128(3);
+    /// Holds transactions that were uploaded without an account.
+    pub const UNASSIGNED_ID: Uuid = Uuid::from_u128(4);
+    /// Counterpart of both legs of a transfer, nets to zero once both legs
+    /// are recorded.
+    pub const TRANSFER_CLEARING_ID: Uuid = Uuid::from_u128(5);
+
+    /// The accounts of the ledger itself, which are left out of the accounts
+    /// listed to users.
+    #[must_use]
+    pub fn is_system(id: Uuid) -> bool {
+        (1..=5).contains(&id.as_u128())
     }
 
-    #[test]
-    fn from_invalid_csv_transaction() {
-        let transaction_from_csv = TransactionFromCSV {
-            date: NaiveDate::from_ymd_opt(2021, 7, 20).unwrap(),
-            income: "IncomeX".to_string(),
-            amount: dec!(12.11),
-            memo: "first".to_string(),
-        };
+    const NAME_COL_NAME: &'static str = "name";
+    const KIND_COL_NAME: &'static str = "kind";
+    const OPENING_BALANCE_COL_NAME: &'static str = "opening_balance";
+    const OPENING_DATE_COL_NAME: &'static str = "opening_date";
+    const CURRENCY_COL_NAME: &'static str = "currency";
 
-        let transaction: Result<Transaction, _> = TryFrom::try_from(transaction_from_csv);
+    /// Currencies are stored as upper case ISO 4217 codes, e.g. `USD`.
+    #[must_use]
+    pub fn is_valid_currency(currency: &str) -> bool {
+        currency.len() == 3 && currency.chars().all(|c| c.is_ascii_uppercase())
+    }
+}
 
-        assert!(matches!(transaction, Err(error::Error::InvalidCSVIncome)));
+impl FromRow<'_, SqliteRow> for Account {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            name: row.try_get(Account::NAME_COL_NAME)?,
+            kind: parse_column(row, Account::KIND_COL_NAME)?,
+            opening_balance: parse_column(row, Account::OPENING_BALANCE_COL_NAME)?,
+            opening_date: parse_column(row, Account::OPENING_DATE_COL_NAME)?,
+            currency: row.try_get(Account::CURRENCY_COL_NAME)?,
+        })
     }
+}
 
-    #[test]
-    fn add_report() {
-        let report_0 = Report {
-            gross_revenue: dec!(87.32),
-            expenses: dec!(12.13),
-            net_revenue: dec!(75.19),
-        };
-        let report_1 = Report {
-            gross_revenue: dec!(10.01),
-            expenses: dec!(2.05),
-            net_revenue: dec!(7.96),
-        };
-        let expected_report = Report {
-            gross_revenue: dec!(97.33),
-            expenses: dec!(14.18),
-            net_revenue: dec!(83.15),
-        };
+/// The five classes of accounts of the double-entry ledger.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountClass {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
 
-        let report = Report::add(&report_0, &report_1);
+/// A posting debits an account when its amount is positive and credits it
+/// when negative.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Posting {
+    pub(crate) account_id: Uuid,
+    pub(crate) amount: Decimal,
+}
 
-        assert_eq!(report, expected_report);
+impl Posting {
+    const JOURNAL_ENTRY_ID_COL_NAME: &'static str = "journal_entry_id";
+    const ACCOUNT_ID_COL_NAME: &'static str = "account_id";
+    const AMOUNT_COL_NAME: &'static str = "amount";
+}
+
+/// Postings of a [`JournalEntry`] together with the id of their entry.
+#[derive(Debug)]
+pub struct PostingOfEntry {
+    pub(crate) journal_entry_id: Uuid,
+    pub(crate) posting: Posting,
+}
+
+impl FromRow<'_, SqliteRow> for PostingOfEntry {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            journal_entry_id: parse_column(row, Posting::JOURNAL_ENTRY_ID_COL_NAME)?,
+            posting: Posting {
+                account_id: parse_column(row, Posting::ACCOUNT_ID_COL_NAME)?,
+                amount: parse_column(row, Posting::AMOUNT_COL_NAME)?,
+            },
+        })
     }
+}
 
-    #[test]
-    fn add_transaction() {
-        let transaction_0 = Transaction {
-            date: NaiveDate::from_ymd_opt(2015, 11, 1).unwrap(),
-            amount: dec!(87.12),
-            memo: "first".to_string(),
-        };
-        let transaction_1 = Transaction {
-            date: NaiveDate::from_ymd_opt(2016, 11, 1).unwrap(),
-            amount: dec!(-12.13),
-            memo: "second".to_string(),
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct JournalEntry {
+    pub(crate) date: NaiveDate,
+    pub(crate) memo: String,
+    pub(crate) postings: Vec<Posting>,
+}
+
+impl JournalEntry {
+    const DATE_COL_NAME: &'static str = "date";
+    const MEMO_COL_NAME: &'static str = "memo";
+
+    /// # Errors
+    ///
+    /// An entry needs at least two postings whose amounts sum to zero.
+    pub fn validate(&self) -> Result<(), error::Error> {
+        if self.postings.len() < 2 {
+            return Err(error::Error::TooFewPostings);
+        }
+        let sum: Decimal = self.postings.iter().map(|x| x.amount).sum();
+        if sum != dec!(0) {
+            return Err(error::Error::UnbalancedJournalEntry(sum));
+        }
+        Ok(())
+    }
+
+    /// A transaction debits (or credits) its account against the uncategorized
+    /// income or expenses account, depending on its sign. Transfer legs are
+    /// posted against the transfer clearing account instead.
+    #[must_use]
+    pub fn from_transaction(transaction: &Transaction) -> JournalEntry {
+        let account_id = transaction.account_id.unwrap_or(Account::UNASSIGNED_ID);
+        let counterpart = if transaction.kind == TransactionKind::Transfer {
+            Account::TRANSFER_CLEARING_ID
+        } else if transaction.amount > dec!(0) {
+            Account::UNCATEGORIZED_INCOME_ID
+        } else {
+            Account::UNCATEGORIZED_EXPENSES_ID
         };
+        JournalEntry {
+            date: transaction.date,
+            memo: transaction.memo.clone(),
+            postings: vec![
+                Posting {
+                    account_id,
+                    amount: transaction.amount,
+                },
+                Posting {
+                    account_id: counterpart,
+                    amount: -transaction.amount,
+                },
+            ],
+        }
+    }
 
-        let report = Report::new();
-        let report = Report::add_transaction(&report, &transaction_0);
-        let report = Report::add_transaction(&report, &transaction_1);
+    /// An opening balance is posted against the opening balance equity
+    /// account, unless it is zero.
+    #[must_use]
+    pub fn from_opening_balance(account: &WithId<Account>) -> Option<JournalEntry> {
+        (account.data.opening_balance != dec!(0)).then(|| JournalEntry {
+            date: account.data.opening_date,
+            memo: format!("Opening balance of {}", account.data.name),
+            postings: vec![
+                Posting {
+                    account_id: account.id,
+                    amount: account.data.opening_balance,
+                },
+                Posting {
+                    account_id: Account::OPENING_BALANCE_EQUITY_ID,
+                    amount: -account.data.opening_balance,
+                },
+            ],
+        })
+    }
+}
 
-        let expected_report = Report {
-            gross_revenue: dec!(87.12),
-            expenses: dec!(12.13),
-            net_revenue: dec!(74.99),
+/// Journal entries are read without their postings, which are stored in
+/// their own table.
+impl FromRow<'_, SqliteRow> for JournalEntry {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            date: parse_column(row, JournalEntry::DATE_COL_NAME)?,
+            memo: row.try_get(JournalEntry::MEMO_COL_NAME)?,
+            postings: vec![],
+        })
+    }
+}
+
+/// Balance of a single ledger account, positive for a debit balance.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct LedgerLine {
+    pub(crate) account_id: Uuid,
+    pub(crate) name: String,
+    pub(crate) class: AccountClass,
+    pub(crate) balance: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct TrialBalanceLine {
+    pub(crate) account_id: Uuid,
+    pub(crate) name: String,
+    pub(crate) class: AccountClass,
+    pub(crate) debit: Decimal,
+    pub(crate) credit: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct TrialBalance {
+    pub(crate) lines: Vec<TrialBalanceLine>,
+    pub(crate) total_debit: Decimal,
+    pub(crate) total_credit: Decimal,
+}
+
+/// Liabilities and equity are shown with credit balances as positive
+/// amounts.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct BalanceSheet {
+    pub(crate) assets: Vec<LedgerLine>,
+    pub(crate) liabilities: Vec<LedgerLine>,
+    pub(crate) equity: Vec<LedgerLine>,
+    pub(crate) retained_earnings: Decimal,
+    pub(crate) total_assets: Decimal,
+    pub(crate) total_liabilities: Decimal,
+    pub(crate) total_equity: Decimal,
+}
+
+/// Income is shown with credit balances as positive amounts.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct IncomeStatement {
+    pub(crate) income: Vec<LedgerLine>,
+    pub(crate) expenses: Vec<LedgerLine>,
+    pub(crate) report: Report,
+}
+
+/// One unit of `base` is worth `rate` units of `quote` on `date`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ExchangeRate {
+    pub(crate) date: NaiveDate,
+    pub(crate) base: String,
+    pub(crate) quote: String,
+    pub(crate) rate: Decimal,
+}
+
+impl ExchangeRate {
+    const DATE_COL_NAME: &'static str = "date";
+    const BASE_COL_NAME: &'static str = "base";
+    const QUOTE_COL_NAME: &'static str = "quote";
+    const RATE_COL_NAME: &'static str = "rate";
+
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        Account::is_valid_currency(&self.base)
+            && Account::is_valid_currency(&self.quote)
+            && self.base != self.quote
+            && self.rate > dec!(0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for ExchangeRate {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            date: parse_column(row, ExchangeRate::DATE_COL_NAME)?,
+            base: row.try_get(ExchangeRate::BASE_COL_NAME)?,
+            quote: row.try_get(ExchangeRate::QUOTE_COL_NAME)?,
+            rate: parse_column(row, ExchangeRate::RATE_COL_NAME)?,
+        })
+    }
+}
+
+/// Daily exchange rates, looked up for the exact date of a transaction.
+#[derive(Debug, Default)]
+pub struct ExchangeRates {
+    rates: HashMap<(NaiveDate, String, String), Decimal>,
+}
+
+impl ExchangeRates {
+    /// Converted amounts are rounded to this many decimal places.
+    pub const DECIMAL_PLACES: u32 = 2;
+
+    pub fn from_rates(rates: impl IntoIterator<Item = ExchangeRate>) -> Self {
+        Self {
+            rates: rates
+                .into_iter()
+                .map(|x| ((x.date, x.base, x.quote), x.rate))
+                .collect(),
+        }
+    }
+
+    /// Converts with the rate of the date, or the inverse of the opposite
+    /// rate, rounding half to even to [`ExchangeRates::DECIMAL_PLACES`].
+    /// Returns `None` when neither rate is known.
+    #[must_use]
+    pub fn convert(
+        &self,
+        amount: Decimal,
+        from: &str,
+        to: &str,
+        date: NaiveDate,
+    ) -> Option<Decimal> {
+        let converted = if from == to {
+            amount
+        } else if let Some(rate) = self.rates.get(&(date, from.to_owned(), to.to_owned())) {
+            amount * rate
+        } else {
+            let rate = self.rates.get(&(date, to.to_owned(), from.to_owned()))?;
+            amount.checked_div(*rate)?
         };
 
-        assert_eq!(report, expected_report);
+        Some(converted.round_dp_with_strategy(
+            ExchangeRates::DECIMAL_PLACES,
+            RoundingStrategy::MidpointNearestEven,
+        ))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct CurrencyReport {
+    pub(crate) currency: String,
+    pub(crate) report: Report,
+}
+
+/// A report converted into a single currency, next to the reports of the
+/// transactions that could not be converted for lack of a rate.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ConvertedReport {
+    pub(crate) currency: String,
+    pub(crate) report: Report,
+    pub(crate) unconverted: Vec<CurrencyReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationStatus {
+    #[default]
+    Open,
+    Finished,
+}
+
+impl ReconciliationStatus {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReconciliationStatus::Open => "open",
+            ReconciliationStatus::Finished => "finished",
+        }
+    }
+}
+
+impl FromStr for ReconciliationStatus {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(ReconciliationStatus::Open),
+            "finished" => Ok(ReconciliationStatus::Finished),
+            _ => Err(error::Error::InvalidReconciliationStatus(s.to_owned())),
+        }
+    }
+}
+
+/// A bank statement of an account, against which the transactions up to its
+/// end date are matched.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Reconciliation {
+    pub(crate) account_id: Uuid,
+    pub(crate) statement_date: NaiveDate,
+    pub(crate) closing_balance: Decimal,
+    #[serde(default)]
+    pub(crate) status: ReconciliationStatus,
+}
+
+impl Reconciliation {
+    const ACCOUNT_ID_COL_NAME: &'static str = "account_id";
+    const STATEMENT_DATE_COL_NAME: &'static str = "statement_date";
+    const CLOSING_BALANCE_COL_NAME: &'static str = "closing_balance";
+    const STATUS_COL_NAME: &'static str = "status";
+}
+
+impl FromRow<'_, SqliteRow> for Reconciliation {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            account_id: parse_column(row, Reconciliation::ACCOUNT_ID_COL_NAME)?,
+            statement_date: parse_column(row, Reconciliation::STATEMENT_DATE_COL_NAME)?,
+            closing_balance: parse_column(row, Reconciliation::CLOSING_BALANCE_COL_NAME)?,
+            status: parse_column(row, Reconciliation::STATUS_COL_NAME)?,
+        })
+    }
+}
+
+/// The difference is the closing balance of the statement minus the cleared
+/// balance, and must be zero to finish the reconciliation.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReconciliationSummary {
+    pub(crate) reconciliation: WithId<Reconciliation>,
+    pub(crate) cleared_balance: Decimal,
+    pub(crate) difference: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct AccountBalance {
+    pub(crate) account_id: Uuid,
+    pub(crate) currency: String,
+    pub(crate) balance: Decimal,
+}
+
+/// A pair of transactions that look like the two legs of a transfer.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct TransferCandidate {
+    pub(crate) outgoing: Uuid,
+    pub(crate) incoming: Uuid,
+    pub(crate) amount: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "daily",
+            Frequency::Weekly => "weekly",
+            Frequency::Monthly => "monthly",
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "daily" => Ok(Frequency::Daily),
+            "weekly" => Ok(Frequency::Weekly),
+            "monthly" => Ok(Frequency::Monthly),
+            _ => Err(error::Error::InvalidFrequency(s.to_owned())),
+        }
+    }
+}
+
+/// A transaction that recurs every `interval` days, weeks or months from its
+/// start date, until its end date or for `count` occurrences. A monthly
+/// schedule falls on `day_of_month`, or the last day of shorter months.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Schedule {
+    pub(crate) account_id: Option<Uuid>,
+    pub(crate) amount: Decimal,
+    pub(crate) memo: String,
+    #[serde(default)]
+    pub(crate) category: Option<String>,
+    pub(crate) frequency: Frequency,
+    #[serde(default = "Schedule::default_interval")]
+    pub(crate) interval: u32,
+    #[serde(default)]
+    pub(crate) day_of_month: Option<u32>,
+    pub(crate) start_date: NaiveDate,
+    #[serde(default)]
+    pub(crate) end_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub(crate) count: Option<u32>,
+}
+
+impl Schedule {
+    const ACCOUNT_ID_COL_NAME: &'static str = "account_id";
+    const AMOUNT_COL_NAME: &'static str = "amount";
+    const MEMO_COL_NAME: &'static str = "memo";
+    const CATEGORY_COL_NAME: &'static str = "category";
+    const FREQUENCY_COL_NAME: &'static str = "frequency";
+    const INTERVAL_COL_NAME: &'static str = "interval";
+    const DAY_OF_MONTH_COL_NAME: &'static str = "day_of_month";
+    const START_DATE_COL_NAME: &'static str = "start_date";
+    const END_DATE_COL_NAME: &'static str = "end_date";
+    const COUNT_COL_NAME: &'static str = "count";
+
+    fn default_interval() -> u32 {
+        1
+    }
+
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.interval > 0
+            && self
+                .day_of_month
+                .is_none_or(|x| self.frequency == Frequency::Monthly && (1..=31).contains(&x))
+            && self.end_date.is_none_or(|x| x >= self.start_date)
+    }
+
+    /// The dates of the schedule, in order.
+    pub fn occurrences(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        let count = self
+            .count
+            .map_or(usize::MAX, |x| usize::try_from(x).unwrap_or(usize::MAX));
+        (0u32..)
+            .map_while(|n| self.nth_date(n))
+            .filter(|x| *x >= self.start_date)
+            .take_while(|x| self.end_date.is_none_or(|end| *x <= end))
+            .take(count)
+    }
+
+    fn nth_date(&self, n: u32) -> Option<NaiveDate> {
+        let step = n.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => self.start_date.checked_add_days(Days::new(step.into())),
+            Frequency::Weekly => self
+                .start_date
+                .checked_add_days(Days::new(u64::from(step) * 7)),
+            Frequency::Monthly => {
+                let month = self
+                    .start_date
+                    .with_day(1)?
+                    .checked_add_months(Months::new(step))?;
+                let last_day = month.checked_add_months(Months::new(1))?.pred_opt()?.day();
+                let day = self.day_of_month.unwrap_or(self.start_date.day());
+                month.with_day(day.min(last_day))
+            }
+        }
+    }
+
+    /// The transaction of the schedule with the given id on one of its dates.
+    #[must_use]
+    pub fn transaction(&self, id: Uuid, date: NaiveDate) -> Transaction {
+        Transaction {
+            date,
+            amount: self.amount,
+            memo: self.memo.clone(),
+            account_id: self.account_id,
+            category: self.category.clone(),
+            schedule_id: Some(id),
+            ..Default::default()
+        }
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Schedule {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            account_id: parse_optional_column(row, Schedule::ACCOUNT_ID_COL_NAME)?,
+            amount: parse_column(row, Schedule::AMOUNT_COL_NAME)?,
+            memo: row.try_get(Schedule::MEMO_COL_NAME)?,
+            category: row.try_get(Schedule::CATEGORY_COL_NAME)?,
+            frequency: parse_column(row, Schedule::FREQUENCY_COL_NAME)?,
+            interval: row.try_get(Schedule::INTERVAL_COL_NAME)?,
+            day_of_month: row.try_get(Schedule::DAY_OF_MONTH_COL_NAME)?,
+            start_date: parse_column(row, Schedule::START_DATE_COL_NAME)?,
+            end_date: parse_optional_column(row, Schedule::END_DATE_COL_NAME)?,
+            count: row.try_get(Schedule::COUNT_COL_NAME)?,
+        })
+    }
+}
+
+/// The amount planned to be spent in a category between two dates, both
+/// included. With rollover, whatever is left unspent is added to the next
+/// budget of the category.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Budget {
+    pub(crate) category: String,
+    pub(crate) start_date: NaiveDate,
+    pub(crate) end_date: NaiveDate,
+    pub(crate) amount: Decimal,
+    #[serde(default)]
+    pub(crate) rollover: bool,
+}
+
+impl Budget {
+    const CATEGORY_COL_NAME: &'static str = "category";
+    const START_DATE_COL_NAME: &'static str = "start_date";
+    const END_DATE_COL_NAME: &'static str = "end_date";
+    const AMOUNT_COL_NAME: &'static str = "amount";
+    const ROLLOVER_COL_NAME: &'static str = "rollover";
+
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        !self.category.is_empty() && self.start_date <= self.end_date && self.amount >= dec!(0)
+    }
+
+    #[must_use]
+    pub fn overlaps(&self, other: &Budget) -> bool {
+        self.category == other.category
+            && self.start_date <= other.end_date
+            && other.start_date <= self.end_date
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Budget {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            category: row.try_get(Budget::CATEGORY_COL_NAME)?,
+            start_date: parse_column(row, Budget::START_DATE_COL_NAME)?,
+            end_date: parse_column(row, Budget::END_DATE_COL_NAME)?,
+            amount: parse_column(row, Budget::AMOUNT_COL_NAME)?,
+            rollover: row.try_get(Budget::ROLLOVER_COL_NAME)?,
+        })
+    }
+}
+
+/// A budget next to what was actually spent. The planned amount includes
+/// what was carried over from the previous budget of the category, and the
+/// percentage used is missing when nothing was planned.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct BudgetLine {
+    pub(crate) budget_id: Uuid,
+    pub(crate) category: String,
+    pub(crate) start_date: NaiveDate,
+    pub(crate) end_date: NaiveDate,
+    pub(crate) carried_over: Decimal,
+    pub(crate) planned: Decimal,
+    pub(crate) actual: Decimal,
+    pub(crate) remaining: Decimal,
+    pub(crate) percent_used: Option<Decimal>,
+}
+
+/// The change of an amount between two periods. The percentage is relative
+/// to the magnitude of the previous amount and is missing when the previous
+/// amount is zero.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Delta {
+    pub(crate) absolute: Decimal,
+    pub(crate) percent: Option<Decimal>,
+}
+
+impl Delta {
+    #[must_use]
+    pub fn between(previous: Decimal, current: Decimal) -> Delta {
+        let absolute = current - previous;
+        let percent = (previous != dec!(0)).then(|| {
+            (absolute / previous.abs() * dec!(100))
+                .round_dp_with_strategy(2, RoundingStrategy::MidpointNearestEven)
+        });
+        Delta { absolute, percent }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Comparison {
+    pub(crate) current: Report,
+    pub(crate) previous: Report,
+    pub(crate) gross_revenue: Delta,
+    pub(crate) expenses: Delta,
+    pub(crate) net_revenue: Delta,
+}
+
+impl Comparison {
+    #[must_use]
+    pub fn between(previous: Report, current: Report) -> Comparison {
+        Comparison {
+            gross_revenue: Delta::between(previous.gross_revenue, current.gross_revenue),
+            expenses: Delta::between(previous.expenses, current.expenses),
+            net_revenue: Delta::between(previous.net_revenue, current.net_revenue),
+            current,
+            previous,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct CategoryComparison {
+    pub(crate) category: Option<String>,
+    pub(crate) comparison: Comparison,
+}
+
+/// A period compared with a previous one, in total and per category.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ComparisonReport {
+    pub(crate) total: Comparison,
+    pub(crate) categories: Vec<CategoryComparison>,
+}
+
+/// Maps the normalized memo of transactions to the name of their payee.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct PayeeAlias {
+    pub(crate) memo: String,
+    pub(crate) payee: String,
+}
+
+impl PayeeAlias {
+    const MEMO_COL_NAME: &'static str = "memo";
+    const PAYEE_COL_NAME: &'static str = "payee";
+}
+
+impl FromRow<'_, SqliteRow> for PayeeAlias {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            memo: row.try_get(PayeeAlias::MEMO_COL_NAME)?,
+            payee: row.try_get(PayeeAlias::PAYEE_COL_NAME)?,
+        })
+    }
+}
+
+/// A payee, derived from the memos of transactions.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Payee {
+    pub(crate) name: String,
+    pub(crate) transactions: usize,
+}
+
+impl Payee {
+    /// Case folds the memo and drops the words that vary between transactions
+    /// of the same payee: card numbers, dates and reference codes.
+    #[must_use]
+    pub fn normalize_memo(memo: &str) -> String {
+        let words: Vec<_> = memo
+            .split_whitespace()
+            .map(|word| {
+                word.trim_matches(|c: char| !c.is_alphanumeric() && c != '#' && c != '*')
+                    .to_lowercase()
+            })
+            .filter(|word| !word.is_empty() && !Payee::is_varying_word(word))
+            .map(|word| word.trim_matches(|c| c == '#' || c == '*').to_owned())
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        if words.is_empty() {
+            memo.trim().to_lowercase()
+        } else {
+            words.join(" ")
+        }
+    }
+
+    /// Reference and card numbers are runs of 6 digits or more. Masked and
+    /// store numbers are digits after `x`, `#` or `*`, e.g. `xxxx4532` or
+    /// `#0042`. Dates are made of digits and separators, e.g. `08/20`, and
+    /// reference codes have 6 characters or more, at least as many digits as
+    /// letters, e.g. `a1b2c3`. Street numbers, years and names such as
+    /// `7eleven` are kept.
+    fn is_varying_word(word: &str) -> bool {
+        let digits = word.chars().filter(char::is_ascii_digit).count();
+        let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+        let is_number = digits >= 6 && digits == word.len();
+        let is_masked = word
+            .trim_start_matches(['x', '#', '*'])
+            .chars()
+            .all(|c| c.is_ascii_digit())
+            && digits > 0
+            && digits < word.len();
+        let is_date = digits > 0
+            && word.chars().any(|c| matches!(c, '/' | '-' | '.'))
+            && word
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '/' | '-' | '.'));
+        let is_code = word.len() >= 6 && letters > 0 && digits >= letters;
+
+        is_number || is_masked || is_date || is_code
+    }
+}
+
+/// The total income or expenses of a payee.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct PayeeTotal {
+    pub(crate) payee: String,
+    pub(crate) total: Decimal,
+}
+
+/// Payees ranked by their total income and by their total expenses.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct PayeeRanking {
+    pub(crate) income: Vec<PayeeTotal>,
+    pub(crate) expenses: Vec<PayeeTotal>,
+}
+
+/// The estimated tax of a quarter. The taxable income is the gross revenue
+/// minus the expenses of deductible categories. The tax is computed on the
+/// cumulative taxable income of the year so far, and the tax of a quarter is
+/// what that adds to the instalments of the previous quarters, never below
+/// zero.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct QuarterlyTax {
+    pub(crate) quarter: u32,
+    pub(crate) start_date: NaiveDate,
+    pub(crate) end_date: NaiveDate,
+    pub(crate) report: Report,
+    pub(crate) deductible_expenses: Decimal,
+    pub(crate) taxable_income: Decimal,
+    pub(crate) cumulative_taxable_income: Decimal,
+    pub(crate) tax: Decimal,
+    pub(crate) cumulative_tax: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct TaxReport {
+    pub(crate) year: i32,
+    pub(crate) deductible_categories: Vec<String>,
+    pub(crate) quarters: Vec<QuarterlyTax>,
+}
+
+/// The tax of the transactions of a tax code in a period. Output tax is
+/// collected on income and input tax is paid on expenses, both reported as
+/// positive amounts.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct VatLine {
+    pub(crate) tax_code: String,
+    pub(crate) sales_net: Decimal,
+    pub(crate) output_tax: Decimal,
+    pub(crate) purchases_net: Decimal,
+    pub(crate) input_tax: Decimal,
+}
+
+/// The VAT return of a period. The net tax is what is owed, or refunded when
+/// negative. Transactions whose tax code has no rate on their date are left
+/// out and listed as unrated.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct VatReturn {
+    pub(crate) from: NaiveDate,
+    pub(crate) to: NaiveDate,
+    pub(crate) lines: Vec<VatLine>,
+    pub(crate) output_tax: Decimal,
+    pub(crate) input_tax: Decimal,
+    pub(crate) net_tax: Decimal,
+    pub(crate) unrated: Vec<Uuid>,
+}
+
+/// A trip made for business, optionally linked to the income transaction it
+/// earned.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Trip {
+    pub(crate) date: NaiveDate,
+    pub(crate) start_odometer: Option<Decimal>,
+    pub(crate) end_odometer: Option<Decimal>,
+    pub(crate) distance: Decimal,
+    pub(crate) purpose: String,
+    pub(crate) transaction_id: Option<Uuid>,
+}
+
+impl Trip {
+    const DATE_COL_NAME: &'static str = "date";
+    const START_ODOMETER_COL_NAME: &'static str = "start_odometer";
+    const END_ODOMETER_COL_NAME: &'static str = "end_odometer";
+    const DISTANCE_COL_NAME: &'static str = "distance";
+    const PURPOSE_COL_NAME: &'static str = "purpose";
+    const TRANSACTION_ID_COL_NAME: &'static str = "transaction_id";
+}
+
+impl FromRow<'_, SqliteRow> for Trip {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            date: parse_column(row, Trip::DATE_COL_NAME)?,
+            start_odometer: parse_optional_column(row, Trip::START_ODOMETER_COL_NAME)?,
+            end_odometer: parse_optional_column(row, Trip::END_ODOMETER_COL_NAME)?,
+            distance: parse_column(row, Trip::DISTANCE_COL_NAME)?,
+            purpose: row.try_get(Trip::PURPOSE_COL_NAME)?,
+            transaction_id: parse_optional_column(row, Trip::TRANSACTION_ID_COL_NAME)?,
+        })
+    }
+}
+
+/// A trip as entered, in JSON or as a `date, start odometer, end odometer,
+/// distance, purpose, transaction id` CSV row. The distance can be left out
+/// when both odometer readings are given.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct TripEntry {
+    date: NaiveDate,
+    #[serde(default)]
+    start_odometer: Option<Decimal>,
+    #[serde(default)]
+    end_odometer: Option<Decimal>,
+    #[serde(default)]
+    distance: Option<Decimal>,
+    purpose: String,
+    #[serde(default)]
+    transaction_id: Option<Uuid>,
+}
+
+impl TryFrom<TripEntry> for Trip {
+    type Error = error::Error;
+
+    fn try_from(value: TripEntry) -> Result<Self, Self::Error> {
+        let travelled = match (value.start_odometer, value.end_odometer) {
+            (Some(start), Some(end)) if start <= end => Some(end - start),
+            (None, None) => None,
+            _ => return Err(error::Error::InvalidTrip),
+        };
+        let distance = match (value.distance, travelled) {
+            (Some(distance), Some(travelled)) if distance != travelled => {
+                return Err(error::Error::InvalidTrip)
+            }
+            (Some(distance), _) | (None, Some(distance)) => distance,
+            (None, None) => return Err(error::Error::InvalidTrip),
+        };
+        if distance <= dec!(0) {
+            return Err(error::Error::InvalidTrip);
+        }
+
+        Ok(Trip {
+            date: value.date,
+            start_odometer: value.start_odometer,
+            end_odometer: value.end_odometer,
+            distance,
+            purpose: value.purpose,
+            transaction_id: value.transaction_id,
+        })
+    }
+}
+
+/// The distance travelled in a year and the amount it allows to deduct.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct MileageYear {
+    pub(crate) year: i32,
+    pub(crate) distance: Decimal,
+    pub(crate) rate: Decimal,
+    pub(crate) deductible: Decimal,
+}
+
+/// The deductible mileage of a period next to the actual expenses of the
+/// vehicle, in total and per category or memo. Trips of years without a rate
+/// are left out of the deductible amount and listed as unrated.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct MileageReport {
+    pub(crate) trips: usize,
+    pub(crate) distance: Decimal,
+    pub(crate) deductible: Decimal,
+    pub(crate) years: Vec<MileageYear>,
+    pub(crate) unrated: Vec<Uuid>,
+    pub(crate) vehicle_expenses: Report,
+    pub(crate) expenses: Vec<CategoryReport>,
+}
+
+/// A file, such as a receipt, uploaded against a transaction. Its content is
+/// stored once per SHA-256 hash, however many times it is uploaded.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Attachment {
+    pub(crate) transaction_id: Uuid,
+    pub(crate) file_name: String,
+    pub(crate) mime_type: String,
+    pub(crate) size: i64,
+    pub(crate) hash: String,
+    pub(crate) uploaded_at: DateTime<Utc>,
+}
+
+impl Attachment {
+    const TRANSACTION_ID_COL_NAME: &'static str = "transaction_id";
+    const FILE_NAME_COL_NAME: &'static str = "file_name";
+    const MIME_TYPE_COL_NAME: &'static str = "mime_type";
+    const SIZE_COL_NAME: &'static str = "size";
+    const HASH_COL_NAME: &'static str = "hash";
+    const UPLOADED_AT_COL_NAME: &'static str = "uploaded_at";
+
+    /// The type of an image or PDF, recognized from its first bytes rather
+    /// than trusted from the upload.
+    #[must_use]
+    pub fn sniff_mime_type(content: &[u8]) -> Option<&'static str> {
+        const SIGNATURES: [(&[u8], &str); 5] = [
+            (b"%PDF-", "application/pdf"),
+            (b"\x89PNG\r\n\x1a\n", "image/png"),
+            (b"\xff\xd8\xff", "image/jpeg"),
+            (b"GIF87a", "image/gif"),
+            (b"GIF89a", "image/gif"),
+        ];
+        if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+            return Some("image/webp");
+        }
+        SIGNATURES
+            .iter()
+            .find(|(signature, _)| content.starts_with(signature))
+            .map(|(_, mime_type)| *mime_type)
+    }
+
+    #[must_use]
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// The header value prompting a download under the original file name.
+    #[must_use]
+    pub fn content_disposition(&self) -> String {
+        format!("attachment; filename=\"{}\"", self.file_name)
+    }
+
+    /// The last component of an uploaded file name, without the characters
+    /// that cannot appear in a `Content-Disposition` header.
+    #[must_use]
+    pub fn sanitize_file_name(file_name: &str) -> String {
+        let file_name: String = file_name
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .filter(|x| !x.is_control() && *x != '"')
+            .collect();
+        if file_name.is_empty() {
+            "attachment".to_owned()
+        } else {
+            file_name
+        }
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Attachment {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            transaction_id: parse_column(row, Attachment::TRANSACTION_ID_COL_NAME)?,
+            file_name: row.try_get(Attachment::FILE_NAME_COL_NAME)?,
+            mime_type: row.try_get(Attachment::MIME_TYPE_COL_NAME)?,
+            size: row.try_get(Attachment::SIZE_COL_NAME)?,
+            hash: row.try_get(Attachment::HASH_COL_NAME)?,
+            uploaded_at: parse_column(row, Attachment::UPLOADED_AT_COL_NAME)?,
+        })
+    }
+}
+
+/// Expenses larger than the threshold, in magnitude, without any attachment.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MissingReceipts {
+    pub(crate) threshold: Decimal,
+    pub(crate) total: Decimal,
+    pub(crate) transactions: Vec<WithId<Transaction>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Customer {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) email: Option<String>,
+}
+
+impl Customer {
+    const NAME_COL_NAME: &'static str = "name";
+    const EMAIL_COL_NAME: &'static str = "email";
+}
+
+impl FromRow<'_, SqliteRow> for Customer {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            name: row.try_get(Customer::NAME_COL_NAME)?,
+            email: row.try_get(Customer::EMAIL_COL_NAME)?,
+        })
+    }
+}
+
+/// Only draft and sent are stored; an invoice is paid once its payments cover
+/// its total, and overdue when it is still owed after its due date.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    #[default]
+    Draft,
+    Sent,
+    Paid,
+    Overdue,
+}
+
+impl InvoiceStatus {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Sent => "sent",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Overdue => "overdue",
+        }
+    }
+}
+
+impl FromStr for InvoiceStatus {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(InvoiceStatus::Draft),
+            "sent" => Ok(InvoiceStatus::Sent),
+            "paid" => Ok(InvoiceStatus::Paid),
+            "overdue" => Ok(InvoiceStatus::Overdue),
+            _ => Err(error::Error::InvalidInvoiceStatus(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct InvoiceLine {
+    pub(crate) description: String,
+    pub(crate) quantity: Decimal,
+    pub(crate) unit_price: Decimal,
+}
+
+impl InvoiceLine {
+    const INVOICE_ID_COL_NAME: &'static str = "invoice_id";
+    const DESCRIPTION_COL_NAME: &'static str = "description";
+    const QUANTITY_COL_NAME: &'static str = "quantity";
+    const UNIT_PRICE_COL_NAME: &'static str = "unit_price";
+
+    /// The amount of the line, rounded half to even to 2 decimal places.
+    #[must_use]
+    pub fn amount(&self) -> Decimal {
+        (self.quantity * self.unit_price)
+            .round_dp_with_strategy(2, RoundingStrategy::MidpointNearestEven)
+    }
+}
+
+/// An [`InvoiceLine`] together with the id of its invoice.
+#[derive(Debug)]
+pub struct LineOfInvoice {
+    pub(crate) invoice_id: Uuid,
+    pub(crate) line: InvoiceLine,
+}
+
+impl FromRow<'_, SqliteRow> for LineOfInvoice {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            invoice_id: parse_column(row, InvoiceLine::INVOICE_ID_COL_NAME)?,
+            line: InvoiceLine {
+                description: row.try_get(InvoiceLine::DESCRIPTION_COL_NAME)?,
+                quantity: parse_column(row, InvoiceLine::QUANTITY_COL_NAME)?,
+                unit_price: parse_column(row, InvoiceLine::UNIT_PRICE_COL_NAME)?,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Invoice {
+    pub(crate) customer_id: Uuid,
+    pub(crate) number: String,
+    pub(crate) issue_date: NaiveDate,
+    pub(crate) due_date: NaiveDate,
+    #[serde(default)]
+    pub(crate) status: InvoiceStatus,
+    pub(crate) lines: Vec<InvoiceLine>,
+}
+
+impl Invoice {
+    const CUSTOMER_ID_COL_NAME: &'static str = "customer_id";
+    const NUMBER_COL_NAME: &'static str = "number";
+    const ISSUE_DATE_COL_NAME: &'static str = "issue_date";
+    const DUE_DATE_COL_NAME: &'static str = "due_date";
+    const STATUS_COL_NAME: &'static str = "status";
+
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        !self.number.is_empty()
+            && self.issue_date <= self.due_date
+            && !self.lines.is_empty()
+            && self
+                .lines
+                .iter()
+                .all(|x| x.quantity > dec!(0) && x.unit_price >= dec!(0))
+    }
+
+    #[must_use]
+    pub fn total(&self) -> Decimal {
+        self.lines.iter().map(InvoiceLine::amount).sum()
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Invoice {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            customer_id: parse_column(row, Invoice::CUSTOMER_ID_COL_NAME)?,
+            number: row.try_get(Invoice::NUMBER_COL_NAME)?,
+            issue_date: parse_column(row, Invoice::ISSUE_DATE_COL_NAME)?,
+            due_date: parse_column(row, Invoice::DUE_DATE_COL_NAME)?,
+            status: parse_column(row, Invoice::STATUS_COL_NAME)?,
+            lines: vec![],
+        })
+    }
+}
+
+/// The part of a transaction that settles an invoice or a bill.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Payment {
+    pub(crate) transaction_id: Uuid,
+    pub(crate) amount: Decimal,
+}
+
+impl Payment {
+    const TRANSACTION_ID_COL_NAME: &'static str = "transaction_id";
+    const AMOUNT_COL_NAME: &'static str = "amount";
+}
+
+impl FromRow<'_, SqliteRow> for Payment {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            transaction_id: parse_column(row, Payment::TRANSACTION_ID_COL_NAME)?,
+            amount: parse_column(row, Payment::AMOUNT_COL_NAME)?,
+        })
+    }
+}
+
+/// A [`Payment`] together with the id of the invoice it settles.
+#[derive(Debug)]
+pub struct PaymentOfInvoice {
+    pub(crate) invoice_id: Uuid,
+    pub(crate) payment: WithId<Payment>,
+}
+
+impl FromRow<'_, SqliteRow> for PaymentOfInvoice {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            invoice_id: parse_column(row, InvoiceLine::INVOICE_ID_COL_NAME)?,
+            payment: FromRow::from_row(row)?,
+        })
+    }
+}
+
+/// An invoice along with its payments and what is still owed on a given day.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InvoiceSummary {
+    pub(crate) invoice: WithId<Invoice>,
+    pub(crate) total: Decimal,
+    pub(crate) paid: Decimal,
+    pub(crate) outstanding: Decimal,
+    pub(crate) status: InvoiceStatus,
+    pub(crate) payments: Vec<WithId<Payment>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Vendor {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) email: Option<String>,
+}
+
+impl Vendor {
+    const NAME_COL_NAME: &'static str = "name";
+    const EMAIL_COL_NAME: &'static str = "email";
+}
+
+impl FromRow<'_, SqliteRow> for Vendor {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            name: row.try_get(Vendor::NAME_COL_NAME)?,
+            email: row.try_get(Vendor::EMAIL_COL_NAME)?,
+        })
+    }
+}
+
+/// A bill received from a vendor, identified by the vendor's own number.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Bill {
+    pub(crate) vendor_id: Uuid,
+    pub(crate) number: String,
+    pub(crate) issue_date: NaiveDate,
+    pub(crate) due_date: NaiveDate,
+    pub(crate) amount: Decimal,
+    #[serde(default)]
+    pub(crate) memo: String,
+}
+
+impl Bill {
+    const VENDOR_ID_COL_NAME: &'static str = "vendor_id";
+    const NUMBER_COL_NAME: &'static str = "number";
+    const ISSUE_DATE_COL_NAME: &'static str = "issue_date";
+    const DUE_DATE_COL_NAME: &'static str = "due_date";
+    const AMOUNT_COL_NAME: &'static str = "amount";
+    const MEMO_COL_NAME: &'static str = "memo";
+    const BILL_ID_COL_NAME: &'static str = "bill_id";
+
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        !self.number.is_empty() && self.issue_date <= self.due_date && self.amount > dec!(0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Bill {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            vendor_id: parse_column(row, Bill::VENDOR_ID_COL_NAME)?,
+            number: row.try_get(Bill::NUMBER_COL_NAME)?,
+            issue_date: parse_column(row, Bill::ISSUE_DATE_COL_NAME)?,
+            due_date: parse_column(row, Bill::DUE_DATE_COL_NAME)?,
+            amount: parse_column(row, Bill::AMOUNT_COL_NAME)?,
+            memo: row.try_get(Bill::MEMO_COL_NAME)?,
+        })
+    }
+}
+
+/// A [`Payment`] together with the id of the bill it settles.
+#[derive(Debug)]
+pub struct PaymentOfBill {
+    pub(crate) bill_id: Uuid,
+    pub(crate) payment: WithId<Payment>,
+}
+
+impl FromRow<'_, SqliteRow> for PaymentOfBill {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            bill_id: parse_column(row, Bill::BILL_ID_COL_NAME)?,
+            payment: FromRow::from_row(row)?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum BillStatus {
+    Open,
+    Paid,
+    Overdue,
+}
+
+/// A bill along with its payments and what is still owed on a given day.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BillSummary {
+    pub(crate) bill: WithId<Bill>,
+    pub(crate) paid: Decimal,
+    pub(crate) outstanding: Decimal,
+    pub(crate) status: BillStatus,
+    pub(crate) payments: Vec<WithId<Payment>>,
+}
+
+/// Outstanding balances by the number of days they are past due, amounts not
+/// yet due counting as 0 days.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct AgingBuckets {
+    pub(crate) days_0_30: Decimal,
+    pub(crate) days_31_60: Decimal,
+    pub(crate) days_61_90: Decimal,
+    pub(crate) days_over_90: Decimal,
+    pub(crate) total: Decimal,
+}
+
+impl AgingBuckets {
+    pub fn add(&mut self, days_past_due: i64, amount: Decimal) {
+        let bucket = match days_past_due {
+            ..=30 => &mut self.days_0_30,
+            31..=60 => &mut self.days_31_60,
+            61..=90 => &mut self.days_61_90,
+            _ => &mut self.days_over_90,
+        };
+        *bucket += amount;
+        self.total += amount;
+    }
+}
+
+/// The aged balances owed by, or to, one customer or vendor.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct AgingLine {
+    pub(crate) id: Uuid,
+    pub(crate) name: String,
+    pub(crate) buckets: AgingBuckets,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct AgingReport {
+    pub(crate) as_of: NaiveDate,
+    pub(crate) lines: Vec<AgingLine>,
+    pub(crate) total: AgingBuckets,
+}
+
+/// Who makes the changes of a [`crate::query::SqliteStore`], and within
+/// which request, as recorded in the audit log.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AuditContext {
+    pub(crate) actor: String,
+    pub(crate) request_id: Option<String>,
+}
+
+impl AuditContext {
+    /// The actor of the changes made by the server itself, such as the
+    /// occurrences of schedules.
+    pub const SYSTEM_ACTOR: &'static str = "system";
+
+    #[must_use]
+    pub fn new(actor: String, request_id: Option<String>) -> Self {
+        Self { actor, request_id }
+    }
+}
+
+impl Default for AuditContext {
+    fn default() -> Self {
+        Self::new(AuditContext::SYSTEM_ACTOR.to_owned(), None)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl AuditAction {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(AuditAction::Create),
+            "update" => Ok(AuditAction::Update),
+            "delete" => Ok(AuditAction::Delete),
+            _ => Err(error::Error::InvalidAuditAction(s.to_owned())),
+        }
+    }
+}
+
+/// A change of an entity, with its state before and after as JSON. Entries
+/// are numbered in the order they were recorded.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct AuditEntry {
+    pub(crate) sequence: i64,
+    pub(crate) recorded_at: DateTime<Utc>,
+    pub(crate) actor: String,
+    pub(crate) request_id: Option<String>,
+    pub(crate) entity: String,
+    pub(crate) entity_id: String,
+    pub(crate) action: AuditAction,
+    pub(crate) before: Option<serde_json::Value>,
+    pub(crate) after: Option<serde_json::Value>,
+}
+
+impl AuditEntry {
+    const SEQUENCE_COL_NAME: &'static str = "sequence";
+    const RECORDED_AT_COL_NAME: &'static str = "recorded_at";
+    const ACTOR_COL_NAME: &'static str = "actor";
+    const REQUEST_ID_COL_NAME: &'static str = "request_id";
+    const ENTITY_COL_NAME: &'static str = "entity";
+    const ENTITY_ID_COL_NAME: &'static str = "entity_id";
+    const ACTION_COL_NAME: &'static str = "action";
+    const BEFORE_COL_NAME: &'static str = "before";
+    const AFTER_COL_NAME: &'static str = "after";
+}
+
+impl FromRow<'_, SqliteRow> for AuditEntry {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        let parse_json = |column: &str| -> Result<Option<serde_json::Value>, sqlx::Error> {
+            row.try_get::<Option<String>, _>(column)?
+                .map(|x| {
+                    serde_json::from_str(&x).map_err(|x| sqlx::Error::ColumnDecode {
+                        index: column.to_owned(),
+                        source: Box::new(x),
+                    })
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            sequence: row.try_get(AuditEntry::SEQUENCE_COL_NAME)?,
+            recorded_at: parse_column(row, AuditEntry::RECORDED_AT_COL_NAME)?,
+            actor: row.try_get(AuditEntry::ACTOR_COL_NAME)?,
+            request_id: row.try_get(AuditEntry::REQUEST_ID_COL_NAME)?,
+            entity: row.try_get(AuditEntry::ENTITY_COL_NAME)?,
+            entity_id: row.try_get(AuditEntry::ENTITY_ID_COL_NAME)?,
+            action: parse_column(row, AuditEntry::ACTION_COL_NAME)?,
+            before: parse_json(AuditEntry::BEFORE_COL_NAME)?,
+            after: parse_json(AuditEntry::AFTER_COL_NAME)?,
+        })
+    }
+}
+
+/// Criteria of the audit log entries to list, all optional. The bounds of
+/// the recording time are included.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct AuditFilter {
+    pub(crate) entity: Option<String>,
+    pub(crate) entity_id: Option<String>,
+    pub(crate) actor: Option<String>,
+    pub(crate) request_id: Option<String>,
+    pub(crate) from: Option<DateTime<Utc>>,
+    pub(crate) to: Option<DateTime<Utc>>,
+}
+
+/// A link of the hash chain over the committed uploads: the hash of the
+/// transactions of an upload chained to the hash of the previous link.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Batch {
+    pub(crate) sequence: i64,
+    pub(crate) report_id: Uuid,
+    pub(crate) previous_hash: Option<String>,
+    pub(crate) hash: String,
+    pub(crate) recorded_at: DateTime<Utc>,
+}
+
+impl Batch {
+    const SEQUENCE_COL_NAME: &'static str = "sequence";
+    const REPORT_ID_COL_NAME: &'static str = "report_id";
+    const PREVIOUS_HASH_COL_NAME: &'static str = "previous_hash";
+    const HASH_COL_NAME: &'static str = "hash";
+    const RECORDED_AT_COL_NAME: &'static str = "recorded_at";
+}
+
+impl FromRow<'_, SqliteRow> for Batch {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            sequence: row.try_get(Batch::SEQUENCE_COL_NAME)?,
+            report_id: parse_column(row, Batch::REPORT_ID_COL_NAME)?,
+            previous_hash: row.try_get(Batch::PREVIOUS_HASH_COL_NAME)?,
+            hash: row.try_get(Batch::HASH_COL_NAME)?,
+            recorded_at: parse_column(row, Batch::RECORDED_AT_COL_NAME)?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainBreakReason {
+    /// The link does not point to the hash of the link before it.
+    PreviousHashMismatch,
+    /// The transactions of the upload no longer hash to the stored hash.
+    HashMismatch,
+}
+
+/// The first link of the chain that does not verify, with the hash it
+/// should have and the hash it has.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ChainBreak {
+    pub(crate) sequence: i64,
+    pub(crate) report_id: Uuid,
+    pub(crate) reason: ChainBreakReason,
+    pub(crate) expected: Option<String>,
+    pub(crate) actual: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ChainVerification {
+    /// Links in the chain.
+    pub(crate) batches: usize,
+    /// Links verified before the first broken one, if any.
+    pub(crate) verified: usize,
+    pub(crate) broken: Option<ChainBreak>,
+}
+
+impl ChainVerification {
+    #[must_use]
+    pub fn is_intact(&self) -> bool {
+        self.broken.is_none()
+    }
+}
+
+/// A stored report of an upload that differs from the report computed from
+/// its transactions. The stored report is missing when transactions refer to
+/// an upload without one.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ReportMismatch {
+    pub(crate) report_id: Uuid,
+    pub(crate) stored: Option<Report>,
+    pub(crate) computed: Report,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ReportCheck {
+    /// Uploads checked, with or without a stored report.
+    pub(crate) reports: usize,
+    pub(crate) mismatches: Vec<ReportMismatch>,
+    /// Whether the mismatched reports were repaired and none differs anymore.
+    pub(crate) repaired: bool,
+}
+
+impl ReportCheck {
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// A version of a transaction, valid from a time, unknown for transactions
+/// imported before versions were kept, until a time, or until now for the
+/// current version. A deleted transaction keeps its last version, valid until
+/// its deletion.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TransactionVersion {
+    pub(crate) transaction_id: Uuid,
+    pub(crate) valid_from: Option<DateTime<Utc>>,
+    pub(crate) valid_to: Option<DateTime<Utc>>,
+    pub(crate) transaction: Transaction,
+}
+
+impl TransactionVersion {
+    const TRANSACTION_ID_COL_NAME: &'static str = "transaction_id";
+    const VALID_FROM_COL_NAME: &'static str = "valid_from";
+    const VALID_TO_COL_NAME: &'static str = "valid_to";
+    const DATA_COL_NAME: &'static str = "data";
+}
+
+/// A superseded version, whose transaction is kept as JSON.
+impl FromRow<'_, SqliteRow> for TransactionVersion {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        let data: String = row.try_get(TransactionVersion::DATA_COL_NAME)?;
+        let transaction = serde_json::from_str(&data).map_err(|x| sqlx::Error::ColumnDecode {
+            index: TransactionVersion::DATA_COL_NAME.to_owned(),
+            source: Box::new(x),
+        })?;
+
+        Ok(Self {
+            transaction_id: parse_column(row, TransactionVersion::TRANSACTION_ID_COL_NAME)?,
+            valid_from: parse_optional_column(row, TransactionVersion::VALID_FROM_COL_NAME)?,
+            valid_to: parse_optional_column(row, TransactionVersion::VALID_TO_COL_NAME)?,
+            transaction,
+        })
+    }
+}
+
+/// A change of a transaction, as recorded in the event store. The events are
+/// replayed to rebuild the read models, such as the reports of the uploads.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type")]
+pub enum Event {
+    TransactionImported {
+        transaction_id: Uuid,
+        transaction: Transaction,
+    },
+    TransactionEdited {
+        transaction_id: Uuid,
+        transaction: Transaction,
+    },
+    CategoryAssigned {
+        transaction_id: Uuid,
+        category: Option<String>,
+    },
+    TransactionDeleted {
+        transaction_id: Uuid,
+    },
+}
+
+impl Event {
+    /// The event of an edit, which only assigns a category when nothing else
+    /// changed.
+    #[must_use]
+    pub fn edited(transaction_id: Uuid, before: &Transaction, after: &Transaction) -> Self {
+        let category_only = Transaction {
+            category: after.category.clone(),
+            ..before.clone()
+        };
+        if category_only == *after {
+            Event::CategoryAssigned {
+                transaction_id,
+                category: after.category.clone(),
+            }
+        } else {
+            Event::TransactionEdited {
+                transaction_id,
+                transaction: after.clone(),
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn transaction_id(&self) -> Uuid {
+        match self {
+            Event::TransactionImported { transaction_id, .. }
+            | Event::TransactionEdited { transaction_id, .. }
+            | Event::CategoryAssigned { transaction_id, .. }
+            | Event::TransactionDeleted { transaction_id } => *transaction_id,
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::TransactionImported { .. } => "TransactionImported",
+            Event::TransactionEdited { .. } => "TransactionEdited",
+            Event::CategoryAssigned { .. } => "CategoryAssigned",
+            Event::TransactionDeleted { .. } => "TransactionDeleted",
+        }
+    }
+}
+
+/// An event along with its place in the store and the upload of its
+/// transaction.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct EventRecord {
+    pub(crate) sequence: i64,
+    pub(crate) recorded_at: DateTime<Utc>,
+    pub(crate) report_id: Option<Uuid>,
+    pub(crate) event: Event,
+}
+
+impl EventRecord {
+    const SEQUENCE_COL_NAME: &'static str = "sequence";
+    const RECORDED_AT_COL_NAME: &'static str = "recorded_at";
+    const REPORT_ID_COL_NAME: &'static str = "report_id";
+    const DATA_COL_NAME: &'static str = "data";
+}
+
+impl FromRow<'_, SqliteRow> for EventRecord {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        let data: String = row.try_get(EventRecord::DATA_COL_NAME)?;
+        let event = serde_json::from_str(&data).map_err(|x| sqlx::Error::ColumnDecode {
+            index: EventRecord::DATA_COL_NAME.to_owned(),
+            source: Box::new(x),
+        })?;
+
+        Ok(Self {
+            sequence: row.try_get(EventRecord::SEQUENCE_COL_NAME)?,
+            recorded_at: parse_column(row, EventRecord::RECORDED_AT_COL_NAME)?,
+            report_id: parse_optional_column(row, EventRecord::REPORT_ID_COL_NAME)?,
+            event,
+        })
+    }
+}
+
+/// What a projection did when it caught up with the events recorded after
+/// its cursor.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ProjectionRun {
+    pub(crate) projection: String,
+    /// The cursor before the run.
+    pub(crate) from: i64,
+    /// The cursor after the run, the last event applied.
+    pub(crate) to: i64,
+    pub(crate) events: usize,
+    /// Read models rewritten because they changed.
+    pub(crate) reports: usize,
+}
+
+/// A change of a transaction or a report, at its place in the change feed.
+/// The data is the state after the change, none for a deletion.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct FeedChange {
+    pub(crate) cursor: i64,
+    pub(crate) recorded_at: DateTime<Utc>,
+    pub(crate) entity: String,
+    pub(crate) entity_id: String,
+    pub(crate) action: AuditAction,
+    pub(crate) data: Option<serde_json::Value>,
+}
+
+impl From<AuditEntry> for FeedChange {
+    fn from(entry: AuditEntry) -> Self {
+        Self {
+            cursor: entry.sequence,
+            recorded_at: entry.recorded_at,
+            entity: entry.entity,
+            entity_id: entry.entity_id,
+            action: entry.action,
+            data: entry.after,
+        }
+    }
+}
+
+/// A page of the change feed, in commit order. The cursor is the one to ask
+/// the next page from: the last change of the page, or the cursor asked from
+/// if there was none.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ChangeFeed {
+    pub(crate) changes: Vec<FeedChange>,
+    pub(crate) cursor: i64,
+    /// Whether more changes follow the page.
+    pub(crate) has_more: bool,
+}
+
+/// A transaction of an account along with the balance of the account right
+/// after it.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct StatementLine {
+    pub(crate) transaction: WithId<Transaction>,
+    pub(crate) balance: Decimal,
+}
+
+/// Parameters of a balance forecast: how many days ahead to project, how
+/// many days of history to average, and the balance to stay above.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ForecastOptions {
+    #[serde(default = "ForecastOptions::default_days")]
+    pub(crate) days: u32,
+    #[serde(default = "ForecastOptions::default_lookback_days")]
+    pub(crate) lookback_days: u32,
+    #[serde(default)]
+    pub(crate) threshold: Decimal,
+}
+
+impl ForecastOptions {
+    fn default_days() -> u32 {
+        30
+    }
+
+    fn default_lookback_days() -> u32 {
+        90
+    }
+}
+
+impl Default for ForecastOptions {
+    fn default() -> Self {
+        Self {
+            days: ForecastOptions::default_days(),
+            lookback_days: ForecastOptions::default_lookback_days(),
+            threshold: dec!(0),
+        }
+    }
+}
+
+/// The average net amount per day of a category over the lookback.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct CategoryAverage {
+    pub(crate) category: Option<String>,
+    pub(crate) daily_amount: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ForecastPoint {
+    pub(crate) date: NaiveDate,
+    pub(crate) balance: Decimal,
+}
+
+/// Projected daily balances of an account, starting from its current
+/// balance, and the first date the balance falls below the threshold.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Forecast {
+    pub(crate) account_id: Uuid,
+    pub(crate) threshold: Decimal,
+    pub(crate) averages: Vec<CategoryAverage>,
+    pub(crate) series: Vec<ForecastPoint>,
+    pub(crate) below_threshold_on: Option<NaiveDate>,
+}
+
+/// A change of the date through which the books are closed, kept as an audit
+/// trail. Nothing dated on or before that date can be imported or edited.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct PeriodClosing {
+    pub(crate) closed_through: Option<NaiveDate>,
+    pub(crate) reason: Option<String>,
+    pub(crate) recorded_at: DateTime<Utc>,
+}
+
+impl PeriodClosing {
+    const CLOSED_THROUGH_COL_NAME: &'static str = "closed_through";
+    const REASON_COL_NAME: &'static str = "reason";
+    const RECORDED_AT_COL_NAME: &'static str = "recorded_at";
+}
+
+impl FromRow<'_, SqliteRow> for PeriodClosing {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            closed_through: parse_optional_column(row, PeriodClosing::CLOSED_THROUGH_COL_NAME)?,
+            reason: row.try_get(PeriodClosing::REASON_COL_NAME)?,
+            recorded_at: parse_column(row, PeriodClosing::RECORDED_AT_COL_NAME)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    use crate::error;
+
+    use super::{
+        AgingBuckets, Attachment, Delta, ExchangeRate, ExchangeRates, Frequency, Payee, Report,
+        Schedule, Split, Transaction, TransactionFromCSV, TransactionKind, Trip, TripEntry,
+    };
+
+    #[test]
+    fn from_valid_csv_transaction() {
+        let transaction_from_csv = TransactionFromCSV {
+            date: NaiveDate::from_ymd_opt(2021, 7, 20).unwrap(),
+            income: "Income".to_string(),
+            amount: dec!(12.11),
+            memo: "first".to_string(),
+            category: None,
+        };
+        let expected_transaction = Transaction {
+            date: NaiveDate::from_ymd_opt(2021, 7, 20).unwrap(),
+            amount: dec!(12.11),
+            memo: "first".to_string(),
+            ..Default::default()
+        };
+
+        let transaction: Transaction = TryFrom::try_from(transaction_from_csv).unwrap();
+
+        assert_eq!(transaction, expected_transaction);
+    }
+
+    #[test]
+    fn from_invalid_csv_transaction() {
+        let transaction_from_csv = TransactionFromCSV {
+            date: NaiveDate::from_ymd_opt(2021, 7, 20).unwrap(),
+            income: "IncomeX".to_string(),
+            amount: dec!(12.11),
+            memo: "first".to_string(),
+            category: None,
+        };
+
+        let transaction: Result<Transaction, _> = TryFrom::try_from(transaction_from_csv);
+
+        assert!(matches!(transaction, Err(error::Error::InvalidCSVIncome)));
+    }
+
+    #[test]
+    fn add_report() {
+        let report_0 = Report {
+            gross_revenue: dec!(87.32),
+            expenses: dec!(12.13),
+            net_revenue: dec!(75.19),
+        };
+        let report_1 = Report {
+            gross_revenue: dec!(10.01),
+            expenses: dec!(2.05),
+            net_revenue: dec!(7.96),
+        };
+        let expected_report = Report {
+            gross_revenue: dec!(97.33),
+            expenses: dec!(14.18),
+            net_revenue: dec!(83.15),
+        };
+
+        let report = Report::add(&report_0, &report_1);
+
+        assert_eq!(report, expected_report);
+    }
+
+    #[test]
+    fn add_transaction() {
+        let transaction_0 = Transaction {
+            date: NaiveDate::from_ymd_opt(2015, 11, 1).unwrap(),
+            amount: dec!(87.12),
+            memo: "first".to_string(),
+            ..Default::default()
+        };
+        let transaction_1 = Transaction {
+            date: NaiveDate::from_ymd_opt(2016, 11, 1).unwrap(),
+            amount: dec!(-12.13),
+            memo: "second".to_string(),
+            ..Default::default()
+        };
+
+        let report = Report::new();
+        let report = Report::add_transaction(&report, &transaction_0);
+        let report = Report::add_transaction(&report, &transaction_1);
+
+        let expected_report = Report {
+            gross_revenue: dec!(87.12),
+            expenses: dec!(12.13),
+            net_revenue: dec!(74.99),
+        };
+
+        assert_eq!(report, expected_report);
+    }
+
+    #[test]
+    fn add_transfer_transaction() {
+        let transfer = Transaction {
+            date: NaiveDate::from_ymd_opt(2016, 11, 1).unwrap(),
+            amount: dec!(-500.00),
+            memo: "to savings".to_string(),
+            kind: TransactionKind::Transfer,
+            ..Default::default()
+        };
+
+        let report = Report::add_transaction(&Report::new(), &transfer);
+
+        assert_eq!(report, Report::new());
+    }
+
+    #[test]
+    fn split_sum_mismatch() {
+        let transaction = Transaction {
+            date: NaiveDate::from_ymd_opt(2021, 3, 4).unwrap(),
+            amount: dec!(-60.00),
+            memo: "hardware store".to_string(),
+            splits: vec![
+                Split {
+                    amount: dec!(-30.00),
+                    category: Some("Supplies".to_string()),
+                    memo: "nails".to_string(),
+                },
+                Split {
+                    amount: dec!(-29.99),
+                    category: Some("Personal".to_string()),
+                    memo: "paint".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let result = transaction.validate_splits();
+
+        assert!(matches!(
+            result,
+            Err(error::Error::SplitSumMismatch { expected, actual })
+                if expected == dec!(-60.00) && actual == dec!(-59.99)
+        ));
+    }
+
+    #[test]
+    fn category_parts_of_split_transaction() {
+        let transaction = Transaction {
+            date: NaiveDate::from_ymd_opt(2021, 3, 4).unwrap(),
+            amount: dec!(-60.00),
+            memo: "hardware store".to_string(),
+            category: Some("Supplies".to_string()),
+            splits: vec![
+                Split {
+                    amount: dec!(-35.00),
+                    category: Some("Supplies".to_string()),
+                    memo: "nails".to_string(),
+                },
+                Split {
+                    amount: dec!(-25.00),
+                    category: None,
+                    memo: "paint".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let parts = transaction.category_parts();
+
+        assert!(transaction.validate_splits().is_ok());
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].amount, dec!(-35.00));
+        assert_eq!(parts[0].category, Some("Supplies".to_string()));
+        assert_eq!(parts[1].amount, dec!(-25.00));
+        assert_eq!(parts[1].category, None);
+        assert_eq!(parts[1].date, transaction.date);
+    }
+
+    #[test]
+    fn convert_currencies() {
+        let date = NaiveDate::from_ymd_opt(2021, 3, 4).unwrap();
+        let rates = ExchangeRates::from_rates([ExchangeRate {
+            date,
+            base: "EUR".to_string(),
+            quote: "USD".to_string(),
+            rate: dec!(1.125),
+        }]);
+
+        assert_eq!(
+            rates.convert(dec!(10.00), "USD", "USD", date),
+            Some(dec!(10.00))
+        );
+        assert_eq!(
+            rates.convert(dec!(10.02), "EUR", "USD", date),
+            Some(dec!(11.27))
+        );
+        assert_eq!(
+            rates.convert(dec!(10.06), "EUR", "USD", date),
+            Some(dec!(11.32))
+        );
+        assert_eq!(
+            rates.convert(dec!(-0.10), "EUR", "USD", date),
+            Some(dec!(-0.11))
+        );
+        assert_eq!(
+            rates.convert(dec!(9.00), "USD", "EUR", date),
+            Some(dec!(8.00))
+        );
+        assert_eq!(rates.convert(dec!(10.00), "CAD", "USD", date), None);
+        assert_eq!(
+            rates.convert(dec!(10.00), "EUR", "USD", date.succ_opt().unwrap()),
+            None
+        );
+    }
+
+    fn monthly_schedule() -> Schedule {
+        Schedule {
+            account_id: None,
+            amount: dec!(-1200.00),
+            memo: "rent".to_string(),
+            category: None,
+            frequency: Frequency::Monthly,
+            interval: 1,
+            day_of_month: Some(31),
+            start_date: NaiveDate::from_ymd_opt(2023, 1, 15).unwrap(),
+            end_date: None,
+            count: Some(3),
+        }
+    }
+
+    #[test]
+    fn monthly_occurrences() {
+        let schedule = monthly_schedule();
+
+        let dates: Vec<_> = schedule.occurrences().collect();
+
+        assert_eq!(
+            dates,
+            [
+                NaiveDate::from_ymd_opt(2023, 1, 31).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 2, 28).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 3, 31).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_occurrences() {
+        let schedule = Schedule {
+            frequency: Frequency::Weekly,
+            interval: 2,
+            day_of_month: None,
+            end_date: Some(NaiveDate::from_ymd_opt(2023, 2, 12).unwrap()),
+            count: None,
+            ..monthly_schedule()
+        };
+
+        let dates: Vec<_> = schedule.occurrences().collect();
+
+        assert!(schedule.is_valid());
+        assert_eq!(
+            dates,
+            [
+                NaiveDate::from_ymd_opt(2023, 1, 15).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 1, 29).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 2, 12).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn delta_between_amounts() {
+        let increase = Delta::between(dec!(200.00), dec!(250.00));
+        let from_loss = Delta::between(dec!(-50.00), dec!(25.00));
+        let from_zero = Delta::between(dec!(0), dec!(25.00));
+
+        assert_eq!(increase.absolute, dec!(50.00));
+        assert_eq!(increase.percent, Some(dec!(25.00)));
+        assert_eq!(from_loss.absolute, dec!(75.00));
+        assert_eq!(from_loss.percent, Some(dec!(150.00)));
+        assert_eq!(from_zero.absolute, dec!(25.00));
+        assert_eq!(from_zero.percent, None);
+    }
+
+    #[test]
+    fn normalize_memo() {
+        assert_eq!(Payee::normalize_memo("347 Woodrow"), "347 woodrow");
+        assert_eq!(Payee::normalize_memo("  FUEL  "), "fuel");
+        assert_eq!(
+            Payee::normalize_memo("SHELL OIL 08/20 CARD XXXX4532"),
+            "shell oil card"
+        );
+        assert_eq!(
+            Payee::normalize_memo("Amazon Mktp #123 REF A1B2C3D4"),
+            "amazon mktp ref"
+        );
+        assert_eq!(Payee::normalize_memo("2023-08-20"), "2023-08-20");
+        assert_eq!(
+            Payee::normalize_memo("Transfer 482913374 Savings"),
+            "transfer savings"
+        );
+        assert_eq!(Payee::normalize_memo("Target *0042"), "target");
+    }
+
+    #[test]
+    fn normalize_memo_keeps_names() {
+        assert_eq!(Payee::normalize_memo("1234 Main St"), "1234 main st");
+        assert_ne!(
+            Payee::normalize_memo("1234 Main St"),
+            Payee::normalize_memo("5678 Main St")
+        );
+        assert_eq!(Payee::normalize_memo("Dues 2023"), "dues 2023");
+        assert_ne!(
+            Payee::normalize_memo("Dues 2023"),
+            Payee::normalize_memo("Dues 2024")
+        );
+        assert_eq!(Payee::normalize_memo("7Eleven Store"), "7eleven store");
+        assert_eq!(Payee::normalize_memo("Boeing747 Parts"), "boeing747 parts");
+        assert_eq!(Payee::normalize_memo("Xerox Supplies"), "xerox supplies");
+    }
+
+    #[test]
+    fn trip_from_entry() {
+        let entry = TripEntry {
+            date: NaiveDate::from_ymd_opt(2020, 7, 4).unwrap(),
+            start_odometer: Some(dec!(1200.5)),
+            end_odometer: Some(dec!(1212.0)),
+            distance: None,
+            purpose: "347 Woodrow".to_string(),
+            transaction_id: None,
+        };
+
+        let trip = Trip::try_from(entry.clone()).unwrap();
+        assert_eq!(trip.distance, dec!(11.5));
+
+        let with_distance = TripEntry {
+            distance: Some(dec!(11.5)),
+            ..entry.clone()
+        };
+        assert_eq!(Trip::try_from(with_distance).unwrap(), trip);
+
+        let mismatch = TripEntry {
+            distance: Some(dec!(12)),
+            ..entry.clone()
+        };
+        assert!(matches!(
+            Trip::try_from(mismatch),
+            Err(error::Error::InvalidTrip)
+        ));
+
+        let backwards = TripEntry {
+            start_odometer: Some(dec!(1300)),
+            ..entry.clone()
+        };
+        assert!(Trip::try_from(backwards).is_err());
+
+        let missing = TripEntry {
+            start_odometer: None,
+            end_odometer: None,
+            ..entry
+        };
+        assert!(Trip::try_from(missing).is_err());
+    }
+
+    #[test]
+    fn sniff_attachments() {
+        assert_eq!(
+            Attachment::sniff_mime_type(b"%PDF-1.7\n"),
+            Some("application/pdf")
+        );
+        assert_eq!(
+            Attachment::sniff_mime_type(b"\xff\xd8\xff\xe0\x00\x10JFIF"),
+            Some("image/jpeg")
+        );
+        assert_eq!(
+            Attachment::sniff_mime_type(b"RIFF\x24\x00\x00\x00WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(Attachment::sniff_mime_type(b"<html>"), None);
+        assert_eq!(
+            Attachment::sanitize_file_name("C:\\scans\\fuel \"july\".pdf"),
+            "fuel july.pdf"
+        );
+        assert_eq!(Attachment::sanitize_file_name("../"), "attachment");
+    }
+
+    #[test]
+    fn aging_buckets() {
+        let mut buckets = AgingBuckets::default();
+        for (days_past_due, amount) in [
+            (-10, dec!(1)),
+            (30, dec!(2)),
+            (31, dec!(4)),
+            (90, dec!(8)),
+            (91, dec!(16)),
+        ] {
+            buckets.add(days_past_due, amount);
+        }
+
+        assert_eq!(buckets.days_0_30, dec!(3));
+        assert_eq!(buckets.days_31_60, dec!(4));
+        assert_eq!(buckets.days_61_90, dec!(8));
+        assert_eq!(buckets.days_over_90, dec!(16));
+        assert_eq!(buckets.total, dec!(31));
     }
 }
@@ -8,4 +8,114 @@ pub enum Error {
     QueryErrorBuilding(#[from] sea_query::error::Error),
     #[error("Invalid CSV income entry")]
     InvalidCSVIncome,
+    #[error("Invalid account type {0}")]
+    InvalidAccountKind(String),
+    #[error("Invalid currency {0}")]
+    InvalidCurrency(String),
+    #[error("Account {0} does not exist")]
+    AccountNotFound(uuid::Uuid),
+    #[error("Invalid transaction kind {0}")]
+    InvalidTransactionKind(String),
+    #[error("Transaction {0} does not exist")]
+    TransactionNotFound(uuid::Uuid),
+    #[error("Transactions {0} and {1} cannot be linked as a transfer")]
+    InvalidTransfer(uuid::Uuid, uuid::Uuid),
+    #[error("A journal entry needs at least two postings")]
+    TooFewPostings,
+    #[error("Journal entry is unbalanced by {0}")]
+    UnbalancedJournalEntry(rust_decimal::Decimal),
+    #[error("Account {0} cannot hold bank transactions")]
+    NotABankAccount(uuid::Uuid),
+    #[error("Splits sum to {actual} instead of {expected}")]
+    SplitSumMismatch {
+        expected: rust_decimal::Decimal,
+        actual: rust_decimal::Decimal,
+    },
+    #[error("Transaction {0} is reconciled and cannot be changed")]
+    TransactionReconciled(uuid::Uuid),
+    #[error("Transaction {0} is a transfer leg or a payment and cannot be deleted")]
+    TransactionInUse(uuid::Uuid),
+    #[error("Invalid reconciliation status {0}")]
+    InvalidReconciliationStatus(String),
+    #[error("Reconciliation {0} does not exist")]
+    ReconciliationNotFound(uuid::Uuid),
+    #[error("Account {0} already has an open reconciliation")]
+    ReconciliationInProgress(uuid::Uuid),
+    #[error("Reconciliation {0} is finished")]
+    ReconciliationFinished(uuid::Uuid),
+    #[error("Transaction {0} is not part of the statement being reconciled")]
+    TransactionNotInStatement(uuid::Uuid),
+    #[error("Cleared balance differs from the statement by {0}")]
+    ReconciliationUnbalanced(rust_decimal::Decimal),
+    #[error("{date} is in a period closed through {closed_through}")]
+    PeriodClosed {
+        date: chrono::NaiveDate,
+        closed_through: chrono::NaiveDate,
+    },
+    #[error("Books cannot be closed through {0}")]
+    InvalidClosingDate(chrono::NaiveDate),
+    #[error("Books cannot be reopened without a reason")]
+    MissingReopenReason,
+    #[error("Invalid frequency {0}")]
+    InvalidFrequency(String),
+    #[error("Invalid schedule")]
+    InvalidSchedule,
+    #[error("Schedule {0} does not exist")]
+    ScheduleNotFound(uuid::Uuid),
+    #[error("Invalid budget")]
+    InvalidBudget,
+    #[error(
+        "Forecast needs 1 to 3650 days of history and at most 730 days ahead, within the calendar"
+    )]
+    InvalidForecast,
+    #[error("Payee alias needs a payee")]
+    InvalidPayeeAlias,
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid year {0}")]
+    InvalidYear(i32),
+    #[error("Unknown tax code {0}")]
+    UnknownTaxCode(String),
+    #[error("Invalid trip")]
+    InvalidTrip,
+    #[error("Trip {0} does not exist")]
+    TripNotFound(uuid::Uuid),
+    #[error("Transaction {0} is not income and cannot be linked to a trip")]
+    TripTransactionNotIncome(uuid::Uuid),
+    #[error("Attachment exceeds the maximum size of {0} bytes")]
+    AttachmentTooLarge(usize),
+    #[error("Attachment is not an image or a PDF")]
+    UnsupportedAttachment,
+    #[error("Attachment {0} does not exist")]
+    AttachmentNotFound(uuid::Uuid),
+    #[error("Invalid customer")]
+    InvalidCustomer,
+    #[error("Customer {0} does not exist")]
+    CustomerNotFound(uuid::Uuid),
+    #[error("Invalid invoice")]
+    InvalidInvoice,
+    #[error("Invoice {0} does not exist")]
+    InvoiceNotFound(uuid::Uuid),
+    #[error("Invalid invoice status {0}")]
+    InvalidInvoiceStatus(String),
+    #[error("Invoice {0} has not been sent")]
+    InvoiceNotSent(uuid::Uuid),
+    #[error("Invalid payment: {0}")]
+    InvalidPayment(String),
+    #[error("Invalid vendor")]
+    InvalidVendor,
+    #[error("Vendor {0} does not exist")]
+    VendorNotFound(uuid::Uuid),
+    #[error("Invalid bill")]
+    InvalidBill,
+    #[error("Cannot look {0} days ahead")]
+    InvalidUpcomingDays(u64),
+    #[error("Bill {0} does not exist")]
+    BillNotFound(uuid::Uuid),
+    #[error("Invalid audit action {0}")]
+    InvalidAuditAction(String),
+    #[error("Budget overlaps budget {0} of the same category")]
+    OverlappingBudget(uuid::Uuid),
+    #[error("Event {0} changes transaction {1}, which was not imported or was deleted")]
+    EventWithoutTransaction(i64, uuid::Uuid),
 }
@@ -0,0 +1,618 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+use crate::{
+    entity::{
+        Account, AccountClass, BalanceSheet, CurrencyReport, IncomeStatement, JournalEntry,
+        LedgerLine, Report, Transaction, TrialBalance, TrialBalanceLine, WithId,
+    },
+    error,
+    query::SqliteStore,
+};
+
+/// Double-entry view of the books. Imported transactions and opening balances
+/// are posted as journal entries when they are stored, next to the entries
+/// that were recorded by hand.
+pub struct Ledger;
+
+impl Ledger {
+    /// The entries posted for the transactions, see
+    /// [`JournalEntry::from_transaction`].
+    pub fn entries_from_transactions<'a>(
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+    ) -> Vec<JournalEntry> {
+        transactions
+            .into_iter()
+            .map(JournalEntry::from_transaction)
+            .collect()
+    }
+
+    /// The entries posted for the opening balances, see
+    /// [`JournalEntry::from_opening_balance`].
+    pub fn entries_from_opening_balances<'a>(
+        accounts: impl IntoIterator<Item = &'a WithId<Account>>,
+    ) -> Vec<JournalEntry> {
+        accounts
+            .into_iter()
+            .filter_map(JournalEntry::from_opening_balance)
+            .collect()
+    }
+
+    /// Sums the postings of every account for the entries dated within the
+    /// given inclusive bounds.
+    pub fn balances<'a>(
+        entries: impl IntoIterator<Item = &'a JournalEntry>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> HashMap<Uuid, Decimal> {
+        let mut balances = HashMap::new();
+        for entry in entries {
+            if from.is_some_and(|from| entry.date < from) || to.is_some_and(|to| entry.date > to) {
+                continue;
+            }
+            for posting in &entry.postings {
+                *balances.entry(posting.account_id).or_insert(dec!(0)) += posting.amount;
+            }
+        }
+        balances
+    }
+
+    fn lines(
+        accounts: &[WithId<Account>],
+        balances: &HashMap<Uuid, Decimal>,
+        class: AccountClass,
+    ) -> Vec<LedgerLine> {
+        accounts
+            .iter()
+            .filter(|x| x.data.kind.class() == class)
+            .filter_map(|x| {
+                balances.get(&x.id).map(|balance| LedgerLine {
+                    account_id: x.id,
+                    name: x.data.name.clone(),
+                    class,
+                    balance: *balance,
+                })
+            })
+            .collect()
+    }
+
+    fn credit_lines(
+        accounts: &[WithId<Account>],
+        balances: &HashMap<Uuid, Decimal>,
+        class: AccountClass,
+    ) -> Vec<LedgerLine> {
+        Ledger::lines(accounts, balances, class)
+            .into_iter()
+            .map(|x| LedgerLine {
+                balance: -x.balance,
+                ..x
+            })
+            .collect()
+    }
+
+    fn total(lines: &[LedgerLine]) -> Decimal {
+        lines.iter().map(|x| x.balance).sum()
+    }
+
+    #[must_use]
+    pub fn trial_balance(
+        accounts: &[WithId<Account>],
+        entries: &[JournalEntry],
+        as_of: Option<NaiveDate>,
+    ) -> TrialBalance {
+        let balances = Ledger::balances(entries, None, as_of);
+        let mut lines: Vec<_> = accounts
+            .iter()
+            .filter_map(|x| {
+                balances
+                    .get(&x.id)
+                    .copied()
+                    .map(|balance| TrialBalanceLine {
+                        account_id: x.id,
+                        name: x.data.name.clone(),
+                        class: x.data.kind.class(),
+                        debit: balance.max(dec!(0)),
+                        credit: (-balance).max(dec!(0)),
+                    })
+            })
+            .collect();
+        lines.sort_by(|lhs, rhs| (lhs.class, &lhs.name).cmp(&(rhs.class, &rhs.name)));
+
+        TrialBalance {
+            total_debit: lines.iter().map(|x| x.debit).sum(),
+            total_credit: lines.iter().map(|x| x.credit).sum(),
+            lines,
+        }
+    }
+
+    /// Income and expenses up to the date are closed into retained earnings,
+    /// so that assets always equal liabilities plus equity.
+    #[must_use]
+    pub fn balance_sheet(
+        accounts: &[WithId<Account>],
+        entries: &[JournalEntry],
+        as_of: Option<NaiveDate>,
+    ) -> BalanceSheet {
+        let balances = Ledger::balances(entries, None, as_of);
+        let assets = Ledger::lines(accounts, &balances, AccountClass::Asset);
+        let liabilities = Ledger::credit_lines(accounts, &balances, AccountClass::Liability);
+        let equity = Ledger::credit_lines(accounts, &balances, AccountClass::Equity);
+        let retained_earnings = Ledger::total(&Ledger::credit_lines(
+            accounts,
+            &balances,
+            AccountClass::Income,
+        )) + Ledger::total(&Ledger::credit_lines(
+            accounts,
+            &balances,
+            AccountClass::Expense,
+        ));
+
+        BalanceSheet {
+            total_assets: Ledger::total(&assets),
+            total_liabilities: Ledger::total(&liabilities),
+            total_equity: Ledger::total(&equity) + retained_earnings,
+            retained_earnings,
+            assets,
+            liabilities,
+            equity,
+        }
+    }
+
+    #[must_use]
+    pub fn income_statement(
+        accounts: &[WithId<Account>],
+        entries: &[JournalEntry],
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> IncomeStatement {
+        let balances = Ledger::balances(entries, from, to);
+        let income = Ledger::credit_lines(accounts, &balances, AccountClass::Income);
+        let expenses = Ledger::lines(accounts, &balances, AccountClass::Expense);
+        let gross_revenue = Ledger::total(&income);
+        let total_expenses = Ledger::total(&expenses);
+
+        IncomeStatement {
+            report: Report::from_dec(
+                gross_revenue,
+                total_expenses,
+                gross_revenue - total_expenses,
+            ),
+            income,
+            expenses,
+        }
+    }
+
+    /// Every journal entry of the books: opening balances, imported
+    /// transactions and the entries recorded by hand, as they were posted.
+    ///
+    /// # Errors
+    pub async fn load(
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<(Vec<WithId<Account>>, Vec<JournalEntry>), error::Error> {
+        let accounts = sqlite_store.get_accounts().await?;
+        let entries = sqlite_store
+            .get_ledger_entries()
+            .await?
+            .into_iter()
+            .map(|x| x.data)
+            .collect();
+
+        Ok((accounts, entries))
+    }
+
+    /// The income and expenses of the entries recorded by hand, or only of
+    /// those recorded by the given time, per currency of their accounts.
+    /// Imported transactions are already part of the stored reports.
+    ///
+    /// # Errors
+    pub async fn journal_report(
+        as_of: Option<DateTime<Utc>>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<CurrencyReport>, error::Error> {
+        let accounts = sqlite_store.get_accounts().await?;
+        let recorded: Vec<_> = sqlite_store
+            .get_journal_entries(as_of)
+            .await?
+            .into_iter()
+            .map(|x| x.data)
+            .collect();
+
+        let mut currencies: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for account in accounts {
+            currencies
+                .entry(account.data.currency.clone())
+                .or_default()
+                .push(account);
+        }
+        Ok(currencies
+            .into_iter()
+            .filter_map(|(currency, accounts)| {
+                let statement = Ledger::income_statement(&accounts, &recorded, None, None);
+                (!statement.income.is_empty() || !statement.expenses.is_empty()).then_some(
+                    CurrencyReport {
+                        currency,
+                        report: statement.report,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    ///
+    /// # Errors
+    pub async fn create_journal_entry(
+        entry: JournalEntry,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<JournalEntry>, error::Error> {
+        entry.validate()?;
+        for posting in &entry.postings {
+            sqlite_store.get_account(posting.account_id).await?;
+        }
+
+        let entry = WithId::from_data(entry);
+        sqlite_store.create_journal_entry(&entry).await?;
+        sqlite_store.commit().await?;
+
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{NaiveDate, Utc};
+    use rust_decimal_macros::dec;
+    use sqlx::SqlitePool;
+    use uuid::Uuid;
+
+    use crate::{
+        entity::{Account, AccountKind, JournalEntry, Posting, Transaction, WithId},
+        error,
+        logic::Model,
+        query::{apply_migrations, SqliteStore},
+    };
+
+    use super::Ledger;
+
+    const POSTED_ENTRIES_VERSION: i64 = 20_240_226_090_000;
+
+    async fn sqlite_store_of(pool: &SqlitePool) -> Result<SqliteStore<'static>, error::Error> {
+        Ok(SqliteStore::from_sqlite_transaction(pool.begin().await?))
+    }
+
+    fn account(id: Uuid, name: &str, kind: AccountKind) -> WithId<Account> {
+        WithId {
+            id,
+            data: Account {
+                name: name.to_string(),
+                kind,
+                opening_balance: dec!(0),
+                opening_date: NaiveDate::from_str("2021-01-01").unwrap(),
+                currency: "USD".to_string(),
+            },
+        }
+    }
+
+    fn accounts(checking: Uuid, card: Uuid) -> Vec<WithId<Account>> {
+        vec![
+            account(
+                Account::OPENING_BALANCE_EQUITY_ID,
+                "Opening Balance Equity",
+                AccountKind::Equity,
+            ),
+            account(
+                Account::UNCATEGORIZED_INCOME_ID,
+                "Uncategorized Income",
+                AccountKind::Income,
+            ),
+            account(
+                Account::UNCATEGORIZED_EXPENSES_ID,
+                "Uncategorized Expenses",
+                AccountKind::Expense,
+            ),
+            account(Account::UNASSIGNED_ID, "Unassigned", AccountKind::Asset),
+            WithId {
+                id: checking,
+                data: Account {
+                    opening_balance: dec!(100.00),
+                    ..account(checking, "checking", AccountKind::Checking).data
+                },
+            },
+            account(card, "card", AccountKind::CreditCard),
+        ]
+    }
+
+    #[test]
+    fn unbalanced_journal_entry() {
+        let entry = JournalEntry {
+            date: NaiveDate::from_str("2021-07-12").unwrap(),
+            memo: "unbalanced".to_string(),
+            postings: vec![
+                Posting {
+                    account_id: Account::UNASSIGNED_ID,
+                    amount: dec!(10.00),
+                },
+                Posting {
+                    account_id: Account::UNCATEGORIZED_INCOME_ID,
+                    amount: dec!(-9.99),
+                },
+            ],
+        };
+        let single = JournalEntry {
+            postings: entry.postings[..1].to_vec(),
+            ..entry.clone()
+        };
+
+        assert!(matches!(
+            entry.validate(),
+            Err(error::Error::UnbalancedJournalEntry(x)) if x == dec!(0.01)
+        ));
+        assert!(matches!(
+            single.validate(),
+            Err(error::Error::TooFewPostings)
+        ));
+    }
+
+    #[test]
+    fn statements_from_transactions() {
+        let checking = Uuid::new_v4();
+        let card = Uuid::new_v4();
+        let accounts = accounts(checking, card);
+        let transactions = vec![
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                account_id: Some(checking),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-08-20").unwrap(),
+                amount: dec!(-12.13),
+                memo: "second".to_string(),
+                account_id: Some(card),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-09-01").unwrap(),
+                amount: dec!(-5.00),
+                memo: "third".to_string(),
+                ..Default::default()
+            },
+        ];
+        let mut entries = Ledger::entries_from_opening_balances(&accounts);
+        entries.extend(Ledger::entries_from_transactions(&transactions));
+
+        let income_statement = Ledger::income_statement(&accounts, &entries, None, None);
+        let balance_sheet = Ledger::balance_sheet(&accounts, &entries, None);
+        let trial_balance = Ledger::trial_balance(&accounts, &entries, None);
+        let august = Ledger::income_statement(
+            &accounts,
+            &entries,
+            NaiveDate::from_ymd_opt(2021, 8, 1),
+            NaiveDate::from_ymd_opt(2021, 8, 31),
+        );
+
+        assert_eq!(
+            income_statement.report,
+            Model::calculate_balance_from_transactions(&transactions)
+        );
+        assert_eq!(balance_sheet.total_assets, dec!(182.32));
+        assert_eq!(balance_sheet.total_liabilities, dec!(12.13));
+        assert_eq!(balance_sheet.retained_earnings, dec!(70.19));
+        assert_eq!(
+            balance_sheet.total_assets,
+            balance_sheet.total_liabilities + balance_sheet.total_equity
+        );
+        assert_eq!(trial_balance.total_debit, trial_balance.total_credit);
+        assert_eq!(august.report.expenses, dec!(12.13));
+        assert_eq!(august.report.gross_revenue, dec!(0));
+    }
+
+    #[sqlx::test]
+    async fn create_journal_entry(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+
+        let entry = JournalEntry {
+            date: NaiveDate::from_str("2021-07-12").unwrap(),
+            memo: "owner contribution".to_string(),
+            postings: vec![
+                Posting {
+                    account_id: Account::UNASSIGNED_ID,
+                    amount: dec!(250.00),
+                },
+                Posting {
+                    account_id: Account::UNCATEGORIZED_INCOME_ID,
+                    amount: dec!(-200.00),
+                },
+                Posting {
+                    account_id: Account::OPENING_BALANCE_EQUITY_ID,
+                    amount: dec!(-50.00),
+                },
+            ],
+        };
+        let before = Utc::now();
+        let created = Ledger::create_journal_entry(entry.clone(), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let entries = sqlite_store.get_journal_entries(None).await?;
+        let report = Ledger::journal_report(None, &mut sqlite_store).await?;
+        let report_before = Ledger::journal_report(Some(before), &mut sqlite_store).await?;
+        let report_after = Ledger::journal_report(Some(Utc::now()), &mut sqlite_store).await?;
+        let (accounts, entries_of_ledger) = Ledger::load(&mut sqlite_store).await?;
+        let trial_balance = Ledger::trial_balance(&accounts, &entries_of_ledger, None);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, created.id);
+        assert_eq!(entries[0].data, entry);
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].currency, "USD");
+        assert_eq!(report[0].report.gross_revenue, dec!(200.00));
+        assert_eq!(report[0].report.net_revenue, dec!(200.00));
+        assert!(report_before.is_empty());
+        assert_eq!(report_after, report);
+        assert_eq!(trial_balance.total_debit, dec!(250.00));
+        assert_eq!(trial_balance.total_credit, dec!(250.00));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn create_journal_entry_for_missing_account(
+        pool: SqlitePool,
+    ) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let missing = Uuid::new_v4();
+
+        let entry = JournalEntry {
+            date: NaiveDate::from_str("2021-07-12").unwrap(),
+            memo: "missing".to_string(),
+            postings: vec![
+                Posting {
+                    account_id: missing,
+                    amount: dec!(1.00),
+                },
+                Posting {
+                    account_id: Account::UNCATEGORIZED_INCOME_ID,
+                    amount: dec!(-1.00),
+                },
+            ],
+        };
+        let result = Ledger::create_journal_entry(entry, sqlite_store).await;
+
+        assert!(matches!(result, Err(error::Error::AccountNotFound(x)) if x == missing));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn post_entries(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let checking = Model::create_account(
+            account(Uuid::new_v4(), "checking", AccountKind::Checking).data,
+            sqlite_store,
+        )
+        .await?;
+        let savings = Model::create_account(
+            Account {
+                opening_balance: dec!(50.00),
+                ..account(Uuid::new_v4(), "savings", AccountKind::Savings).data
+            },
+            sqlite_store_of(&pool).await?,
+        )
+        .await?;
+
+        let transactions = [
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(-20.00),
+                memo: "to savings".to_string(),
+                account_id: Some(checking.id),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(20.00),
+                memo: "from checking".to_string(),
+                account_id: Some(savings.id),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-07-20").unwrap(),
+                amount: dec!(-5.00),
+                memo: "fee".to_string(),
+                ..Default::default()
+            },
+        ];
+        Model::commit_transactions(&transactions, sqlite_store_of(&pool).await?).await?;
+
+        let mut sqlite_store = sqlite_store_of(&pool).await?;
+        let committed = sqlite_store.get_transactions().await?;
+        drop(sqlite_store);
+        let id_of = |memo: &str| committed.iter().find(|x| x.data.memo == memo).unwrap().id;
+        Model::link_transfer(
+            id_of("to savings"),
+            id_of("from checking"),
+            sqlite_store_of(&pool).await?,
+        )
+        .await?;
+        Model::delete_transaction(id_of("fee"), sqlite_store_of(&pool).await?).await?;
+
+        let mut sqlite_store = sqlite_store_of(&pool).await?;
+        let (accounts, entries) = Ledger::load(&mut sqlite_store).await?;
+        let live = sqlite_store.get_transactions().await?;
+        let mut expected = Ledger::entries_from_opening_balances(&accounts);
+        expected.extend(Ledger::entries_from_transactions(
+            live.iter().map(|x| &x.data),
+        ));
+        let listed = Model::get_accounts(&mut sqlite_store).await?;
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            Ledger::balances(&entries, None, None),
+            Ledger::balances(&expected, None, None)
+        );
+        assert_eq!(
+            Ledger::balances(&entries, None, None).get(&Account::TRANSFER_CLEARING_ID),
+            Some(&dec!(0))
+        );
+        assert!(sqlite_store.get_journal_entries(None).await?.is_empty());
+        assert_eq!(
+            listed.iter().map(|x| x.id).collect::<Vec<_>>(),
+            vec![checking.id, savings.id]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn post_entries_stored_before(pool: SqlitePool) -> Result<(), error::Error> {
+        let checking = Uuid::new_v4();
+        apply_migrations(&pool, ..POSTED_ENTRIES_VERSION).await?;
+        sqlx::query(
+            "INSERT INTO accounts (id, name, kind, opening_balance, opening_date, currency)
+            VALUES (?, 'checking', 'checking', '100.00', '2021-01-01', 'USD')",
+        )
+        .bind(checking.to_string())
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "INSERT INTO transactions (id, date, amount, memo, account_id, kind)
+            VALUES (?, '2021-07-12', '87.32', 'first', ?, 'regular'),
+                (?, '2021-08-20', '-12.13', 'second', NULL, 'regular'),
+                (?, '2021-08-21', '-3.00', 'leg', ?, 'transfer')",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(checking.to_string())
+        .bind(Uuid::new_v4().to_string())
+        .bind(Uuid::new_v4().to_string())
+        .bind(checking.to_string())
+        .execute(&pool)
+        .await?;
+        apply_migrations(&pool, POSTED_ENTRIES_VERSION..).await?;
+
+        let mut sqlite_store = sqlite_store_of(&pool).await?;
+        let (accounts, entries) = Ledger::load(&mut sqlite_store).await?;
+        let transactions = sqlite_store.get_transactions().await?;
+        let mut expected = Ledger::entries_from_opening_balances(&accounts);
+        expected.extend(Ledger::entries_from_transactions(
+            transactions.iter().map(|x| &x.data),
+        ));
+        let balances = Ledger::balances(&entries, None, None);
+
+        assert_eq!(entries.len(), 4);
+        assert!(entries.iter().all(|x| x.validate().is_ok()));
+        assert_eq!(balances, Ledger::balances(&expected, None, None));
+        assert_eq!(balances[&checking], dec!(184.32));
+        assert_eq!(balances[&Account::UNASSIGNED_ID], dec!(-12.13));
+        assert_eq!(balances[&Account::TRANSFER_CLEARING_ID], dec!(3.00));
+        assert!(sqlite_store.get_journal_entries(None).await?.is_empty());
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 #![warn(clippy::pedantic)]
 
+pub mod config;
 pub mod entity;
 pub mod error;
+pub mod ledger;
 pub mod logic;
 pub mod query;
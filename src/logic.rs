@@ -1,15 +1,49 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+};
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc};
 use csv_async::{AsyncReaderBuilder, Trim};
 use futures::StreamExt;
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
 use crate::{
-    entity::{Report, Transaction, TransactionFromCSV, WithId},
+    config::{Config, MileageConfig, TaxConfig, VatConfig},
+    entity::{
+        Account, AccountBalance, AccountClass, AgingBuckets, AgingLine, AgingReport, Attachment,
+        Batch, Bill, BillStatus, BillSummary, Budget, BudgetLine, CategoryAverage,
+        CategoryComparison, CategoryReport, ChainBreak, ChainBreakReason, ChainVerification,
+        ChangeFeed, Comparison, ComparisonReport, ConvertedReport, CurrencyReport, Customer, Event,
+        EventRecord, ExchangeRate, ExchangeRates, Forecast, ForecastOptions, ForecastPoint,
+        Invoice, InvoiceStatus, InvoiceSummary, MileageReport, MileageYear, MissingReceipts, Payee,
+        PayeeAlias, PayeeRanking, PayeeTotal, Payment, PeriodClosing, ProjectionRun, QuarterlyTax,
+        Reconciliation, ReconciliationStatus, ReconciliationSummary, Report, ReportCheck,
+        ReportMismatch, Schedule, Split, StatementLine, TaxReport, Transaction, TransactionFromCSV,
+        TransactionKind, TransferCandidate, Trip, TripEntry, VatLine, VatReturn, Vendor, WithId,
+    },
     error,
+    ledger::Ledger,
     query::SqliteStore,
 };
 
 pub struct Model;
 
 impl Model {
+    /// How many days apart the two legs of a detected transfer may be.
+    pub const TRANSFER_WINDOW_DAYS: i64 = 3;
+    /// How many days ahead a balance forecast may project.
+    pub const MAX_FORECAST_DAYS: u32 = 730;
+    /// How many days of history a balance forecast may average.
+    pub const MAX_FORECAST_LOOKBACK_DAYS: u32 = 3650;
+    /// The most items a page of a statement or of the change feed lists.
+    pub const MAX_PAGE_SIZE: u64 = 1000;
+    /// The projection of the events into the reports of the uploads.
+    pub const REPORTS_PROJECTION: &'static str = "reports";
+
     pub fn calculate_balance_from_transactions<'a>(
         transactions: impl IntoIterator<Item = &'a Transaction>,
     ) -> Report {
@@ -28,225 +62,5233 @@ impl Model {
         report
     }
 
+    /// One report per category, using the splits of a transaction instead of
+    /// the transaction itself when it has any.
+    pub fn calculate_category_reports<'a>(
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+    ) -> Vec<CategoryReport> {
+        let mut categories: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for part in transactions
+            .into_iter()
+            .flat_map(Transaction::category_parts)
+        {
+            categories
+                .entry(part.category.clone())
+                .or_default()
+                .push(part);
+        }
+        categories
+            .into_iter()
+            .map(|(category, parts)| CategoryReport {
+                category,
+                report: Model::calculate_balance_from_transactions(&parts),
+            })
+            .collect()
+    }
+
+    /// One report per currency of the transactions.
+    pub fn calculate_currency_reports<'a>(
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+    ) -> Vec<CurrencyReport> {
+        let mut currencies: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for transaction in transactions {
+            currencies
+                .entry(transaction.currency.clone())
+                .or_default()
+                .push(transaction);
+        }
+        currencies
+            .into_iter()
+            .map(|(currency, transactions)| CurrencyReport {
+                currency,
+                report: Model::calculate_balance_from_transactions(transactions),
+            })
+            .collect()
+    }
+
+    /// The global report over the income and expense accounts: the reports of
+    /// every currency of [`Model::get_global_report`] added up as they are.
     ///
     /// # Errors
-    pub async fn commit_transactions<'a>(
-        transactions: &[Transaction],
-        mut sqlite_store: SqliteStore<'a>,
+    pub async fn get_report(
+        as_of: Option<DateTime<Utc>>,
+        sqlite_store: &mut SqliteStore<'_>,
     ) -> Result<Report, error::Error> {
-        let report = Model::calculate_balance_from_transactions(transactions);
-        let report_with_id = WithId::from_data(report);
+        let reports = Model::get_global_report(as_of, sqlite_store).await?;
+        Ok(Model::calculate_total_report(
+            reports.iter().map(|x| &x.report),
+        ))
+    }
 
-        sqlite_store
-            .create_transactions(transactions.iter().map(WithId::from_data))
-            .await?;
-        tracing::debug!("updated transactions");
+    /// The global report, per currency: the stored reports of the uploads,
+    /// each in the currency of its transactions, or the reports of every
+    /// transaction as they were at the given time. The income and expenses of
+    /// the journal entries recorded by hand, by then, are added to the report
+    /// of the currency of their accounts. Uploads of transactions in several
+    /// currencies, such as the occurrences of schedules of different accounts,
+    /// are reported from their transactions.
+    ///
+    /// # Errors
+    pub async fn get_global_report(
+        as_of: Option<DateTime<Utc>>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<CurrencyReport>, error::Error> {
+        let mut parts = vec![];
+        if let Some(at) = as_of {
+            let transactions = sqlite_store.get_transactions_as_of(at, None, None).await?;
+            parts.extend(Model::calculate_currency_reports(
+                transactions.iter().map(|x| &x.data),
+            ));
+        } else {
+            let currencies = sqlite_store.get_report_currencies().await?;
+            for report in sqlite_store.get_reports_with_ids().await? {
+                match currencies.get(&report.id).map(Vec::as_slice) {
+                    Some([currency]) => parts.push(CurrencyReport {
+                        currency: currency.clone(),
+                        report: report.data,
+                    }),
+                    Some(_) => {
+                        let transactions = sqlite_store.get_report_transactions(report.id).await?;
+                        parts.extend(Model::calculate_currency_reports(
+                            transactions.iter().map(|x| &x.data),
+                        ));
+                    }
+                    None => {}
+                }
+            }
+        }
+        parts.extend(Ledger::journal_report(as_of, sqlite_store).await?);
 
-        sqlite_store.create_report(&report_with_id).await?;
-        tracing::debug!("updated report");
+        let mut currencies: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for part in parts {
+            currencies
+                .entry(part.currency)
+                .or_default()
+                .push(part.report);
+        }
+        Ok(currencies
+            .into_iter()
+            .map(|(currency, reports)| CurrencyReport {
+                currency,
+                report: Model::calculate_total_report(&reports),
+            })
+            .collect())
+    }
 
-        sqlite_store.commit().await?;
-        tracing::debug!("commited");
+    /// The transactions dated within the inclusive bounds, as they are now or
+    /// as they were at the given time.
+    async fn get_transactions_as_of(
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        as_of: Option<DateTime<Utc>>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<WithId<Transaction>>, error::Error> {
+        match as_of {
+            Some(at) => sqlite_store.get_transactions_as_of(at, from, to).await,
+            None => sqlite_store.get_transactions_in_period(from, to).await,
+        }
+    }
 
-        Ok(report)
+    /// The report of every transaction as they were at the given time, which
+    /// is what the stored reports of the uploads summed to then.
+    ///
+    /// # Errors
+    pub async fn get_report_as_of(
+        at: DateTime<Utc>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Report, error::Error> {
+        let transactions = sqlite_store.get_transactions_as_of(at, None, None).await?;
+
+        Ok(Model::calculate_balance_from_transactions(
+            transactions.iter().map(|x| &x.data),
+        ))
     }
-}
 
-pub struct CSVReader;
+    ///
+    /// # Errors
+    pub async fn get_category_reports(
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        as_of: Option<DateTime<Utc>>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<CategoryReport>, error::Error> {
+        let transactions = Model::get_transactions_as_of(from, to, as_of, sqlite_store).await?;
 
-impl CSVReader {
-    #[must_use]
-    pub fn read_transaction_from_csv_bytes(
-        bytes: &[u8],
-    ) -> impl StreamExt<Item = Transaction> + '_ {
-        let csv_reader = AsyncReaderBuilder::new()
-            .trim(Trim::All)
-            .comment(Some(b'#'))
-            .has_headers(false)
-            .flexible(true)
-            .create_deserializer(bytes);
-        let records = csv_reader.into_deserialize::<TransactionFromCSV>();
-        records
-            .filter_map(|x| async move {
-                if x.is_err() {
-                    tracing::warn!("{:?}", x);
-                };
-                x.ok()
-            })
-            .filter_map(|x| async move {
-                let x = x.try_into();
-                if x.is_err() {
-                    tracing::warn!("{:?}", x);
-                };
-                tracing::debug!("{:?}", x);
-                x.ok()
-            })
+        Ok(Model::calculate_category_reports(
+            transactions.iter().map(|x| &x.data),
+        ))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::str::FromStr;
+    /// Compares every budget with the net spending of its category over its
+    /// dates, refunds reducing the spending. The budgets are expected in
+    /// order of category and start date, so that unspent amounts roll over
+    /// into the next budget of the same category.
+    pub fn calculate_budget_report<'a>(
+        budgets: &[WithId<Budget>],
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+    ) -> Vec<BudgetLine> {
+        let parts: Vec<_> = transactions
+            .into_iter()
+            .flat_map(Transaction::category_parts)
+            .collect();
 
-    use chrono::NaiveDate;
-    use futures::StreamExt;
-    use rust_decimal_macros::dec;
-    use sqlx::SqlitePool;
+        let mut lines = vec![];
+        let mut rollover: Option<(&str, Decimal)> = None;
+        for WithId { id, data: budget } in budgets {
+            let carried_over = match rollover {
+                Some((category, amount)) if category == budget.category => amount,
+                _ => dec!(0),
+            };
+            let report = Model::calculate_balance_from_transactions(parts.iter().filter(|x| {
+                x.category.as_deref() == Some(budget.category.as_str())
+                    && (budget.start_date..=budget.end_date).contains(&x.date)
+            }));
 
-    use crate::{
-        entity::{Report, Transaction},
-        error,
-        logic::CSVReader,
-        query::SqliteStore,
-    };
+            let planned = budget.amount + carried_over;
+            let actual = report.expenses - report.gross_revenue;
+            let remaining = planned - actual;
+            let percent_used = (planned != dec!(0)).then(|| {
+                (actual / planned * dec!(100))
+                    .round_dp_with_strategy(2, RoundingStrategy::MidpointNearestEven)
+            });
+            rollover = budget
+                .rollover
+                .then(|| (budget.category.as_str(), remaining.max(dec!(0))));
+            lines.push(BudgetLine {
+                budget_id: *id,
+                category: budget.category.clone(),
+                start_date: budget.start_date,
+                end_date: budget.end_date,
+                carried_over,
+                planned,
+                actual,
+                remaining,
+                percent_used,
+            });
+        }
+        lines
+    }
 
-    use super::Model;
+    /// The budget lines overlapping the inclusive bounds. Rollovers still
+    /// account for the budgets before the bounds.
+    ///
+    /// # Errors
+    pub async fn get_budget_report(
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<BudgetLine>, error::Error> {
+        let budgets = sqlite_store.get_budgets().await?;
+        let (Some(start), Some(end)) = (
+            budgets.iter().map(|x| x.data.start_date).min(),
+            budgets.iter().map(|x| x.data.end_date).max(),
+        ) else {
+            return Ok(vec![]);
+        };
+        let transactions = sqlite_store
+            .get_transactions_in_period(Some(start), Some(end))
+            .await?;
 
-    #[tokio::test]
-    async fn valid_csv() {
-        let csv = vec![
-            "2021-07-12, Income, 87.32, first",
-            "2023-08-20, Expense, 12.13, second",
-        ]
-        .join("\n");
-        let expected_transactions = vec![
-            Transaction {
-                date: NaiveDate::from_str("2021-07-12").unwrap(),
-                amount: dec!(87.32),
-                memo: "first".to_string(),
-            },
-            Transaction {
-                date: NaiveDate::from_str("2023-08-20").unwrap(),
-                amount: dec!(-12.13),
-                memo: "second".to_string(),
-            },
-        ];
+        Ok(
+            Model::calculate_budget_report(&budgets, transactions.iter().map(|x| &x.data))
+                .into_iter()
+                .filter(|x| from.is_none_or(|from| x.end_date >= from))
+                .filter(|x| to.is_none_or(|to| x.start_date <= to))
+                .collect(),
+        )
+    }
 
-        let transactions: Vec<Transaction> =
-            CSVReader::read_transaction_from_csv_bytes(csv.as_bytes())
-                .collect()
-                .await;
+    /// Creates a budget, which cannot overlap another budget of the same
+    /// category.
+    ///
+    /// # Errors
+    pub async fn create_budget(
+        budget: Budget,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<Budget>, error::Error> {
+        if !budget.is_valid() {
+            return Err(error::Error::InvalidBudget);
+        }
+        if let Some(other) = sqlite_store
+            .get_budgets()
+            .await?
+            .into_iter()
+            .find(|x| x.data.overlaps(&budget))
+        {
+            return Err(error::Error::OverlappingBudget(other.id));
+        }
 
-        assert_eq!(transactions, expected_transactions);
+        let budget = WithId::from_data(budget);
+        sqlite_store.create_budget(&budget).await?;
+        sqlite_store.commit().await?;
+
+        Ok(budget)
     }
 
-    #[tokio::test]
-    async fn invalid_csv() {
-        let csv = vec![
-            "text",
-            "# comment",
-            "2020-09-12, Income",
-            "2021-07-12, Income, 87.32, first",
-            "2023-08-13, NotExpense, 10.12, third",
-            "2023-08-20, Expense, 12.13, second",
-            "20-08-2023, Income, 10.00, fourth",
-        ]
-        .join("\n");
-        let expected_transactions = vec![
-            Transaction {
-                date: NaiveDate::from_str("2021-07-12").unwrap(),
-                amount: dec!(87.32),
-                memo: "first".to_string(),
-            },
-            Transaction {
-                date: NaiveDate::from_str("2023-08-20").unwrap(),
-                amount: dec!(-12.13),
-                memo: "second".to_string(),
-            },
-        ];
+    /// Compares the transactions of a period with those of a previous period,
+    /// in total and for every category found in either period.
+    pub fn calculate_comparison<'a>(
+        previous: impl IntoIterator<Item = &'a Transaction>,
+        current: impl IntoIterator<Item = &'a Transaction>,
+    ) -> ComparisonReport {
+        let previous: Vec<_> = previous.into_iter().collect();
+        let current: Vec<_> = current.into_iter().collect();
 
-        let transactions: Vec<Transaction> =
-            CSVReader::read_transaction_from_csv_bytes(csv.as_bytes())
-                .collect()
-                .await;
+        let mut categories: BTreeMap<_, (Report, Report)> = BTreeMap::new();
+        for CategoryReport { category, report } in
+            Model::calculate_category_reports(previous.iter().copied())
+        {
+            categories.entry(category).or_default().0 = report;
+        }
+        for CategoryReport { category, report } in
+            Model::calculate_category_reports(current.iter().copied())
+        {
+            categories.entry(category).or_default().1 = report;
+        }
 
-        assert_eq!(transactions, expected_transactions);
+        ComparisonReport {
+            total: Comparison::between(
+                Model::calculate_balance_from_transactions(previous),
+                Model::calculate_balance_from_transactions(current),
+            ),
+            categories: categories
+                .into_iter()
+                .map(|(category, (previous, current))| CategoryComparison {
+                    category,
+                    comparison: Comparison::between(previous, current),
+                })
+                .collect(),
+        }
     }
 
-    #[test]
-    fn balance_from_transactions() {
-        let transactions = vec![
-            Transaction {
-                date: NaiveDate::from_str("2021-07-12").unwrap(),
-                amount: dec!(87.32),
-                memo: "first".to_string(),
-            },
-            Transaction {
-                date: NaiveDate::from_str("2023-08-20").unwrap(),
-                amount: dec!(-12.13),
-                memo: "second".to_string(),
-            },
-        ];
-        let expected_report = Report {
-            gross_revenue: dec!(87.32),
-            expenses: dec!(12.13),
-            net_revenue: dec!(75.19),
-        };
+    /// Compares two periods, each given by its inclusive bounds.
+    ///
+    /// # Errors
+    pub async fn get_comparison(
+        previous: (NaiveDate, NaiveDate),
+        current: (NaiveDate, NaiveDate),
+        as_of: Option<DateTime<Utc>>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<ComparisonReport, error::Error> {
+        let previous =
+            Model::get_transactions_as_of(Some(previous.0), Some(previous.1), as_of, sqlite_store)
+                .await?;
+        let current =
+            Model::get_transactions_as_of(Some(current.0), Some(current.1), as_of, sqlite_store)
+                .await?;
 
-        let report = Model::calculate_balance_from_transactions(transactions.iter());
+        Ok(Model::calculate_comparison(
+            previous.iter().map(|x| &x.data),
+            current.iter().map(|x| &x.data),
+        ))
+    }
 
-        assert_eq!(report, expected_report);
+    /// The payee of a memo: the payee of the alias of its normalized memo, or
+    /// the normalized memo itself.
+    #[must_use]
+    pub fn payee_of(memo: &str, aliases: &HashMap<String, String>) -> String {
+        let memo = Payee::normalize_memo(memo);
+        aliases.get(&memo).cloned().unwrap_or(memo)
     }
 
-    #[test]
-    fn total_reports() {
-        let reports = vec![
-            Report {
-                gross_revenue: dec!(87.32),
-                expenses: dec!(12.13),
-                net_revenue: dec!(75.19),
-            },
-            Report {
-                gross_revenue: dec!(10.01),
-                expenses: dec!(2.05),
-                net_revenue: dec!(7.96),
-            },
-        ];
-        let expected_report = Report {
-            gross_revenue: dec!(97.33),
-            expenses: dec!(14.18),
-            net_revenue: dec!(83.15),
+    fn group_by_payee<'a>(
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+        aliases: &[PayeeAlias],
+    ) -> BTreeMap<String, Vec<&'a Transaction>> {
+        let aliases: HashMap<_, _> = aliases
+            .iter()
+            .map(|x| (x.memo.clone(), x.payee.clone()))
+            .collect();
+
+        let mut payees: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for transaction in transactions {
+            payees
+                .entry(Model::payee_of(&transaction.memo, &aliases))
+                .or_default()
+                .push(transaction);
+        }
+        payees
+    }
+
+    /// Ranks the payees by total income and by total expenses, largest
+    /// first, leaving out payees without any.
+    pub fn calculate_payee_ranking<'a>(
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+        aliases: &[PayeeAlias],
+    ) -> PayeeRanking {
+        let reports: Vec<_> = Model::group_by_payee(transactions, aliases)
+            .into_iter()
+            .map(|(payee, transactions)| {
+                (
+                    payee,
+                    Model::calculate_balance_from_transactions(transactions),
+                )
+            })
+            .collect();
+
+        let rank = |total: fn(&Report) -> Decimal| {
+            let mut totals: Vec<_> = reports
+                .iter()
+                .filter(|(_, report)| total(report) > dec!(0))
+                .map(|(payee, report)| PayeeTotal {
+                    payee: payee.clone(),
+                    total: total(report),
+                })
+                .collect();
+            totals.sort_by_key(|x| Reverse(x.total));
+            totals
         };
 
-        let report = Model::calculate_total_report(reports.iter());
+        PayeeRanking {
+            income: rank(|x| x.gross_revenue),
+            expenses: rank(|x| x.expenses),
+        }
+    }
 
-        assert_eq!(report, expected_report);
+    ///
+    /// # Errors
+    pub async fn get_payee_ranking(
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<PayeeRanking, error::Error> {
+        let aliases = sqlite_store.get_payee_aliases().await?;
+        let transactions = sqlite_store.get_transactions_in_period(from, to).await?;
+
+        Ok(Model::calculate_payee_ranking(
+            transactions.iter().map(|x| &x.data),
+            &aliases,
+        ))
     }
 
-    #[sqlx::test]
-    async fn commit_transactions(pool: SqlitePool) -> Result<(), error::Error> {
-        let tx = pool.begin().await?;
-        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+    /// Every payee with its number of transactions.
+    ///
+    /// # Errors
+    pub async fn get_payees(
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<Payee>, error::Error> {
+        let aliases = sqlite_store.get_payee_aliases().await?;
+        let transactions = sqlite_store.get_transactions().await?;
 
-        let transactions = vec![
-            Transaction {
-                date: NaiveDate::from_str("2021-07-12").unwrap(),
-                amount: dec!(87.32),
-                memo: "first".to_string(),
-            },
-            Transaction {
-                date: NaiveDate::from_str("2023-08-20").unwrap(),
-                amount: dec!(-12.13),
-                memo: "second".to_string(),
-            },
-        ];
-        let expected_report = Report {
-            gross_revenue: dec!(87.32),
-            expenses: dec!(12.13),
-            net_revenue: dec!(75.19),
+        Ok(
+            Model::group_by_payee(transactions.iter().map(|x| &x.data), &aliases)
+                .into_iter()
+                .map(|(name, transactions)| Payee {
+                    name,
+                    transactions: transactions.len(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Makes every transaction whose memo normalizes like the memo of the
+    /// alias belong to its payee.
+    ///
+    /// # Errors
+    pub async fn set_payee_alias(
+        alias: PayeeAlias,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<PayeeAlias, error::Error> {
+        let alias = PayeeAlias {
+            memo: Payee::normalize_memo(&alias.memo),
+            payee: alias.payee.trim().to_owned(),
         };
+        if alias.payee.is_empty() {
+            return Err(error::Error::InvalidPayeeAlias);
+        }
+        sqlite_store.upsert_payee_alias(&alias).await?;
+        sqlite_store.commit().await?;
 
-        let report = Model::commit_transactions(&transactions, sqlite_store).await?;
+        Ok(alias)
+    }
 
-        assert_eq!(report, expected_report);
+    /// Estimates the tax of every quarter of the year from its transactions.
+    ///
+    /// # Errors
+    pub fn calculate_tax_report<'a>(
+        year: i32,
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+        config: &TaxConfig,
+    ) -> Result<TaxReport, error::Error> {
+        let parts: Vec<_> = transactions
+            .into_iter()
+            .flat_map(Transaction::category_parts)
+            .collect();
 
-        let tx = pool.begin().await?;
-        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let mut quarters = vec![];
+        let mut cumulative_taxable_income = dec!(0);
+        let mut instalments = dec!(0);
+        for quarter in 1..=4 {
+            let start_date = NaiveDate::from_ymd_opt(year, quarter * 3 - 2, 1)
+                .ok_or(error::Error::InvalidYear(year))?;
+            let end_date = start_date
+                .checked_add_months(Months::new(3))
+                .and_then(|x| x.pred_opt())
+                .ok_or(error::Error::InvalidYear(year))?;
+            let in_quarter: Vec<_> = parts
+                .iter()
+                .filter(|x| (start_date..=end_date).contains(&x.date))
+                .collect();
 
-        let report_from_store = sqlite_store.get_reports().await?;
+            let report = Model::calculate_balance_from_transactions(in_quarter.iter().copied());
+            let deductible_expenses =
+                Model::calculate_balance_from_transactions(in_quarter.into_iter().filter(|x| {
+                    x.category
+                        .as_ref()
+                        .is_some_and(|x| config.deductible_categories.contains(x))
+                }))
+                .expenses;
+            let taxable_income = report.gross_revenue - deductible_expenses;
+            cumulative_taxable_income += taxable_income;
+            let tax_so_far = config
+                .rates
+                .tax_on(cumulative_taxable_income)
+                .round_dp_with_strategy(2, RoundingStrategy::MidpointNearestEven);
 
-        assert_eq!(report_from_store.len(), 1);
-        assert_eq!(report_from_store[0], expected_report);
+            let tax = (tax_so_far - instalments).max(dec!(0));
+            instalments += tax;
+
+            quarters.push(QuarterlyTax {
+                quarter,
+                start_date,
+                end_date,
+                report,
+                deductible_expenses,
+                taxable_income,
+                cumulative_taxable_income,
+                tax,
+                cumulative_tax: tax_so_far,
+            });
+        }
+
+        Ok(TaxReport {
+            year,
+            deductible_categories: config.deductible_categories.clone(),
+            quarters,
+        })
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_tax_report(
+        year: i32,
+        config: &Config,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<TaxReport, error::Error> {
+        let from = NaiveDate::from_ymd_opt(year, 1, 1).ok_or(error::Error::InvalidYear(year))?;
+        let to = NaiveDate::from_ymd_opt(year, 12, 31).ok_or(error::Error::InvalidYear(year))?;
+        let transactions = sqlite_store
+            .get_transactions_in_period(Some(from), Some(to))
+            .await?;
+
+        Model::calculate_tax_report(year, transactions.iter().map(|x| &x.data), &config.tax)
+    }
+
+    /// Sums the tax included in the transactions of the period that carry a
+    /// tax code, at the rate of the code on the date of each transaction.
+    /// Transfers move money between accounts and are left out.
+    pub fn calculate_vat_return<'a>(
+        from: NaiveDate,
+        to: NaiveDate,
+        transactions: impl IntoIterator<Item = &'a WithId<Transaction>>,
+        config: &VatConfig,
+    ) -> VatReturn {
+        let mut lines: BTreeMap<&str, VatLine> = BTreeMap::new();
+        let mut unrated = vec![];
+        for WithId { id, data } in transactions {
+            let Some(tax_code) = &data.tax_code else {
+                continue;
+            };
+            if !(from..=to).contains(&data.date) || data.kind == TransactionKind::Transfer {
+                continue;
+            }
+            let Some(rate) = config.rate(tax_code, data.date) else {
+                unrated.push(*id);
+                continue;
+            };
+            let (net, tax) = VatConfig::split(data.amount, rate);
+            let line = lines.entry(tax_code).or_insert_with(|| VatLine {
+                tax_code: tax_code.clone(),
+                sales_net: dec!(0),
+                output_tax: dec!(0),
+                purchases_net: dec!(0),
+                input_tax: dec!(0),
+            });
+            if data.amount > dec!(0) {
+                line.sales_net += net;
+                line.output_tax += tax;
+            } else {
+                line.purchases_net -= net;
+                line.input_tax -= tax;
+            }
+        }
+
+        let lines: Vec<_> = lines.into_values().collect();
+        let output_tax = lines.iter().map(|x| x.output_tax).sum();
+        let input_tax = lines.iter().map(|x| x.input_tax).sum();
+        VatReturn {
+            from,
+            to,
+            lines,
+            output_tax,
+            input_tax,
+            net_tax: output_tax - input_tax,
+            unrated,
+        }
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_vat_return(
+        from: NaiveDate,
+        to: NaiveDate,
+        config: &Config,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<VatReturn, error::Error> {
+        let transactions = sqlite_store
+            .get_transactions_in_period(Some(from), Some(to))
+            .await?;
+
+        Ok(Model::calculate_vat_return(
+            from,
+            to,
+            &transactions,
+            &config.vat,
+        ))
+    }
+
+    /// Computes the deductible amount of the trips at the rate of their year,
+    /// rounded half to even to 2 decimal places per year, next to the
+    /// expenses of the vehicle. An expense belongs to the vehicle when its
+    /// category, or else its memo, is one of the configured vehicle expenses.
+    pub fn calculate_mileage_report<'a>(
+        trips: &[WithId<Trip>],
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+        config: &MileageConfig,
+    ) -> MileageReport {
+        let mut distances: BTreeMap<i32, Decimal> = BTreeMap::new();
+        let mut unrated = vec![];
+        for WithId { id, data } in trips {
+            let year = data.date.year();
+            if config.rates.contains_key(&year) {
+                *distances.entry(year).or_default() += data.distance;
+            } else {
+                unrated.push(*id);
+            }
+        }
+        let years: Vec<_> = distances
+            .into_iter()
+            .map(|(year, distance)| {
+                let rate = config.rates[&year];
+                MileageYear {
+                    year,
+                    distance,
+                    rate,
+                    deductible: (distance * rate)
+                        .round_dp_with_strategy(2, RoundingStrategy::MidpointNearestEven),
+                }
+            })
+            .collect();
+
+        let mut expenses: BTreeMap<&str, Vec<Transaction>> = BTreeMap::new();
+        for part in transactions
+            .into_iter()
+            .flat_map(Transaction::category_parts)
+            .filter(|x| x.amount < dec!(0))
+        {
+            let label = part.category.as_deref().unwrap_or(&part.memo);
+            if let Some(name) = config
+                .vehicle_expenses
+                .iter()
+                .find(|x| x.eq_ignore_ascii_case(label))
+            {
+                expenses.entry(name).or_default().push(part);
+            }
+        }
+
+        MileageReport {
+            trips: trips.len(),
+            distance: trips.iter().map(|x| x.data.distance).sum(),
+            deductible: years.iter().map(|x| x.deductible).sum(),
+            years,
+            unrated,
+            vehicle_expenses: Model::calculate_balance_from_transactions(
+                expenses.values().flatten(),
+            ),
+            expenses: expenses
+                .iter()
+                .map(|(name, parts)| CategoryReport {
+                    category: Some((*name).to_owned()),
+                    report: Model::calculate_balance_from_transactions(parts),
+                })
+                .collect(),
+        }
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_mileage_report(
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        config: &Config,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<MileageReport, error::Error> {
+        let trips = sqlite_store.get_trips(from, to).await?;
+        let transactions = sqlite_store.get_transactions_in_period(from, to).await?;
+
+        Ok(Model::calculate_mileage_report(
+            &trips,
+            transactions.iter().map(|x| &x.data),
+            &config.mileage,
+        ))
+    }
+
+    async fn ensure_trips_linked(
+        trips: &[WithId<Trip>],
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        for transaction_id in trips.iter().filter_map(|x| x.data.transaction_id) {
+            let transaction = sqlite_store.get_transaction(transaction_id).await?;
+            if transaction.data.kind != TransactionKind::Regular
+                || transaction.data.amount <= dec!(0)
+            {
+                return Err(error::Error::TripTransactionNotIncome(transaction_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records trips, whose linked transactions must be existing income.
+    ///
+    /// # Errors
+    pub async fn create_trips(
+        trips: Vec<Trip>,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<Vec<WithId<Trip>>, error::Error> {
+        let trips: Vec<_> = trips.into_iter().map(WithId::from_data).collect();
+        Model::ensure_trips_linked(&trips, &mut sqlite_store).await?;
+        sqlite_store.create_trips(&trips).await?;
+        sqlite_store.commit().await?;
+
+        Ok(trips)
+    }
+
+    ///
+    /// # Errors
+    pub async fn create_trip(
+        entry: TripEntry,
+        sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<Trip>, error::Error> {
+        let trip = Trip::try_from(entry)?;
+        let mut trips = Model::create_trips(vec![trip], sqlite_store).await?;
+        Ok(trips.remove(0))
+    }
+
+    ///
+    /// # Errors
+    pub async fn update_trip(
+        id: Uuid,
+        entry: TripEntry,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<Trip>, error::Error> {
+        let trip = WithId {
+            id,
+            data: Trip::try_from(entry)?,
+        };
+        Model::ensure_trips_linked(std::slice::from_ref(&trip), &mut sqlite_store).await?;
+        sqlite_store.update_trip(&trip).await?;
+        sqlite_store.commit().await?;
+
+        Ok(trip)
+    }
+
+    ///
+    /// # Errors
+    pub async fn delete_trip(
+        id: Uuid,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        sqlite_store.delete_trip(id).await?;
+        sqlite_store.commit().await
+    }
+
+    /// Attaches a file to a transaction. Only images and PDFs up to the
+    /// configured size are accepted.
+    ///
+    /// # Errors
+    pub async fn add_attachment(
+        transaction_id: Uuid,
+        file_name: &str,
+        content: Vec<u8>,
+        config: &Config,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<Attachment>, error::Error> {
+        let max_size = config.max_attachment_size();
+        if content.len() > max_size {
+            return Err(error::Error::AttachmentTooLarge(max_size));
+        }
+        let mime_type =
+            Attachment::sniff_mime_type(&content).ok_or(error::Error::UnsupportedAttachment)?;
+        sqlite_store.get_transaction(transaction_id).await?;
+
+        let attachment = WithId::from_data(Attachment {
+            transaction_id,
+            file_name: Attachment::sanitize_file_name(file_name),
+            mime_type: mime_type.to_owned(),
+            size: i64::try_from(content.len())
+                .map_err(|_| error::Error::AttachmentTooLarge(max_size))?,
+            hash: hex::encode(Sha256::digest(&content)),
+            uploaded_at: Utc::now(),
+        });
+        sqlite_store.create_attachment(&attachment, content).await?;
+        sqlite_store.commit().await?;
+
+        Ok(attachment)
+    }
+
+    /// An attachment along with its content.
+    ///
+    /// # Errors
+    pub async fn get_attachment_file(
+        id: Uuid,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<(Attachment, Vec<u8>), error::Error> {
+        let attachment = sqlite_store.get_attachment(id).await?;
+        let content = sqlite_store
+            .get_attachment_content(&attachment.data.hash)
+            .await?;
+
+        Ok((attachment.data, content))
+    }
+
+    /// Lists the expenses above the threshold, in magnitude, that have no
+    /// attachment, in order of date. Transfers are not expenses and are left
+    /// out.
+    pub fn calculate_missing_receipts(
+        threshold: Decimal,
+        transactions: impl IntoIterator<Item = WithId<Transaction>>,
+        attached: &HashSet<Uuid>,
+    ) -> MissingReceipts {
+        let mut transactions: Vec<_> = transactions
+            .into_iter()
+            .filter(|x| {
+                x.data.kind == TransactionKind::Regular
+                    && -x.data.amount > threshold
+                    && !attached.contains(&x.id)
+            })
+            .collect();
+        transactions.sort_by_key(|x| x.data.date);
+
+        MissingReceipts {
+            threshold,
+            total: -transactions.iter().map(|x| x.data.amount).sum::<Decimal>(),
+            transactions,
+        }
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_missing_receipts(
+        threshold: Decimal,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<MissingReceipts, error::Error> {
+        let transactions = sqlite_store.get_transactions_in_period(from, to).await?;
+        let attached = sqlite_store.get_attached_transaction_ids().await?;
+
+        Ok(Model::calculate_missing_receipts(
+            threshold,
+            transactions,
+            &attached,
+        ))
+    }
+
+    /// Ages what is still owed on every item, given as the customer or vendor
+    /// owing or owed, the due date and the outstanding amount. Lines follow
+    /// the order of the parties and only parties with a balance are listed.
+    pub fn calculate_aging(
+        as_of: NaiveDate,
+        parties: &[(Uuid, String)],
+        items: impl IntoIterator<Item = (Uuid, NaiveDate, Decimal)>,
+    ) -> AgingReport {
+        let mut buckets: HashMap<Uuid, AgingBuckets> = HashMap::new();
+        let mut total = AgingBuckets::default();
+        for (party_id, due_date, outstanding) in items {
+            let days_past_due = (as_of - due_date).num_days();
+            buckets
+                .entry(party_id)
+                .or_default()
+                .add(days_past_due, outstanding);
+            total.add(days_past_due, outstanding);
+        }
+
+        AgingReport {
+            as_of,
+            lines: parties
+                .iter()
+                .filter_map(|(id, name)| {
+                    buckets.remove(id).map(|buckets| AgingLine {
+                        id: *id,
+                        name: name.clone(),
+                        buckets,
+                    })
+                })
+                .collect(),
+            total,
+        }
+    }
+
+    /// The amount of a payment, which defaults to as much of the transaction
+    /// as is left to allocate, up to what is outstanding.
+    fn payment_amount(
+        requested: Option<Decimal>,
+        available: Decimal,
+        outstanding: Decimal,
+    ) -> Result<Decimal, error::Error> {
+        let amount = requested.unwrap_or(available.min(outstanding));
+        if amount <= dec!(0) {
+            return Err(error::Error::InvalidPayment(
+                "nothing left to allocate".to_owned(),
+            ));
+        }
+        if amount > available {
+            return Err(error::Error::InvalidPayment(format!(
+                "only {available} of the transaction is left to allocate"
+            )));
+        }
+        if amount > outstanding {
+            return Err(error::Error::InvalidPayment(format!(
+                "only {outstanding} is outstanding"
+            )));
+        }
+        Ok(amount)
+    }
+
+    ///
+    /// # Errors
+    pub async fn create_customer(
+        customer: Customer,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<Customer>, error::Error> {
+        if customer.name.trim().is_empty() {
+            return Err(error::Error::InvalidCustomer);
+        }
+
+        let customer = WithId::from_data(customer);
+        sqlite_store.create_customer(&customer).await?;
+        sqlite_store.commit().await?;
+
+        Ok(customer)
+    }
+
+    /// Creates an invoice of an existing customer, as a draft.
+    ///
+    /// # Errors
+    pub async fn create_invoice(
+        mut invoice: Invoice,
+        today: NaiveDate,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<InvoiceSummary, error::Error> {
+        if !invoice.is_valid() {
+            return Err(error::Error::InvalidInvoice);
+        }
+        sqlite_store.get_customer(invoice.customer_id).await?;
+
+        invoice.status = InvoiceStatus::Draft;
+        let invoice = WithId::from_data(invoice);
+        sqlite_store.create_invoice(&invoice).await?;
+        sqlite_store.commit().await?;
+
+        Ok(Model::calculate_invoice_summary(invoice, vec![], today))
+    }
+
+    /// Sums the payments of an invoice and derives its status on the given
+    /// day.
+    #[must_use]
+    pub fn calculate_invoice_summary(
+        invoice: WithId<Invoice>,
+        payments: Vec<WithId<Payment>>,
+        today: NaiveDate,
+    ) -> InvoiceSummary {
+        let total = invoice.data.total();
+        let paid: Decimal = payments.iter().map(|x| x.data.amount).sum();
+        let outstanding = total - paid;
+        let status = match invoice.data.status {
+            InvoiceStatus::Draft => InvoiceStatus::Draft,
+            _ if outstanding <= dec!(0) => InvoiceStatus::Paid,
+            _ if invoice.data.due_date < today => InvoiceStatus::Overdue,
+            _ => InvoiceStatus::Sent,
+        };
+
+        InvoiceSummary {
+            invoice,
+            total,
+            paid,
+            outstanding,
+            status,
+            payments,
+        }
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_invoices(
+        today: NaiveDate,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<InvoiceSummary>, error::Error> {
+        Model::summarize_invoices(today, None, sqlite_store).await
+    }
+
+    /// Summarizes every invoice on the given day, with only the payments of
+    /// transactions dated on or before `paid_through`, if any.
+    async fn summarize_invoices(
+        today: NaiveDate,
+        paid_through: Option<NaiveDate>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<InvoiceSummary>, error::Error> {
+        let invoices = sqlite_store.get_invoices().await?;
+        let mut payments = sqlite_store.get_invoice_payments(paid_through).await?;
+
+        Ok(invoices
+            .into_iter()
+            .map(|x| {
+                let payments = payments.remove(&x.id).unwrap_or_default();
+                Model::calculate_invoice_summary(x, payments, today)
+            })
+            .collect())
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_invoice(
+        id: Uuid,
+        today: NaiveDate,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<InvoiceSummary, error::Error> {
+        let invoice = sqlite_store.get_invoice(id).await?;
+        let payments = sqlite_store.get_payments_of_invoice(id).await?;
+
+        Ok(Model::calculate_invoice_summary(invoice, payments, today))
+    }
+
+    /// Marks a draft invoice as sent to the customer.
+    ///
+    /// # Errors
+    pub async fn send_invoice(
+        id: Uuid,
+        today: NaiveDate,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<InvoiceSummary, error::Error> {
+        let invoice = sqlite_store.get_invoice(id).await?;
+        if invoice.data.status != InvoiceStatus::Draft {
+            return Err(error::Error::InvalidInvoiceStatus(
+                invoice.data.status.as_str().to_owned(),
+            ));
+        }
+
+        sqlite_store
+            .set_invoice_status(id, InvoiceStatus::Sent)
+            .await?;
+        let summary = Model::get_invoice(id, today, &mut sqlite_store).await?;
+        sqlite_store.commit().await?;
+
+        Ok(summary)
+    }
+
+    /// Records part or all of an income transaction as a payment of a sent
+    /// invoice. A transaction can pay several invoices and an invoice can be
+    /// paid by several transactions, but neither can be allocated more than
+    /// its amount.
+    ///
+    /// # Errors
+    pub async fn pay_invoice(
+        id: Uuid,
+        transaction_id: Uuid,
+        amount: Option<Decimal>,
+        today: NaiveDate,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<InvoiceSummary, error::Error> {
+        let summary = Model::get_invoice(id, today, &mut sqlite_store).await?;
+        if summary.status == InvoiceStatus::Draft {
+            return Err(error::Error::InvoiceNotSent(id));
+        }
+        let transaction = sqlite_store.get_transaction(transaction_id).await?;
+        if transaction.data.kind != TransactionKind::Regular || transaction.data.amount <= dec!(0) {
+            return Err(error::Error::InvalidPayment(
+                "only income can pay an invoice".to_owned(),
+            ));
+        }
+        let allocated: Decimal = sqlite_store
+            .get_invoice_payments_of_transaction(transaction_id)
+            .await?
+            .iter()
+            .map(|x| x.data.amount)
+            .sum();
+        let amount = Model::payment_amount(
+            amount,
+            transaction.data.amount - allocated,
+            summary.outstanding,
+        )?;
+
+        let payment = WithId::from_data(Payment {
+            transaction_id,
+            amount,
+        });
+        sqlite_store.create_invoice_payment(id, &payment).await?;
+        let summary = Model::get_invoice(id, today, &mut sqlite_store).await?;
+        sqlite_store.commit().await?;
+
+        Ok(summary)
+    }
+
+    /// Ages the balances still owed on the sent invoices issued by the given
+    /// date, by customer. Only the payments of transactions dated by then
+    /// count, and the invoices are overdue or paid as they were that day.
+    ///
+    /// # Errors
+    pub async fn get_receivables_aging(
+        as_of: NaiveDate,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<AgingReport, error::Error> {
+        let customers: Vec<_> = sqlite_store
+            .get_customers()
+            .await?
+            .into_iter()
+            .map(|x| (x.id, x.data.name))
+            .collect();
+        let invoices = Model::summarize_invoices(as_of, Some(as_of), sqlite_store).await?;
+
+        Ok(Model::calculate_aging(
+            as_of,
+            &customers,
+            invoices
+                .iter()
+                .filter(|x| {
+                    x.status != InvoiceStatus::Draft
+                        && x.invoice.data.issue_date <= as_of
+                        && x.outstanding > dec!(0)
+                })
+                .map(|x| {
+                    (
+                        x.invoice.data.customer_id,
+                        x.invoice.data.due_date,
+                        x.outstanding,
+                    )
+                }),
+        ))
+    }
+
+    ///
+    /// # Errors
+    pub async fn create_vendor(
+        vendor: Vendor,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<Vendor>, error::Error> {
+        if vendor.name.trim().is_empty() {
+            return Err(error::Error::InvalidVendor);
+        }
+
+        let vendor = WithId::from_data(vendor);
+        sqlite_store.create_vendor(&vendor).await?;
+        sqlite_store.commit().await?;
+
+        Ok(vendor)
+    }
+
+    /// Records a bill of an existing vendor.
+    ///
+    /// # Errors
+    pub async fn create_bill(
+        bill: Bill,
+        today: NaiveDate,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<BillSummary, error::Error> {
+        if !bill.is_valid() {
+            return Err(error::Error::InvalidBill);
+        }
+        sqlite_store.get_vendor(bill.vendor_id).await?;
+
+        let bill = WithId::from_data(bill);
+        sqlite_store.create_bill(&bill).await?;
+        sqlite_store.commit().await?;
+
+        Ok(Model::calculate_bill_summary(bill, vec![], today))
+    }
+
+    /// Sums the payments of a bill and derives its status on the given day.
+    #[must_use]
+    pub fn calculate_bill_summary(
+        bill: WithId<Bill>,
+        payments: Vec<WithId<Payment>>,
+        today: NaiveDate,
+    ) -> BillSummary {
+        let paid: Decimal = payments.iter().map(|x| x.data.amount).sum();
+        let outstanding = bill.data.amount - paid;
+        let status = if outstanding <= dec!(0) {
+            BillStatus::Paid
+        } else if bill.data.due_date < today {
+            BillStatus::Overdue
+        } else {
+            BillStatus::Open
+        };
+
+        BillSummary {
+            bill,
+            paid,
+            outstanding,
+            status,
+            payments,
+        }
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_bills(
+        today: NaiveDate,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<BillSummary>, error::Error> {
+        Model::summarize_bills(today, None, sqlite_store).await
+    }
+
+    /// Summarizes every bill on the given day, with only the payments of
+    /// transactions dated on or before `paid_through`, if any.
+    async fn summarize_bills(
+        today: NaiveDate,
+        paid_through: Option<NaiveDate>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<BillSummary>, error::Error> {
+        let bills = sqlite_store.get_bills().await?;
+        let mut payments = sqlite_store.get_bill_payments(paid_through).await?;
+
+        Ok(bills
+            .into_iter()
+            .map(|x| {
+                let payments = payments.remove(&x.id).unwrap_or_default();
+                Model::calculate_bill_summary(x, payments, today)
+            })
+            .collect())
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_bill(
+        id: Uuid,
+        today: NaiveDate,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<BillSummary, error::Error> {
+        let bill = sqlite_store.get_bill(id).await?;
+        let payments = sqlite_store.get_payments_of_bill(id).await?;
+
+        Ok(Model::calculate_bill_summary(bill, payments, today))
+    }
+
+    /// Records part or all of an expense transaction as a payment of a bill,
+    /// with the same rules as the payments of invoices.
+    ///
+    /// # Errors
+    pub async fn pay_bill(
+        id: Uuid,
+        transaction_id: Uuid,
+        amount: Option<Decimal>,
+        today: NaiveDate,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<BillSummary, error::Error> {
+        let summary = Model::get_bill(id, today, &mut sqlite_store).await?;
+        let transaction = sqlite_store.get_transaction(transaction_id).await?;
+        if transaction.data.kind != TransactionKind::Regular || transaction.data.amount >= dec!(0) {
+            return Err(error::Error::InvalidPayment(
+                "only expenses can pay a bill".to_owned(),
+            ));
+        }
+        let allocated: Decimal = sqlite_store
+            .get_bill_payments_of_transaction(transaction_id)
+            .await?
+            .iter()
+            .map(|x| x.data.amount)
+            .sum();
+        let amount = Model::payment_amount(
+            amount,
+            -transaction.data.amount - allocated,
+            summary.outstanding,
+        )?;
+
+        let payment = WithId::from_data(Payment {
+            transaction_id,
+            amount,
+        });
+        sqlite_store.create_bill_payment(id, &payment).await?;
+        let summary = Model::get_bill(id, today, &mut sqlite_store).await?;
+        sqlite_store.commit().await?;
+
+        Ok(summary)
+    }
+
+    /// Ages the balances still owed on the bills issued by the given date, by
+    /// vendor. Only the payments of transactions dated by then count.
+    ///
+    /// # Errors
+    pub async fn get_payables_aging(
+        as_of: NaiveDate,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<AgingReport, error::Error> {
+        let vendors: Vec<_> = sqlite_store
+            .get_vendors()
+            .await?
+            .into_iter()
+            .map(|x| (x.id, x.data.name))
+            .collect();
+        let bills = Model::summarize_bills(as_of, Some(as_of), sqlite_store).await?;
+
+        Ok(Model::calculate_aging(
+            as_of,
+            &vendors,
+            bills
+                .iter()
+                .filter(|x| x.bill.data.issue_date <= as_of && x.outstanding > dec!(0))
+                .map(|x| (x.bill.data.vendor_id, x.bill.data.due_date, x.outstanding)),
+        ))
+    }
+
+    /// Bills still owed that fall due from today through the given number of
+    /// days, in order of due date. Overdue bills are in the payables aging.
+    ///
+    /// # Errors
+    pub async fn get_upcoming_bills(
+        today: NaiveDate,
+        days: u64,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<BillSummary>, error::Error> {
+        let until = today
+            .checked_add_days(Days::new(days))
+            .ok_or(error::Error::InvalidUpcomingDays(days))?;
+
+        Ok(Model::get_bills(today, sqlite_store)
+            .await?
+            .into_iter()
+            .filter(|x| {
+                x.status == BillStatus::Open && (today..=until).contains(&x.bill.data.due_date)
+            })
+            .collect())
+    }
+
+    /// Converts every transaction into `currency` at the rate of its date.
+    /// Transactions without a rate are reported per currency, unconverted.
+    pub fn calculate_converted_report<'a>(
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+        rates: &ExchangeRates,
+        currency: &str,
+    ) -> ConvertedReport {
+        let mut converted = vec![];
+        let mut unconverted = vec![];
+        for transaction in transactions {
+            match rates.convert(
+                transaction.amount,
+                &transaction.currency,
+                currency,
+                transaction.date,
+            ) {
+                Some(amount) => converted.push(Transaction {
+                    amount,
+                    currency: currency.to_owned(),
+                    ..transaction.clone()
+                }),
+                None => unconverted.push(transaction),
+            }
+        }
+
+        ConvertedReport {
+            currency: currency.to_owned(),
+            report: Model::calculate_balance_from_transactions(&converted),
+            unconverted: Model::calculate_currency_reports(unconverted),
+        }
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_converted_report(
+        currency: &str,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<ConvertedReport, error::Error> {
+        if !Account::is_valid_currency(currency) {
+            return Err(error::Error::InvalidCurrency(currency.to_owned()));
+        }
+        let transactions = sqlite_store.get_transactions_in_period(from, to).await?;
+        let rates = ExchangeRates::from_rates(sqlite_store.get_exchange_rates(from, to).await?);
+
+        Ok(Model::calculate_converted_report(
+            transactions.iter().map(|x| &x.data),
+            &rates,
+            currency,
+        ))
+    }
+
+    ///
+    /// # Errors
+    pub async fn commit_exchange_rates(
+        rates: &[ExchangeRate],
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        sqlite_store.upsert_exchange_rates(rates).await?;
+        sqlite_store.commit().await
+    }
+
+    /// Fetches a transaction that is about to be changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`error::Error::TransactionReconciled`] if the transaction is
+    /// part of a finished reconciliation.
+    async fn get_editable_transaction(
+        id: Uuid,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<WithId<Transaction>, error::Error> {
+        let transaction = sqlite_store.get_transaction(id).await?;
+        if transaction.data.reconciliation_id.is_some() {
+            return Err(error::Error::TransactionReconciled(id));
+        }
+        Ok(transaction)
+    }
+
+    /// Deletes a transaction and recomputes the report of its upload. Its
+    /// versions are kept, so reports as of an earlier time still include it.
+    /// Transfer legs and transactions paying invoices or bills cannot be
+    /// deleted.
+    ///
+    /// # Errors
+    pub async fn delete_transaction(
+        id: Uuid,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        let transaction = Model::get_editable_transaction(id, &mut sqlite_store).await?;
+        if transaction.data.transfer_id.is_some()
+            || !sqlite_store
+                .get_invoice_payments_of_transaction(id)
+                .await?
+                .is_empty()
+            || !sqlite_store
+                .get_bill_payments_of_transaction(id)
+                .await?
+                .is_empty()
+        {
+            return Err(error::Error::TransactionInUse(id));
+        }
+
+        sqlite_store.delete_transaction(id).await?;
+        Model::project_pending_events(&mut sqlite_store).await?;
+        sqlite_store.commit().await
+    }
+
+    ///
+    /// # Errors
+    pub async fn set_category(
+        id: Uuid,
+        category: Option<String>,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        let mut transaction = Model::get_editable_transaction(id, &mut sqlite_store).await?;
+        transaction.data.category = category;
+        sqlite_store.update_transaction(&transaction).await?;
+        Model::project_pending_events(&mut sqlite_store).await?;
+        sqlite_store.commit().await
+    }
+
+    /// Sets the tax code of a transaction, which must be one of the configured
+    /// codes.
+    ///
+    /// # Errors
+    pub async fn set_tax_code(
+        id: Uuid,
+        tax_code: Option<String>,
+        config: &Config,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        if let Some(tax_code) = tax_code.as_ref().filter(|x| !config.vat.has_code(x)) {
+            return Err(error::Error::UnknownTaxCode(tax_code.clone()));
+        }
+        let mut transaction = Model::get_editable_transaction(id, &mut sqlite_store).await?;
+        transaction.data.tax_code = tax_code;
+        sqlite_store.update_transaction(&transaction).await?;
+        Model::project_pending_events(&mut sqlite_store).await?;
+        sqlite_store.commit().await
+    }
+
+    /// Replaces the splits of a transaction, an empty list removing them.
+    ///
+    /// # Errors
+    pub async fn set_splits(
+        id: Uuid,
+        splits: Vec<Split>,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        let mut transaction = Model::get_editable_transaction(id, &mut sqlite_store).await?;
+        transaction.data.splits = splits;
+        transaction.data.validate_splits()?;
+        sqlite_store
+            .set_transaction_splits(id, &transaction.data.splits)
+            .await?;
+        Model::project_pending_events(&mut sqlite_store).await?;
+        sqlite_store.commit().await
+    }
+
+    /// Balance of an account after all of its transactions, starting from its
+    /// opening balance. Transactions dated before the opening date are already
+    /// part of the opening balance and are left out.
+    pub fn calculate_account_balance<'a>(
+        account: &Account,
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+    ) -> Decimal {
+        transactions
+            .into_iter()
+            .filter(|x| x.date >= account.opening_date)
+            .fold(account.opening_balance, |balance, transaction| {
+                balance + transaction.amount
+            })
+    }
+
+    /// The accounts of the user, without the accounts of the ledger itself.
+    ///
+    /// # Errors
+    pub async fn get_accounts(
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<WithId<Account>>, error::Error> {
+        Ok(sqlite_store
+            .get_accounts()
+            .await?
+            .into_iter()
+            .filter(|x| !Account::is_system(x.id))
+            .collect())
+    }
+
+    ///
+    /// # Errors
+    pub async fn create_account(
+        account: Account,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<Account>, error::Error> {
+        if !Account::is_valid_currency(&account.currency) {
+            return Err(error::Error::InvalidCurrency(account.currency));
+        }
+
+        let account = WithId::from_data(account);
+        sqlite_store.create_account(&account).await?;
+        sqlite_store.commit().await?;
+
+        Ok(account)
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_account_balance(
+        account_id: Uuid,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<AccountBalance, error::Error> {
+        let account = sqlite_store.get_account(account_id).await?;
+        let transactions = sqlite_store.get_account_transactions(account_id).await?;
+        let balance =
+            Model::calculate_account_balance(&account.data, transactions.iter().map(|x| &x.data));
+
+        Ok(AccountBalance {
+            account_id,
+            currency: account.data.currency,
+            balance,
+        })
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_account_report(
+        account_id: Uuid,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Report, error::Error> {
+        sqlite_store.get_account(account_id).await?;
+        let transactions = sqlite_store.get_account_transactions(account_id).await?;
+
+        Ok(Model::calculate_balance_from_transactions(
+            transactions.iter().map(|x| &x.data),
+        ))
+    }
+
+    /// Fetches an account that holds money, such as a checking account or a
+    /// credit card.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`error::Error::NotABankAccount`] for any other account.
+    async fn get_bank_account(
+        account_id: Uuid,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<WithId<Account>, error::Error> {
+        let account = sqlite_store.get_account(account_id).await?;
+        if !matches!(
+            account.data.kind.class(),
+            AccountClass::Asset | AccountClass::Liability
+        ) {
+            return Err(error::Error::NotABankAccount(account_id));
+        }
+        Ok(account)
+    }
+
+    /// Whether the transaction of the account falls within the statement.
+    fn is_in_statement(reconciliation: &Reconciliation, transaction: &Transaction) -> bool {
+        transaction.account_id == Some(reconciliation.account_id)
+            && transaction.date <= reconciliation.statement_date
+    }
+
+    /// Opening balance of the account plus its cleared transactions up to
+    /// the statement date, compared with the closing balance of the
+    /// statement.
+    pub fn calculate_reconciliation_summary<'a>(
+        account: &Account,
+        reconciliation: WithId<Reconciliation>,
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+    ) -> ReconciliationSummary {
+        let cleared_balance = Model::calculate_account_balance(
+            account,
+            transactions
+                .into_iter()
+                .filter(|x| x.cleared && Model::is_in_statement(&reconciliation.data, x)),
+        );
+
+        ReconciliationSummary {
+            difference: reconciliation.data.closing_balance - cleared_balance,
+            reconciliation,
+            cleared_balance,
+        }
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_reconciliation(
+        id: Uuid,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<ReconciliationSummary, error::Error> {
+        let reconciliation = sqlite_store.get_reconciliation(id).await?;
+        let account = sqlite_store
+            .get_account(reconciliation.data.account_id)
+            .await?;
+        let transactions = sqlite_store
+            .get_account_transactions(reconciliation.data.account_id)
+            .await?;
+
+        Ok(Model::calculate_reconciliation_summary(
+            &account.data,
+            reconciliation,
+            transactions.iter().map(|x| &x.data),
+        ))
+    }
+
+    /// Opens a reconciliation of a bank account against a statement. An
+    /// account has at most one open reconciliation at a time.
+    ///
+    /// # Errors
+    pub async fn start_reconciliation(
+        mut reconciliation: Reconciliation,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<ReconciliationSummary, error::Error> {
+        let account_id = reconciliation.account_id;
+        Model::get_bank_account(account_id, &mut sqlite_store).await?;
+        if let Some(open) = sqlite_store.get_open_reconciliation(account_id).await? {
+            return Err(error::Error::ReconciliationInProgress(open.id));
+        }
+
+        reconciliation.status = ReconciliationStatus::Open;
+        let reconciliation = WithId::from_data(reconciliation);
+        sqlite_store.create_reconciliation(&reconciliation).await?;
+        let summary = Model::get_reconciliation(reconciliation.id, &mut sqlite_store).await?;
+        sqlite_store.commit().await?;
+
+        Ok(summary)
+    }
+
+    /// Marks transactions of the statement as cleared, or not cleared.
+    ///
+    /// # Errors
+    pub async fn set_cleared(
+        id: Uuid,
+        transaction_ids: &[Uuid],
+        cleared: bool,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<ReconciliationSummary, error::Error> {
+        let reconciliation = sqlite_store.get_reconciliation(id).await?;
+        if reconciliation.data.status == ReconciliationStatus::Finished {
+            return Err(error::Error::ReconciliationFinished(id));
+        }
+        for &transaction_id in transaction_ids {
+            let transaction =
+                Model::get_editable_transaction(transaction_id, &mut sqlite_store).await?;
+            if !Model::is_in_statement(&reconciliation.data, &transaction.data) {
+                return Err(error::Error::TransactionNotInStatement(transaction_id));
+            }
+        }
+
+        sqlite_store
+            .set_transactions_cleared(transaction_ids, cleared)
+            .await?;
+        let summary = Model::get_reconciliation(id, &mut sqlite_store).await?;
+        sqlite_store.commit().await?;
+
+        Ok(summary)
+    }
+
+    /// Finishes the reconciliation once the cleared balance matches the
+    /// statement, locking the cleared transactions from further edits.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`error::Error::ReconciliationUnbalanced`] if there is still a
+    /// difference.
+    pub async fn finish_reconciliation(
+        id: Uuid,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<ReconciliationSummary, error::Error> {
+        let mut summary = Model::get_reconciliation(id, &mut sqlite_store).await?;
+        if summary.reconciliation.data.status == ReconciliationStatus::Finished {
+            return Err(error::Error::ReconciliationFinished(id));
+        }
+        if summary.difference != dec!(0) {
+            return Err(error::Error::ReconciliationUnbalanced(summary.difference));
+        }
+
+        sqlite_store
+            .reconcile_cleared_transactions(&summary.reconciliation)
+            .await?;
+        sqlite_store
+            .set_reconciliation_status(id, ReconciliationStatus::Finished)
+            .await?;
+        sqlite_store.commit().await?;
+
+        summary.reconciliation.data.status = ReconciliationStatus::Finished;
+        Ok(summary)
+    }
+
+    /// Closes the books through the given date, which must be after the
+    /// current closing date.
+    ///
+    /// # Errors
+    pub async fn close_period(
+        through: NaiveDate,
+        reason: Option<String>,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<PeriodClosing, error::Error> {
+        if matches!(sqlite_store.get_closed_through().await?, Some(x) if x >= through) {
+            return Err(error::Error::InvalidClosingDate(through));
+        }
+
+        let closing = PeriodClosing {
+            closed_through: Some(through),
+            reason,
+            recorded_at: Utc::now(),
+        };
+        sqlite_store.create_period_closing(&closing).await?;
+        sqlite_store.commit().await?;
+
+        Ok(closing)
+    }
+
+    /// Moves the closing date back, or removes it when no date is given. The
+    /// reason is kept along with the change.
+    ///
+    /// # Errors
+    pub async fn reopen_period(
+        through: Option<NaiveDate>,
+        reason: String,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<PeriodClosing, error::Error> {
+        if reason.trim().is_empty() {
+            return Err(error::Error::MissingReopenReason);
+        }
+        let closed_through = sqlite_store.get_closed_through().await?;
+        if let Some(through) = through {
+            if !matches!(closed_through, Some(x) if x > through) {
+                return Err(error::Error::InvalidClosingDate(through));
+            }
+        }
+
+        let closing = PeriodClosing {
+            closed_through: through,
+            reason: Some(reason),
+            recorded_at: Utc::now(),
+        };
+        sqlite_store.create_period_closing(&closing).await?;
+        sqlite_store.commit().await?;
+
+        Ok(closing)
+    }
+
+    ///
+    /// # Errors
+    pub async fn create_schedule(
+        schedule: Schedule,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<WithId<Schedule>, error::Error> {
+        if !schedule.is_valid() {
+            return Err(error::Error::InvalidSchedule);
+        }
+        if let Some(account_id) = schedule.account_id {
+            Model::get_bank_account(account_id, &mut sqlite_store).await?;
+        }
+
+        let schedule = WithId::from_data(schedule);
+        sqlite_store.create_schedule(&schedule).await?;
+        sqlite_store.commit().await?;
+
+        Ok(schedule)
+    }
+
+    /// The next `count` dates of the schedule after the given date.
+    #[must_use]
+    pub fn preview_schedule(schedule: &Schedule, after: NaiveDate, count: usize) -> Vec<NaiveDate> {
+        schedule
+            .occurrences()
+            .skip_while(|x| *x <= after)
+            .take(count)
+            .collect()
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_schedule_preview(
+        id: Uuid,
+        after: NaiveDate,
+        count: usize,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Vec<NaiveDate>, error::Error> {
+        let schedule = sqlite_store.get_schedule(id).await?;
+        Ok(Model::preview_schedule(&schedule.data, after, count))
+    }
+
+    /// Commits, as a single upload, every occurrence of the schedules due by
+    /// the given date that is not a transaction yet. Occurrences in a closed
+    /// period are left out.
+    ///
+    /// # Errors
+    pub async fn materialize_schedules(
+        today: NaiveDate,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<Vec<Transaction>, error::Error> {
+        let closed_through = sqlite_store.get_closed_through().await?;
+
+        let mut transactions = vec![];
+        for schedule in sqlite_store.get_schedules().await? {
+            let materialized: HashSet<_> = sqlite_store
+                .get_schedule_dates(schedule.id)
+                .await?
+                .into_iter()
+                .collect();
+            let currency = match schedule.data.account_id {
+                Some(account_id) => sqlite_store.get_account(account_id).await?.data.currency,
+                None => Transaction::DEFAULT_CURRENCY.to_owned(),
+            };
+
+            for date in schedule
+                .data
+                .occurrences()
+                .take_while(|x| *x <= today)
+                .filter(|x| closed_through.is_none_or(|closed| *x > closed))
+                .filter(|x| !materialized.contains(x))
+            {
+                let mut transaction = schedule.data.transaction(schedule.id, date);
+                transaction.currency.clone_from(&currency);
+                transactions.push(transaction);
+            }
+        }
+
+        if !transactions.is_empty() {
+            Model::commit_transactions(&transactions, sqlite_store).await?;
+        }
+        Ok(transactions)
+    }
+
+    /// Projects the balance of an account day by day after `today`. Every
+    /// day adds the average daily amount of each category over the lookback,
+    /// computed from the transactions that are neither transfers nor
+    /// occurrences of a schedule, and the occurrences of the schedules due
+    /// that day. The balances are rounded half to even to 2 decimal places.
+    ///
+    /// # Errors
+    /// The lookback must be 1 to `MAX_FORECAST_LOOKBACK_DAYS` days and the
+    /// forecast at most `MAX_FORECAST_DAYS` days, both within the dates.
+    pub fn calculate_forecast<'a>(
+        account_id: Uuid,
+        balance: Decimal,
+        today: NaiveDate,
+        options: &ForecastOptions,
+        history: impl IntoIterator<Item = &'a Transaction>,
+        schedules: &[Schedule],
+    ) -> Result<Forecast, error::Error> {
+        if options.lookback_days == 0
+            || options.lookback_days > Model::MAX_FORECAST_LOOKBACK_DAYS
+            || options.days > Model::MAX_FORECAST_DAYS
+        {
+            return Err(error::Error::InvalidForecast);
+        }
+        let lookback_start = today
+            .checked_sub_days(Days::new(options.lookback_days.into()))
+            .ok_or(error::Error::InvalidForecast)?;
+        let history: Vec<_> = history
+            .into_iter()
+            .filter(|x| {
+                x.kind == TransactionKind::Regular
+                    && x.schedule_id.is_none()
+                    && x.date >= lookback_start
+                    && x.date < today
+            })
+            .collect();
+        let averages: Vec<_> = Model::calculate_category_reports(history)
+            .into_iter()
+            .map(|x| CategoryAverage {
+                category: x.category,
+                daily_amount: x.report.net_revenue / Decimal::from(options.lookback_days),
+            })
+            .collect();
+        let daily_amount: Decimal = averages.iter().map(|x| x.daily_amount).sum();
+
+        let end = today
+            .checked_add_days(Days::new(options.days.into()))
+            .ok_or(error::Error::InvalidForecast)?;
+        let mut occurrences: BTreeMap<NaiveDate, Decimal> = BTreeMap::new();
+        for schedule in schedules {
+            for date in schedule
+                .occurrences()
+                .skip_while(|x| *x <= today)
+                .take_while(|x| *x <= end)
+            {
+                *occurrences.entry(date).or_default() += schedule.amount;
+            }
+        }
+
+        let mut series = vec![ForecastPoint {
+            date: today,
+            balance,
+        }];
+        let mut projected = balance;
+        for date in today.iter_days().skip(1).take_while(|x| *x <= end) {
+            projected += daily_amount + occurrences.get(&date).copied().unwrap_or_default();
+            series.push(ForecastPoint {
+                date,
+                balance: projected.round_dp_with_strategy(2, RoundingStrategy::MidpointNearestEven),
+            });
+        }
+        let below_threshold_on = series
+            .iter()
+            .find(|x| x.balance < options.threshold)
+            .map(|x| x.date);
+
+        Ok(Forecast {
+            account_id,
+            threshold: options.threshold,
+            averages,
+            series,
+            below_threshold_on,
+        })
+    }
+
+    ///
+    /// # Errors
+    pub async fn get_forecast(
+        account_id: Uuid,
+        today: NaiveDate,
+        options: &ForecastOptions,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<Forecast, error::Error> {
+        let account = sqlite_store.get_account(account_id).await?;
+        let transactions = sqlite_store.get_account_transactions(account_id).await?;
+        let balance = Model::calculate_account_balance(
+            &account.data,
+            transactions
+                .iter()
+                .map(|x| &x.data)
+                .filter(|x| x.date <= today),
+        );
+        let schedules: Vec<_> = sqlite_store
+            .get_schedules()
+            .await?
+            .into_iter()
+            .filter(|x| x.data.account_id == Some(account_id))
+            .map(|x| x.data)
+            .collect();
+
+        Model::calculate_forecast(
+            account_id,
+            balance,
+            today,
+            options,
+            transactions.iter().map(|x| &x.data),
+            &schedules,
+        )
+    }
+
+    /// Pairs each transaction with the balance after it, starting from the
+    /// given balance.
+    #[must_use]
+    pub fn calculate_running_balances(
+        balance: Decimal,
+        transactions: Vec<WithId<Transaction>>,
+    ) -> Vec<StatementLine> {
+        transactions
+            .into_iter()
+            .scan(balance, |balance, transaction| {
+                *balance += transaction.data.amount;
+                Some(StatementLine {
+                    transaction,
+                    balance: *balance,
+                })
+            })
+            .collect()
+    }
+
+    /// A page of the transactions of an account from its opening date, in
+    /// order of date and then of insertion, with its running balance, after
+    /// the transaction of the given id if any. The balance before the page is
+    /// the opening balance of the account plus the sum of the transactions
+    /// before it, computed by the store, which keeps a checkpoint of it. A
+    /// page lists 1 to `MAX_PAGE_SIZE` transactions, whatever the limit
+    /// asked.
+    ///
+    /// # Errors
+    pub async fn get_account_statement(
+        account_id: Uuid,
+        after: Option<Uuid>,
+        limit: u64,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<Vec<StatementLine>, error::Error> {
+        let account = sqlite_store.get_account(account_id).await?;
+        let from = account.data.opening_date;
+        let mut balance = account.data.opening_balance;
+        let mut position = None;
+        if let Some(id) = after {
+            let through = sqlite_store
+                .get_account_transaction_position(account_id, id)
+                .await?;
+            balance += sqlite_store
+                .get_account_total(account_id, from, through)
+                .await?;
+            position = Some(through);
+        }
+        let transactions = sqlite_store
+            .get_account_transactions_page(
+                account_id,
+                from,
+                position,
+                limit.clamp(1, Model::MAX_PAGE_SIZE),
+            )
+            .await?;
+        sqlite_store.commit().await?;
+
+        Ok(Model::calculate_running_balances(balance, transactions))
+    }
+
+    /// Commits the transactions of a CSV upload, assigning all of them to
+    /// the given account.
+    ///
+    /// # Errors
+    pub async fn commit_account_transactions(
+        account_id: Uuid,
+        mut transactions: Vec<Transaction>,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<Report, error::Error> {
+        let account = Model::get_bank_account(account_id, &mut sqlite_store).await?;
+        for transaction in &mut transactions {
+            transaction.account_id = Some(account_id);
+            transaction.currency.clone_from(&account.data.currency);
+        }
+
+        Model::commit_transactions(&transactions, sqlite_store).await
+    }
+
+    /// Commits the transactions as a single upload, with its report, and
+    /// chains the hash of its transactions to the hash of the previous upload.
+    ///
+    /// # Errors
+    pub async fn commit_transactions(
+        transactions: &[Transaction],
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<Report, error::Error> {
+        for transaction in transactions {
+            transaction.validate_splits()?;
+        }
+        let report = Model::calculate_balance_from_transactions(transactions);
+        let report_id = Uuid::new_v4();
+        let transactions: Vec<_> = transactions.iter().map(WithId::from_data).collect();
+
+        sqlite_store
+            .create_transactions(
+                report_id,
+                transactions.iter().map(|x| WithId {
+                    id: x.id,
+                    data: x.data,
+                }),
+            )
+            .await?;
+        tracing::debug!("updated transactions");
+
+        Model::project_pending_events(&mut sqlite_store).await?;
+        tracing::debug!("projected report");
+
+        let previous = sqlite_store.get_last_batch().await?;
+        let previous_hash = previous.as_ref().map(|x| x.hash.clone());
+        let batch = Batch {
+            sequence: previous.map_or(1, |x| x.sequence + 1),
+            report_id,
+            hash: Model::calculate_batch_hash(
+                previous_hash.as_deref(),
+                transactions.iter().map(|x| (x.id, x.data)),
+            ),
+            previous_hash,
+            recorded_at: Utc::now(),
+        };
+        sqlite_store.create_batch(&batch).await?;
+        tracing::debug!("chained batch {}", batch.sequence);
+
+        sqlite_store.commit().await?;
+        tracing::debug!("commited");
+
+        Ok(report)
+    }
+
+    /// The hash of the transactions of an upload chained to the hash of the
+    /// previous upload. Transactions are hashed in order of id and only by
+    /// the fields set when they are imported, so that later edits such as
+    /// categories or transfer links do not break the chain.
+    #[must_use]
+    pub fn calculate_batch_hash<'a>(
+        previous_hash: Option<&str>,
+        transactions: impl IntoIterator<Item = (Uuid, &'a Transaction)>,
+    ) -> String {
+        let mut transactions: Vec<_> = transactions.into_iter().collect();
+        transactions.sort_by_key(|(id, _)| *id);
+
+        let mut hasher = Sha256::new();
+        hasher.update(previous_hash.unwrap_or_default());
+        for (id, transaction) in transactions {
+            let line = serde_json::json!([
+                id,
+                transaction.date,
+                transaction.amount.normalize().to_string(),
+                transaction.memo,
+                transaction.account_id,
+                transaction.currency,
+            ]);
+            hasher.update(b"\n");
+            hasher.update(line.to_string());
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// Walks the hash chain from its first link and stops at the first link
+    /// that does not point to the link before it, or whose transactions no
+    /// longer hash to its hash.
+    ///
+    /// # Errors
+    pub async fn verify_chain(
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<ChainVerification, error::Error> {
+        let batches = sqlite_store.get_batches().await?;
+
+        let mut previous_hash = None;
+        for (verified, batch) in batches.iter().enumerate() {
+            let broken = |reason, expected, actual| ChainVerification {
+                batches: batches.len(),
+                verified,
+                broken: Some(ChainBreak {
+                    sequence: batch.sequence,
+                    report_id: batch.report_id,
+                    reason,
+                    expected,
+                    actual,
+                }),
+            };
+
+            if batch.previous_hash != previous_hash {
+                return Ok(broken(
+                    ChainBreakReason::PreviousHashMismatch,
+                    previous_hash,
+                    batch.previous_hash.clone(),
+                ));
+            }
+
+            let transactions = sqlite_store
+                .get_committed_report_transactions(batch.report_id)
+                .await?;
+            let hash = Model::calculate_batch_hash(
+                batch.previous_hash.as_deref(),
+                transactions.iter().map(|x| (x.id, &x.data)),
+            );
+            if hash != batch.hash {
+                return Ok(broken(
+                    ChainBreakReason::HashMismatch,
+                    Some(batch.hash.clone()),
+                    Some(hash),
+                ));
+            }
+            previous_hash = Some(batch.hash.clone());
+        }
+
+        Ok(ChainVerification {
+            batches: batches.len(),
+            verified: batches.len(),
+            broken: None,
+        })
+    }
+
+    /// The mismatch between the stored report of an upload and the report
+    /// computed from its transactions, if they differ.
+    #[must_use]
+    pub fn calculate_report_mismatch<'a>(
+        report_id: Uuid,
+        stored: Option<Report>,
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+    ) -> Option<ReportMismatch> {
+        let computed = Model::calculate_balance_from_transactions(transactions);
+        (stored != Some(computed)).then_some(ReportMismatch {
+            report_id,
+            stored,
+            computed,
+        })
+    }
+
+    /// Recomputes the report of every upload from its transactions and lists
+    /// the stored reports that differ, or are missing. With `repair`, the
+    /// events that bring the projection in line with the transactions are
+    /// recorded and every report is rebuilt from the first event, in the same
+    /// transaction, which is committed only when no report differs anymore;
+    /// otherwise nothing is changed.
+    ///
+    /// # Errors
+    pub async fn check_reports(
+        repair: bool,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<ReportCheck, error::Error> {
+        let (reports, mismatches) = Model::find_report_mismatches(&mut sqlite_store).await?;
+
+        let repaired = repair && !mismatches.is_empty() && {
+            Model::repair_reports(&mismatches, &mut sqlite_store).await?;
+            Model::find_report_mismatches(&mut sqlite_store)
+                .await?
+                .1
+                .is_empty()
+        };
+        if repaired {
+            sqlite_store.commit().await?;
+        }
+
+        Ok(ReportCheck {
+            reports,
+            mismatches,
+            repaired,
+        })
+    }
+
+    /// The number of uploads, with or without a stored report, and the
+    /// stored reports that differ from their transactions, or are missing.
+    async fn find_report_mismatches(
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<(usize, Vec<ReportMismatch>), error::Error> {
+        let mut stored: BTreeMap<_, _> = sqlite_store
+            .get_reports_with_ids()
+            .await?
+            .into_iter()
+            .map(|x| (x.id, Some(x.data)))
+            .collect();
+        for id in sqlite_store.get_transaction_report_ids().await? {
+            stored.entry(id).or_insert(None);
+        }
+
+        let mut mismatches = vec![];
+        for (&id, &report) in &stored {
+            let transactions = sqlite_store.get_report_transactions(id).await?;
+            mismatches.extend(Model::calculate_report_mismatch(
+                id,
+                report,
+                transactions.iter().map(|x| &x.data),
+            ));
+        }
+
+        Ok((stored.len(), mismatches))
+    }
+
+    /// Records, for every mismatched upload, the events that turn the
+    /// transactions projected from its events into its stored transactions,
+    /// which may have been changed outside of the store, then rebuilds every
+    /// report from the first event.
+    async fn repair_reports(
+        mismatches: &[ReportMismatch],
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        let report_ids: Vec<_> = mismatches.iter().map(|x| x.report_id).collect();
+        let history = sqlite_store
+            .get_report_events(&report_ids, i64::MAX)
+            .await?;
+        let mut projected = Model::project_transactions(&history)?;
+        for id in report_ids {
+            let transactions = sqlite_store.get_report_transactions(id).await?;
+            let events = Model::calculate_corrective_events(
+                &projected.remove(&id).unwrap_or_default(),
+                &transactions,
+            );
+            sqlite_store.record_report_events(id, &events).await?;
+        }
+
+        Model::project_reports_from(0, sqlite_store).await?;
+        Ok(())
+    }
+
+    /// The events that turn the transactions projected from the events of an
+    /// upload into its stored transactions: an import for those missing from
+    /// the projection, an edit for those that differ and a deletion for
+    /// those no longer stored.
+    #[must_use]
+    pub fn calculate_corrective_events(
+        projected: &HashMap<Uuid, Transaction>,
+        stored: &[WithId<Transaction>],
+    ) -> Vec<Event> {
+        let mut events = vec![];
+        for WithId { id, data } in stored {
+            match projected.get(id) {
+                None => events.push(Event::TransactionImported {
+                    transaction_id: *id,
+                    transaction: data.clone(),
+                }),
+                Some(before) if before != data => events.push(Event::edited(*id, before, data)),
+                Some(_) => {}
+            }
+        }
+        let stored: HashSet<_> = stored.iter().map(|x| x.id).collect();
+        events.extend(
+            projected
+                .keys()
+                .filter(|x| !stored.contains(x))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(|&transaction_id| Event::TransactionDeleted { transaction_id }),
+        );
+        events
+    }
+
+    /// Replays the events of transactions, in order, into the report of each
+    /// upload. An upload whose transactions were all deleted gets an empty
+    /// report.
+    ///
+    /// # Errors
+    /// Every transaction must be imported before it is edited, categorized or
+    /// deleted, and cannot be changed once deleted.
+    pub fn project_reports<'a>(
+        events: impl IntoIterator<Item = &'a EventRecord>,
+    ) -> Result<BTreeMap<Uuid, Report>, error::Error> {
+        Ok(Model::project_transactions(events)?
+            .into_iter()
+            .map(|(id, transactions)| {
+                (
+                    id,
+                    Model::calculate_balance_from_transactions(transactions.values()),
+                )
+            })
+            .collect())
+    }
+
+    /// Replays the events of transactions, in order, into the live
+    /// transactions of each upload.
+    ///
+    /// # Errors
+    /// See [`Model::project_reports`].
+    pub fn project_transactions<'a>(
+        events: impl IntoIterator<Item = &'a EventRecord>,
+    ) -> Result<BTreeMap<Uuid, HashMap<Uuid, Transaction>>, error::Error> {
+        let mut uploads: BTreeMap<Uuid, HashMap<Uuid, Transaction>> = BTreeMap::new();
+        for record in events {
+            let Some(report_id) = record.report_id else {
+                continue;
+            };
+            let transactions = uploads.entry(report_id).or_default();
+            let missing = |transaction_id: &Uuid| {
+                error::Error::EventWithoutTransaction(record.sequence, *transaction_id)
+            };
+            match &record.event {
+                Event::TransactionImported {
+                    transaction_id,
+                    transaction,
+                } => {
+                    transactions.insert(*transaction_id, transaction.clone());
+                }
+                Event::TransactionEdited {
+                    transaction_id,
+                    transaction,
+                } => {
+                    *transactions
+                        .get_mut(transaction_id)
+                        .ok_or_else(|| missing(transaction_id))? = transaction.clone();
+                }
+                Event::CategoryAssigned {
+                    transaction_id,
+                    category,
+                } => {
+                    transactions
+                        .get_mut(transaction_id)
+                        .ok_or_else(|| missing(transaction_id))?
+                        .category
+                        .clone_from(category);
+                }
+                Event::TransactionDeleted { transaction_id } => {
+                    transactions
+                        .remove(transaction_id)
+                        .ok_or_else(|| missing(transaction_id))?;
+                }
+            }
+        }
+
+        Ok(uploads)
+    }
+
+    /// Applies the events recorded after `from` to the reports projection:
+    /// the reports of the uploads they concern are rebuilt from all of the
+    /// events of those uploads, and only the reports that changed are
+    /// rewritten.
+    async fn project_reports_from(
+        from: i64,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<ProjectionRun, error::Error> {
+        let events = sqlite_store.get_events(from).await?;
+        let mut run = ProjectionRun {
+            projection: Model::REPORTS_PROJECTION.to_owned(),
+            from,
+            to: from,
+            events: events.len(),
+            reports: 0,
+        };
+        let Some(to) = events.last().map(|x| x.sequence) else {
+            return Ok(run);
+        };
+
+        let report_ids: Vec<_> = events
+            .iter()
+            .filter_map(|x| x.report_id)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let history = sqlite_store.get_report_events(&report_ids, to).await?;
+        let stored: HashMap<_, _> = sqlite_store
+            .get_reports_with_ids()
+            .await?
+            .into_iter()
+            .map(|x| (x.id, x.data))
+            .collect();
+
+        for (id, report) in Model::project_reports(&history)? {
+            let report = WithId { id, data: report };
+            match stored.get(&id) {
+                Some(stored) if *stored == report.data => continue,
+                Some(_) => sqlite_store.update_report(&report).await?,
+                None => sqlite_store.create_report(&report).await?,
+            }
+            run.reports += 1;
+        }
+
+        sqlite_store
+            .set_projection_cursor(Model::REPORTS_PROJECTION, to)
+            .await?;
+        run.to = to;
+        Ok(run)
+    }
+
+    /// Applies the events recorded since the cursor of the reports
+    /// projection. Every change of transactions applies them before it
+    /// commits, so the projection is the only writer of the reports and they
+    /// are up to date as soon as the change is.
+    async fn project_pending_events(
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<ProjectionRun, error::Error> {
+        let from = sqlite_store
+            .get_projection_cursor(Model::REPORTS_PROJECTION)
+            .await?;
+        Model::project_reports_from(from, sqlite_store).await
+    }
+
+    /// Brings the projections up to date with the events recorded since
+    /// their cursor.
+    ///
+    /// # Errors
+    pub async fn catch_up_projections(
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<ProjectionRun, error::Error> {
+        let run = Model::project_pending_events(&mut sqlite_store).await?;
+        sqlite_store.commit().await?;
+        Ok(run)
+    }
+
+    /// Rebuilds the projections from the first event.
+    ///
+    /// # Errors
+    pub async fn replay_projections(
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<ProjectionRun, error::Error> {
+        let run = Model::project_reports_from(0, &mut sqlite_store).await?;
+        sqlite_store.commit().await?;
+        Ok(run)
+    }
+
+    /// Lists a page of the changes of transactions and reports committed
+    /// after the `since` cursor. A page lists 1 to `MAX_PAGE_SIZE`
+    /// changes, whatever the limit asked.
+    ///
+    /// # Errors
+    pub async fn get_changes(
+        since: i64,
+        limit: u64,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<ChangeFeed, error::Error> {
+        let limit = limit.clamp(1, Model::MAX_PAGE_SIZE);
+        let mut changes = sqlite_store.get_feed_changes(since, limit + 1).await?;
+        let has_more = changes.len() as u64 > limit;
+        changes.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        let cursor = changes.last().map_or(since, |x| x.cursor);
+
+        Ok(ChangeFeed {
+            changes,
+            cursor,
+            has_more,
+        })
+    }
+
+    fn is_transfer_leg(transaction: &Transaction) -> bool {
+        transaction.kind == TransactionKind::Regular
+            && transaction.account_id.is_some()
+            && transaction.amount != dec!(0)
+    }
+
+    /// Pairs each outgoing transaction with an incoming transaction of the
+    /// opposite amount in another account, at most `max_days` apart. Every
+    /// transaction is used at most once and the closest date wins.
+    #[must_use]
+    pub fn find_transfer_candidates(
+        transactions: &[WithId<Transaction>],
+        max_days: i64,
+    ) -> Vec<TransferCandidate> {
+        let mut outgoing: Vec<_> = transactions
+            .iter()
+            .filter(|x| Model::is_transfer_leg(&x.data) && x.data.amount < dec!(0))
+            .collect();
+        outgoing.sort_by_key(|x| x.data.date);
+
+        let mut matched = HashSet::new();
+        let mut candidates = vec![];
+        for out in outgoing {
+            let incoming = transactions
+                .iter()
+                .filter(|x| {
+                    Model::is_transfer_leg(&x.data)
+                        && x.data.amount == -out.data.amount
+                        && x.data.account_id != out.data.account_id
+                        && (x.data.date - out.data.date).num_days().abs() <= max_days
+                        && !matched.contains(&x.id)
+                })
+                .min_by_key(|x| ((x.data.date - out.data.date).num_days().abs(), x.data.date));
+
+            if let Some(incoming) = incoming {
+                matched.insert(incoming.id);
+                candidates.push(TransferCandidate {
+                    outgoing: out.id,
+                    incoming: incoming.id,
+                    amount: incoming.data.amount,
+                });
+            }
+        }
+        candidates
+    }
+
+    async fn link_transfer_legs(
+        outgoing: Uuid,
+        incoming: Uuid,
+        sqlite_store: &mut SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        let mut out = Model::get_editable_transaction(outgoing, sqlite_store).await?;
+        let mut inc = Model::get_editable_transaction(incoming, sqlite_store).await?;
+
+        if !Model::is_transfer_leg(&out.data)
+            || !Model::is_transfer_leg(&inc.data)
+            || out.data.account_id == inc.data.account_id
+            || out.data.amount > dec!(0)
+            || out.data.amount != -inc.data.amount
+        {
+            return Err(error::Error::InvalidTransfer(outgoing, incoming));
+        }
+
+        out.data.kind = TransactionKind::Transfer;
+        out.data.transfer_id = Some(incoming);
+        inc.data.kind = TransactionKind::Transfer;
+        inc.data.transfer_id = Some(outgoing);
+        sqlite_store.update_transaction(&out).await?;
+        sqlite_store.update_transaction(&inc).await?;
+
+        Model::project_pending_events(sqlite_store).await?;
+        Ok(())
+    }
+
+    /// Marks two transactions as the legs of a transfer, removing them from
+    /// the revenue and expenses of the reports.
+    ///
+    /// # Errors
+    pub async fn link_transfer(
+        outgoing: Uuid,
+        incoming: Uuid,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<(), error::Error> {
+        Model::link_transfer_legs(outgoing, incoming, &mut sqlite_store).await?;
+        sqlite_store.commit().await
+    }
+
+    /// Links every pair found by [`Model::find_transfer_candidates`].
+    ///
+    /// # Errors
+    pub async fn link_detected_transfers(
+        max_days: i64,
+        mut sqlite_store: SqliteStore<'_>,
+    ) -> Result<Vec<TransferCandidate>, error::Error> {
+        let transactions = sqlite_store.get_transactions().await?;
+        let candidates = Model::find_transfer_candidates(&transactions, max_days);
+        for candidate in &candidates {
+            Model::link_transfer_legs(candidate.outgoing, candidate.incoming, &mut sqlite_store)
+                .await?;
+        }
+        sqlite_store.commit().await?;
+
+        Ok(candidates)
+    }
+}
+
+pub struct CSVReader;
+
+impl CSVReader {
+    #[must_use]
+    pub fn read_transaction_from_csv_bytes(
+        bytes: &[u8],
+    ) -> impl StreamExt<Item = Transaction> + '_ {
+        let csv_reader = AsyncReaderBuilder::new()
+            .trim(Trim::All)
+            .comment(Some(b'#'))
+            .has_headers(false)
+            .flexible(true)
+            .create_deserializer(bytes);
+        let records = csv_reader.into_deserialize::<TransactionFromCSV>();
+        records
+            .filter_map(|x| async move {
+                if x.is_err() {
+                    tracing::warn!("{:?}", x);
+                }
+                x.ok()
+            })
+            .filter_map(|x| async move {
+                let x = x.try_into();
+                if x.is_err() {
+                    tracing::warn!("{:?}", x);
+                }
+                tracing::debug!("{:?}", x);
+                x.ok()
+            })
+    }
+
+    /// Reads trips as [`TripEntry`] rows, skipping invalid ones.
+    #[must_use]
+    pub fn read_trips_from_csv_bytes(bytes: &[u8]) -> impl StreamExt<Item = Trip> + '_ {
+        let csv_reader = AsyncReaderBuilder::new()
+            .trim(Trim::All)
+            .comment(Some(b'#'))
+            .has_headers(false)
+            .flexible(true)
+            .create_deserializer(bytes);
+        let records = csv_reader.into_deserialize::<TripEntry>();
+        records
+            .filter_map(|x| async move {
+                if x.is_err() {
+                    tracing::warn!("{:?}", x);
+                }
+                x.ok()
+            })
+            .filter_map(|x| async move {
+                let x = Trip::try_from(x);
+                if x.is_err() {
+                    tracing::warn!("{:?}", x);
+                }
+                x.ok()
+            })
+    }
+
+    /// Reads rates as `date, base, quote, rate` rows, skipping invalid ones.
+    #[must_use]
+    pub fn read_exchange_rates_from_csv_bytes(
+        bytes: &[u8],
+    ) -> impl StreamExt<Item = ExchangeRate> + '_ {
+        let csv_reader = AsyncReaderBuilder::new()
+            .trim(Trim::All)
+            .comment(Some(b'#'))
+            .has_headers(false)
+            .flexible(true)
+            .create_deserializer(bytes);
+        let records = csv_reader.into_deserialize::<ExchangeRate>();
+        records.filter_map(|x| async move {
+            match x {
+                Ok(rate) if rate.is_valid() => Some(rate),
+                _ => {
+                    tracing::warn!("{:?}", x);
+                    None
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use chrono::{Datelike, NaiveDate, Utc};
+    use futures::StreamExt;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use sqlx::SqlitePool;
+    use uuid::Uuid;
+
+    use crate::{
+        config::{
+            Config, MileageConfig, TaxBracket, TaxConfig, TaxRates, VatCode, VatConfig, VatRate,
+        },
+        entity::{
+            Account, AccountKind, AuditAction, Bill, BillStatus, Budget, CategoryReport,
+            ChainBreakReason, CurrencyReport, Customer, Event, EventRecord, ExchangeRate,
+            ForecastOptions, Frequency, Invoice, InvoiceLine, InvoiceStatus, JournalEntry,
+            PayeeAlias, Posting, Reconciliation, ReconciliationStatus, Report, ReportMismatch,
+            Schedule, Split, Transaction, TransactionKind, Trip, Vendor, WithId,
+        },
+        error,
+        ledger::Ledger,
+        logic::CSVReader,
+        query::{apply_migrations, SqliteStore},
+    };
+
+    use super::Model;
+
+    #[tokio::test]
+    #[allow(clippy::useless_vec)]
+    async fn valid_csv() {
+        let csv = vec![
+            "2021-07-12, Income, 87.32, first",
+            "2023-08-20, Expense, 12.13, second",
+        ]
+        .join("\n");
+        let expected_transactions = vec![
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2023-08-20").unwrap(),
+                amount: dec!(-12.13),
+                memo: "second".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let transactions: Vec<Transaction> =
+            CSVReader::read_transaction_from_csv_bytes(csv.as_bytes())
+                .collect()
+                .await;
+
+        assert_eq!(transactions, expected_transactions);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::useless_vec)]
+    async fn invalid_csv() {
+        let csv = vec![
+            "text",
+            "# comment",
+            "2020-09-12, Income",
+            "2021-07-12, Income, 87.32, first",
+            "2023-08-13, NotExpense, 10.12, third",
+            "2023-08-20, Expense, 12.13, second",
+            "20-08-2023, Income, 10.00, fourth",
+        ]
+        .join("\n");
+        let expected_transactions = vec![
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2023-08-20").unwrap(),
+                amount: dec!(-12.13),
+                memo: "second".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let transactions: Vec<Transaction> =
+            CSVReader::read_transaction_from_csv_bytes(csv.as_bytes())
+                .collect()
+                .await;
+
+        assert_eq!(transactions, expected_transactions);
+    }
+
+    #[test]
+    #[allow(clippy::useless_vec)]
+    fn balance_from_transactions() {
+        let transactions = vec![
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2023-08-20").unwrap(),
+                amount: dec!(-12.13),
+                memo: "second".to_string(),
+                ..Default::default()
+            },
+        ];
+        let expected_report = Report {
+            gross_revenue: dec!(87.32),
+            expenses: dec!(12.13),
+            net_revenue: dec!(75.19),
+        };
+
+        let report = Model::calculate_balance_from_transactions(transactions.iter());
+
+        assert_eq!(report, expected_report);
+    }
+
+    #[test]
+    #[allow(clippy::useless_vec)]
+    fn total_reports() {
+        let reports = vec![
+            Report {
+                gross_revenue: dec!(87.32),
+                expenses: dec!(12.13),
+                net_revenue: dec!(75.19),
+            },
+            Report {
+                gross_revenue: dec!(10.01),
+                expenses: dec!(2.05),
+                net_revenue: dec!(7.96),
+            },
+        ];
+        let expected_report = Report {
+            gross_revenue: dec!(97.33),
+            expenses: dec!(14.18),
+            net_revenue: dec!(83.15),
+        };
+
+        let report = Model::calculate_total_report(reports.iter());
+
+        assert_eq!(report, expected_report);
+    }
+
+    #[sqlx::test]
+    async fn commit_transactions(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+
+        let transactions = vec![
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2023-08-20").unwrap(),
+                amount: dec!(-12.13),
+                memo: "second".to_string(),
+                ..Default::default()
+            },
+        ];
+        let expected_report = Report {
+            gross_revenue: dec!(87.32),
+            expenses: dec!(12.13),
+            net_revenue: dec!(75.19),
+        };
+
+        let report = Model::commit_transactions(&transactions, sqlite_store).await?;
+
+        assert_eq!(report, expected_report);
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+
+        let report_from_store = sqlite_store.get_reports().await?;
+
+        assert_eq!(report_from_store.len(), 1);
+        assert_eq!(report_from_store[0], expected_report);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn check_reports(pool: SqlitePool) -> Result<(), error::Error> {
+        for amount in [dec!(87.32), dec!(-12.13)] {
+            let tx = pool.begin().await?;
+            let transactions = [Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount,
+                memo: "upload".to_string(),
+                ..Default::default()
+            }];
+            Model::commit_transactions(&transactions, SqliteStore::from_sqlite_transaction(tx))
+                .await?;
+        }
+
+        let tx = pool.begin().await?;
+        let check = Model::check_reports(false, SqliteStore::from_sqlite_transaction(tx)).await?;
+        assert!(check.is_consistent());
+        assert_eq!(2, check.reports);
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let reports = sqlite_store.get_reports_with_ids().await?;
+        drop(sqlite_store);
+        let revenue = reports
+            .iter()
+            .find(|x| x.data.gross_revenue == dec!(87.32))
+            .unwrap()
+            .id;
+        let expenses = reports
+            .iter()
+            .find(|x| x.data.expenses == dec!(12.13))
+            .unwrap()
+            .id;
+        sqlx::query("UPDATE report SET gross_revenue = '80' WHERE id = ?")
+            .bind(revenue.to_string())
+            .execute(&pool)
+            .await?;
+        sqlx::query("DELETE FROM report WHERE id = ?")
+            .bind(expenses.to_string())
+            .execute(&pool)
+            .await?;
+
+        let tx = pool.begin().await?;
+        let check = Model::check_reports(false, SqliteStore::from_sqlite_transaction(tx)).await?;
+        let mut mismatches = check.mismatches.clone();
+        mismatches.sort_by_key(|x| x.stored.is_none());
+
+        assert!(!check.repaired);
+        assert_eq!(
+            vec![
+                ReportMismatch {
+                    report_id: revenue,
+                    stored: Some(Report {
+                        gross_revenue: dec!(80),
+                        expenses: dec!(0),
+                        net_revenue: dec!(80),
+                    }),
+                    computed: Report {
+                        gross_revenue: dec!(87.32),
+                        expenses: dec!(0),
+                        net_revenue: dec!(87.32),
+                    },
+                },
+                ReportMismatch {
+                    report_id: expenses,
+                    stored: None,
+                    computed: Report {
+                        gross_revenue: dec!(0),
+                        expenses: dec!(12.13),
+                        net_revenue: dec!(-12.13),
+                    },
+                },
+            ],
+            mismatches
+        );
+
+        let tx = pool.begin().await?;
+        let check = Model::check_reports(true, SqliteStore::from_sqlite_transaction(tx)).await?;
+        assert!(check.repaired);
+        assert_eq!(2, check.mismatches.len());
+
+        let tx = pool.begin().await?;
+        let check = Model::check_reports(false, SqliteStore::from_sqlite_transaction(tx)).await?;
+        assert!(check.is_consistent());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn repair_transactions_changed_outside_of_store(
+        pool: SqlitePool,
+    ) -> Result<(), error::Error> {
+        let transactions = [
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "salary".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-07-13").unwrap(),
+                amount: dec!(-12.13),
+                memo: "groceries".to_string(),
+                ..Default::default()
+            },
+        ];
+        let tx = pool.begin().await?;
+        Model::commit_transactions(&transactions, SqliteStore::from_sqlite_transaction(tx)).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let stored = sqlite_store.get_transactions().await?;
+        let report_id = sqlite_store.get_reports_with_ids().await?[0].id;
+        let after = sqlite_store.get_events(0).await?.last().unwrap().sequence;
+        drop(sqlite_store);
+        let id_of = |memo: &str| stored.iter().find(|x| x.data.memo == memo).unwrap().id;
+        let (salary, groceries, added) = (id_of("salary"), id_of("groceries"), Uuid::new_v4());
+        sqlx::query("UPDATE transactions SET amount = '90.00' WHERE id = ?")
+            .bind(salary.to_string())
+            .execute(&pool)
+            .await?;
+        sqlx::query(
+            "UPDATE transactions SET valid_to = '2021-08-01T00:00:00.000000Z' WHERE id = ?",
+        )
+        .bind(groceries.to_string())
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "INSERT INTO transactions (id, date, amount, memo, report_id)
+            VALUES (?, '2021-07-14', '-5.00', 'added', ?)",
+        )
+        .bind(added.to_string())
+        .bind(report_id.to_string())
+        .execute(&pool)
+        .await?;
+
+        let tx = pool.begin().await?;
+        let check = Model::check_reports(false, SqliteStore::from_sqlite_transaction(tx)).await?;
+        assert_eq!(
+            vec![ReportMismatch {
+                report_id,
+                stored: Some(Report::from_dec(dec!(87.32), dec!(12.13), dec!(75.19))),
+                computed: Report::from_dec(dec!(90.00), dec!(5.00), dec!(85.00)),
+            }],
+            check.mismatches
+        );
+
+        let tx = pool.begin().await?;
+        let check = Model::check_reports(true, SqliteStore::from_sqlite_transaction(tx)).await?;
+        assert!(check.repaired);
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let events = sqlite_store.get_events(after).await?;
+        assert_eq!(
+            vec![
+                ("TransactionEdited", salary),
+                ("TransactionImported", added),
+                ("TransactionDeleted", groceries)
+            ],
+            events
+                .iter()
+                .map(|x| (x.event.kind(), x.event.transaction_id()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Report::from_dec(dec!(90.00), dec!(5.00), dec!(85.00)),
+            sqlite_store.get_reports_with_ids().await?[0].data
+        );
+        let check = Model::check_reports(false, sqlite_store).await?;
+        assert!(check.is_consistent());
+
+        let tx = pool.begin().await?;
+        Model::replay_projections(SqliteStore::from_sqlite_transaction(tx)).await?;
+        let tx = pool.begin().await?;
+        let check = Model::check_reports(false, SqliteStore::from_sqlite_transaction(tx)).await?;
+        assert!(check.is_consistent());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn transaction_versions(pool: SqlitePool) -> Result<(), error::Error> {
+        let transactions = [
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "salary".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-07-13").unwrap(),
+                amount: dec!(-12.13),
+                memo: "groceries".to_string(),
+                ..Default::default()
+            },
+        ];
+        let tx = pool.begin().await?;
+        let original =
+            Model::commit_transactions(&transactions, SqliteStore::from_sqlite_transaction(tx))
+                .await?;
+        let committed_at = Utc::now();
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let stored = sqlite_store.get_transactions().await?;
+        drop(sqlite_store);
+        let id_of = |memo: &str| stored.iter().find(|x| x.data.memo == memo).unwrap().id;
+        let (salary, groceries) = (id_of("salary"), id_of("groceries"));
+
+        let tx = pool.begin().await?;
+        Model::set_category(
+            groceries,
+            Some("Food".to_owned()),
+            SqliteStore::from_sqlite_transaction(tx),
+        )
+        .await?;
+        let tx = pool.begin().await?;
+        Model::delete_transaction(salary, SqliteStore::from_sqlite_transaction(tx)).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+
+        assert_eq!(
+            vec![groceries],
+            sqlite_store
+                .get_transactions()
+                .await?
+                .iter()
+                .map(|x| x.id)
+                .collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            sqlite_store.get_transaction(salary).await,
+            Err(error::Error::TransactionNotFound(_))
+        ));
+        assert_eq!(
+            Report {
+                gross_revenue: dec!(0),
+                expenses: dec!(12.13),
+                net_revenue: dec!(-12.13),
+            },
+            Model::calculate_total_report(&sqlite_store.get_reports().await?)
+        );
+        assert_eq!(
+            original,
+            Model::get_report_as_of(committed_at, &mut sqlite_store).await?
+        );
+
+        let categories = |reports: Vec<CategoryReport>| {
+            reports.into_iter().map(|x| x.category).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![None],
+            categories(
+                Model::get_category_reports(None, None, Some(committed_at), &mut sqlite_store)
+                    .await?
+            )
+        );
+        assert_eq!(
+            vec![Some("Food".to_owned())],
+            categories(Model::get_category_reports(None, None, None, &mut sqlite_store).await?)
+        );
+
+        let versions = sqlite_store.get_transaction_versions(groceries).await?;
+        assert_eq!(2, versions.len());
+        assert_eq!(None, versions[0].transaction.category);
+        assert_eq!(versions[0].valid_to, versions[1].valid_from);
+        assert_eq!(None, versions[1].valid_to);
+
+        let versions = sqlite_store.get_transaction_versions(salary).await?;
+        assert_eq!(1, versions.len());
+        assert!(versions[0].valid_to.is_some());
+
+        assert!(Model::verify_chain(&mut sqlite_store).await?.is_intact());
+        assert!(Model::check_reports(false, sqlite_store)
+            .await?
+            .is_consistent());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn hash_chain(pool: SqlitePool) -> Result<(), error::Error> {
+        for memo in ["first", "second", "third"] {
+            let tx = pool.begin().await?;
+            let transactions = [Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: memo.to_string(),
+                ..Default::default()
+            }];
+            Model::commit_transactions(&transactions, SqliteStore::from_sqlite_transaction(tx))
+                .await?;
+        }
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let batches = sqlite_store.get_batches().await?;
+        let verification = Model::verify_chain(&mut sqlite_store).await?;
+        let transactions = sqlite_store.get_transactions().await?;
+        drop(sqlite_store);
+
+        assert_eq!(
+            vec![1, 2, 3],
+            batches.iter().map(|x| x.sequence).collect::<Vec<_>>()
+        );
+        assert_eq!(None, batches[0].previous_hash);
+        assert_eq!(Some(&batches[0].hash), batches[1].previous_hash.as_ref());
+        assert!(verification.is_intact());
+        assert_eq!(3, verification.verified);
+
+        let second = transactions
+            .iter()
+            .find(|x| x.data.memo == "second")
+            .unwrap()
+            .id;
+        let tx = pool.begin().await?;
+        Model::set_category(
+            second,
+            Some("Food".to_owned()),
+            SqliteStore::from_sqlite_transaction(tx),
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        assert!(Model::verify_chain(&mut sqlite_store).await?.is_intact());
+        drop(sqlite_store);
+
+        sqlx::query("UPDATE transactions SET amount = '8.73' WHERE id = ?")
+            .bind(second.to_string())
+            .execute(&pool)
+            .await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let verification = Model::verify_chain(&mut sqlite_store).await?;
+        let broken = verification.broken.unwrap();
+
+        assert_eq!(1, verification.verified);
+        assert_eq!(2, broken.sequence);
+        assert_eq!(ChainBreakReason::HashMismatch, broken.reason);
+        assert_eq!(Some(batches[1].hash.clone()), broken.expected);
+        Ok(())
+    }
+
+    fn checking_account() -> Account {
+        Account {
+            name: "checking".to_string(),
+            kind: AccountKind::Checking,
+            opening_balance: dec!(100.00),
+            opening_date: NaiveDate::from_str("2021-01-01").unwrap(),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn account_balance() {
+        let transactions = [
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2023-08-20").unwrap(),
+                amount: dec!(-12.13),
+                memo: "second".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2020-12-31").unwrap(),
+                amount: dec!(-40.00),
+                memo: "before opening".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let balance = Model::calculate_account_balance(&checking_account(), transactions.iter());
+
+        assert_eq!(balance, dec!(175.19));
+    }
+
+    #[sqlx::test]
+    async fn create_account_with_invalid_currency(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+
+        let account = Account {
+            currency: "usd".to_string(),
+            ..checking_account()
+        };
+
+        let result = Model::create_account(account, sqlite_store).await;
+
+        assert!(matches!(result, Err(error::Error::InvalidCurrency(_))));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn commit_account_transactions(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let checking = Model::create_account(checking_account(), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let savings = Model::create_account(
+            Account {
+                name: "savings".to_string(),
+                kind: AccountKind::Savings,
+                ..checking_account()
+            },
+            sqlite_store,
+        )
+        .await?;
+
+        let transactions = vec![
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2023-08-20").unwrap(),
+                amount: dec!(-12.13),
+                memo: "second".to_string(),
+                ..Default::default()
+            },
+        ];
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_account_transactions(checking.id, transactions, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+
+        let checking_balance = Model::get_account_balance(checking.id, &mut sqlite_store).await?;
+        let checking_report = Model::get_account_report(checking.id, &mut sqlite_store).await?;
+        let savings_balance = Model::get_account_balance(savings.id, &mut sqlite_store).await?;
+        let savings_report = Model::get_account_report(savings.id, &mut sqlite_store).await?;
+
+        assert_eq!(checking_balance.balance, dec!(175.19));
+        assert_eq!(
+            checking_report,
+            Report {
+                gross_revenue: dec!(87.32),
+                expenses: dec!(12.13),
+                net_revenue: dec!(75.19),
+            }
+        );
+        assert_eq!(savings_balance.balance, dec!(100.00));
+        assert_eq!(savings_report, Report::new());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn commit_transactions_to_missing_account(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let account_id = uuid::Uuid::new_v4();
+
+        let result = Model::commit_account_transactions(account_id, vec![], sqlite_store).await;
+
+        assert!(matches!(result, Err(error::Error::AccountNotFound(id)) if id == account_id));
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_candidates() {
+        let checking = Some(uuid::Uuid::new_v4());
+        let savings = Some(uuid::Uuid::new_v4());
+        let transactions: Vec<_> = [
+            ("2021-07-01", dec!(-500.00), checking),
+            ("2021-07-02", dec!(500.00), checking),
+            ("2021-07-06", dec!(500.00), savings),
+            ("2021-07-03", dec!(500.00), savings),
+            ("2021-07-20", dec!(-80.00), savings),
+            ("2021-07-30", dec!(80.00), checking),
+        ]
+        .into_iter()
+        .map(|(date, amount, account_id)| {
+            WithId::from_data(Transaction {
+                date: NaiveDate::from_str(date).unwrap(),
+                amount,
+                account_id,
+                ..Default::default()
+            })
+        })
+        .collect();
+
+        let candidates = Model::find_transfer_candidates(&transactions, 3);
+
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].outgoing, transactions[0].id);
+        assert_eq!(candidates[0].incoming, transactions[3].id);
+        assert_eq!(candidates[0].amount, dec!(500.00));
+    }
+
+    #[sqlx::test]
+    async fn link_detected_transfers(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let checking = Model::create_account(checking_account(), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let savings = Model::create_account(
+            Account {
+                name: "savings".to_string(),
+                kind: AccountKind::Savings,
+                ..checking_account()
+            },
+            sqlite_store,
+        )
+        .await?;
+
+        for (account, amount) in [(checking.id, dec!(-50.00)), (savings.id, dec!(50.00))] {
+            let transactions = vec![
+                Transaction {
+                    date: NaiveDate::from_str("2021-07-12").unwrap(),
+                    amount,
+                    memo: "transfer".to_string(),
+                    ..Default::default()
+                },
+                Transaction {
+                    date: NaiveDate::from_str("2021-07-13").unwrap(),
+                    amount: dec!(10.00),
+                    memo: "income".to_string(),
+                    ..Default::default()
+                },
+            ];
+            let tx = pool.begin().await?;
+            let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+            Model::commit_account_transactions(account, transactions, sqlite_store).await?;
+        }
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let linked =
+            Model::link_detected_transfers(Model::TRANSFER_WINDOW_DAYS, sqlite_store).await?;
+
+        assert_eq!(linked.len(), 1);
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let report = Model::calculate_total_report(sqlite_store.get_reports().await?.iter());
+        let outgoing = sqlite_store.get_transaction(linked[0].outgoing).await?;
+        let checking_balance = Model::get_account_balance(checking.id, &mut sqlite_store).await?;
+
+        assert_eq!(
+            report,
+            Report {
+                gross_revenue: dec!(20.00),
+                expenses: dec!(0),
+                net_revenue: dec!(20.00),
+            }
+        );
+        assert_eq!(outgoing.data.kind, TransactionKind::Transfer);
+        assert_eq!(outgoing.data.transfer_id, Some(linked[0].incoming));
+        assert_eq!(checking_balance.balance, dec!(60.00));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn csv_with_category() {
+        let csv = [
+            "2021-07-12, Income, 87.32, first, Jobs",
+            "2023-08-20, Expense, 12.13, second,",
+        ]
+        .join("\n");
+        let expected_transactions = vec![
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                category: Some("Jobs".to_string()),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2023-08-20").unwrap(),
+                amount: dec!(-12.13),
+                memo: "second".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let transactions: Vec<Transaction> =
+            CSVReader::read_transaction_from_csv_bytes(csv.as_bytes())
+                .collect()
+                .await;
+
+        assert_eq!(transactions, expected_transactions);
+    }
+
+    #[sqlx::test]
+    async fn category_reports_use_splits(pool: SqlitePool) -> Result<(), error::Error> {
+        let transactions = vec![
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                category: Some("Jobs".to_string()),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-08-20").unwrap(),
+                amount: dec!(-60.00),
+                memo: "hardware store".to_string(),
+                category: Some("Supplies".to_string()),
+                ..Default::default()
+            },
+        ];
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_transactions(&transactions, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let hardware_store = sqlite_store
+            .get_transactions()
+            .await?
+            .into_iter()
+            .find(|x| x.data.amount == dec!(-60.00))
+            .unwrap();
+        drop(sqlite_store);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::set_splits(
+            hardware_store.id,
+            vec![Split {
+                amount: dec!(-59.00),
+                category: Some("Supplies".to_string()),
+                memo: "nails".to_string(),
+            }],
+            sqlite_store,
+        )
+        .await;
+
+        assert!(matches!(result, Err(error::Error::SplitSumMismatch { .. })));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let imported = Transaction {
+            splits: vec![Split {
+                amount: dec!(-59.00),
+                category: Some("Supplies".to_string()),
+                memo: "nails".to_string(),
+            }],
+            ..transactions[1].clone()
+        };
+        let result = Model::commit_transactions(&[imported], sqlite_store).await;
+
+        assert!(matches!(result, Err(error::Error::SplitSumMismatch { .. })));
+
+        let splits = vec![
+            Split {
+                amount: dec!(-30.00),
+                category: Some("Supplies".to_string()),
+                memo: "nails".to_string(),
+            },
+            Split {
+                amount: dec!(-30.00),
+                category: Some("Personal".to_string()),
+                memo: "paint".to_string(),
+            },
+        ];
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::set_splits(hardware_store.id, splits.clone(), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let from_store = sqlite_store.get_transaction(hardware_store.id).await?;
+        let reports = Model::get_category_reports(None, None, None, &mut sqlite_store).await?;
+
+        assert_eq!(from_store.data.splits, splits);
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].category, Some("Jobs".to_string()));
+        assert_eq!(reports[0].report.gross_revenue, dec!(87.32));
+        assert_eq!(reports[1].category, Some("Personal".to_string()));
+        assert_eq!(reports[1].report.expenses, dec!(30.00));
+        assert_eq!(reports[2].category, Some("Supplies".to_string()));
+        assert_eq!(reports[2].report.expenses, dec!(30.00));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn exchange_rates_csv() {
+        let csv = [
+            "2021-07-12, EUR, USD, 1.1834",
+            "2021-07-12, CAD, USD, -1",
+            "2021-07-12, EUR, EUR, 1",
+            "2021-07-13, usd, CAD, 1.25",
+            "2021-07-13, USD, CAD, 1.2501",
+        ]
+        .join("\n");
+        let expected_rates = vec![
+            ExchangeRate {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                base: "EUR".to_string(),
+                quote: "USD".to_string(),
+                rate: dec!(1.1834),
+            },
+            ExchangeRate {
+                date: NaiveDate::from_str("2021-07-13").unwrap(),
+                base: "USD".to_string(),
+                quote: "CAD".to_string(),
+                rate: dec!(1.2501),
+            },
+        ];
+
+        let rates: Vec<ExchangeRate> =
+            CSVReader::read_exchange_rates_from_csv_bytes(csv.as_bytes())
+                .collect()
+                .await;
+
+        assert_eq!(rates, expected_rates);
+    }
+
+    #[sqlx::test]
+    async fn converted_report(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let euros = Model::create_account(
+            Account {
+                currency: "EUR".to_string(),
+                ..checking_account()
+            },
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_account_transactions(
+            euros.id,
+            vec![
+                Transaction {
+                    date: NaiveDate::from_str("2021-07-12").unwrap(),
+                    amount: dec!(100.00),
+                    memo: "first".to_string(),
+                    ..Default::default()
+                },
+                Transaction {
+                    date: NaiveDate::from_str("2021-07-13").unwrap(),
+                    amount: dec!(-20.00),
+                    memo: "second".to_string(),
+                    ..Default::default()
+                },
+            ],
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_transactions(
+            &[Transaction {
+                date: NaiveDate::from_str("2021-07-13").unwrap(),
+                amount: dec!(-5.00),
+                memo: "third".to_string(),
+                ..Default::default()
+            }],
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_exchange_rates(
+            &[ExchangeRate {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                base: "EUR".to_string(),
+                quote: "USD".to_string(),
+                rate: dec!(1.18345),
+            }],
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let report = Model::get_converted_report("USD", None, None, &mut sqlite_store).await?;
+
+        assert_eq!(report.currency, "USD");
+        assert_eq!(
+            report.report,
+            Report {
+                gross_revenue: dec!(118.34),
+                expenses: dec!(5.00),
+                net_revenue: dec!(113.34),
+            }
+        );
+        assert_eq!(report.unconverted.len(), 1);
+        assert_eq!(report.unconverted[0].currency, "EUR");
+        assert_eq!(report.unconverted[0].report.expenses, dec!(20.00));
+        drop(sqlite_store);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let fees = Model::create_account(
+            Account {
+                name: "fees".to_string(),
+                kind: AccountKind::Income,
+                opening_balance: dec!(0),
+                ..euros.data.clone()
+            },
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Ledger::create_journal_entry(
+            JournalEntry {
+                date: NaiveDate::from_str("2021-07-14").unwrap(),
+                memo: "fee".to_string(),
+                postings: vec![
+                    Posting {
+                        account_id: euros.id,
+                        amount: dec!(10.00),
+                    },
+                    Posting {
+                        account_id: fees.id,
+                        amount: dec!(-10.00),
+                    },
+                ],
+            },
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let global = Model::get_global_report(None, &mut sqlite_store).await?;
+        let global_as_of = Model::get_global_report(Some(Utc::now()), &mut sqlite_store).await?;
+
+        assert_eq!(
+            global,
+            vec![
+                CurrencyReport {
+                    currency: "EUR".to_string(),
+                    report: Report {
+                        gross_revenue: dec!(110.00),
+                        expenses: dec!(20.00),
+                        net_revenue: dec!(90.00),
+                    },
+                },
+                CurrencyReport {
+                    currency: "USD".to_string(),
+                    report: Report {
+                        gross_revenue: dec!(0),
+                        expenses: dec!(5.00),
+                        net_revenue: dec!(-5.00),
+                    },
+                },
+            ]
+        );
+        assert_eq!(global_as_of, global);
+        assert_eq!(
+            Report {
+                gross_revenue: dec!(110.00),
+                expenses: dec!(25.00),
+                net_revenue: dec!(85.00),
+            },
+            Model::get_report(None, &mut sqlite_store).await?
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn reconciliation(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let checking = Model::create_account(checking_account(), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_account_transactions(
+            checking.id,
+            vec![
+                Transaction {
+                    date: NaiveDate::from_str("2021-07-12").unwrap(),
+                    amount: dec!(87.32),
+                    memo: "first".to_string(),
+                    ..Default::default()
+                },
+                Transaction {
+                    date: NaiveDate::from_str("2021-08-20").unwrap(),
+                    amount: dec!(-12.13),
+                    memo: "second".to_string(),
+                    ..Default::default()
+                },
+            ],
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let transactions = sqlite_store.get_account_transactions(checking.id).await?;
+        let first = transactions
+            .iter()
+            .find(|x| x.data.memo == "first")
+            .unwrap()
+            .id;
+        let second = transactions
+            .iter()
+            .find(|x| x.data.memo == "second")
+            .unwrap()
+            .id;
+        drop(sqlite_store);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let summary = Model::start_reconciliation(
+            Reconciliation {
+                account_id: checking.id,
+                statement_date: NaiveDate::from_str("2021-07-31").unwrap(),
+                closing_balance: dec!(187.32),
+                status: ReconciliationStatus::Open,
+            },
+            sqlite_store,
+        )
+        .await?;
+        let id = summary.reconciliation.id;
+        assert_eq!(summary.cleared_balance, dec!(100.00));
+        assert_eq!(summary.difference, dec!(87.32));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::finish_reconciliation(id, sqlite_store).await;
+        assert!(matches!(
+            result,
+            Err(error::Error::ReconciliationUnbalanced(x)) if x == dec!(87.32)
+        ));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::set_cleared(id, &[second], true, sqlite_store).await;
+        assert!(matches!(
+            result,
+            Err(error::Error::TransactionNotInStatement(x)) if x == second
+        ));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let summary = Model::set_cleared(id, &[first], true, sqlite_store).await?;
+        assert_eq!(summary.difference, dec!(0));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let summary = Model::finish_reconciliation(id, sqlite_store).await?;
+        assert_eq!(
+            summary.reconciliation.data.status,
+            ReconciliationStatus::Finished
+        );
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::set_category(first, Some("Food".to_string()), sqlite_store).await;
+        assert!(matches!(
+            result,
+            Err(error::Error::TransactionReconciled(x)) if x == first
+        ));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::set_category(second, Some("Food".to_string()), sqlite_store).await?;
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn closed_period(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_transactions(
+            &[Transaction {
+                date: NaiveDate::from_str("2021-06-30").unwrap(),
+                amount: dec!(87.32),
+                memo: "first".to_string(),
+                ..Default::default()
+            }],
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::close_period(
+            NaiveDate::from_str("2021-06-30").unwrap(),
+            None,
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::commit_transactions(
+            &[
+                Transaction {
+                    date: NaiveDate::from_str("2021-07-01").unwrap(),
+                    amount: dec!(-12.13),
+                    memo: "second".to_string(),
+                    ..Default::default()
+                },
+                Transaction {
+                    date: NaiveDate::from_str("2021-06-15").unwrap(),
+                    amount: dec!(-5.00),
+                    memo: "third".to_string(),
+                    ..Default::default()
+                },
+            ],
+            sqlite_store,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(error::Error::PeriodClosed { date, .. })
+                if date == NaiveDate::from_str("2021-06-15").unwrap()
+        ));
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let first = sqlite_store.get_transactions().await?[0].id;
+        drop(sqlite_store);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::set_category(first, Some("Food".to_string()), sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::PeriodClosed { .. })));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::reopen_period(None, " ".to_string(), sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::MissingReopenReason)));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::reopen_period(None, "amended filing".to_string(), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::set_category(first, Some("Food".to_string()), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let closings = sqlite_store.get_period_closings().await?;
+        assert_eq!(closings.len(), 2);
+        assert_eq!(closings[1].closed_through, None);
+        assert_eq!(closings[1].reason.as_deref(), Some("amended filing"));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn materialize_schedules(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let checking = Model::create_account(checking_account(), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let schedule = Model::create_schedule(
+            Schedule {
+                account_id: Some(checking.id),
+                amount: dec!(-1200.00),
+                memo: "rent".to_string(),
+                category: Some("Rent".to_string()),
+                frequency: Frequency::Monthly,
+                interval: 1,
+                day_of_month: Some(1),
+                start_date: NaiveDate::from_str("2021-07-01").unwrap(),
+                end_date: None,
+                count: None,
+            },
+            sqlite_store,
+        )
+        .await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let transactions =
+            Model::materialize_schedules(NaiveDate::from_str("2021-08-15").unwrap(), sqlite_store)
+                .await?;
+        assert_eq!(transactions.len(), 2);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let transactions =
+            Model::materialize_schedules(NaiveDate::from_str("2021-09-01").unwrap(), sqlite_store)
+                .await?;
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(
+            transactions[0].date,
+            NaiveDate::from_str("2021-09-01").unwrap()
+        );
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let balance = Model::get_account_balance(checking.id, &mut sqlite_store).await?;
+        assert_eq!(balance.balance, dec!(-3500.00));
+
+        let preview = Model::get_schedule_preview(
+            schedule.id,
+            NaiveDate::from_str("2021-09-01").unwrap(),
+            2,
+            &mut sqlite_store,
+        )
+        .await?;
+        assert_eq!(
+            preview,
+            [
+                NaiveDate::from_str("2021-10-01").unwrap(),
+                NaiveDate::from_str("2021-11-01").unwrap(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn budget_report() {
+        let budget = Budget {
+            category: "Food".to_string(),
+            start_date: NaiveDate::from_str("2021-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2021-07-31").unwrap(),
+            amount: dec!(300.00),
+            rollover: true,
+        };
+        let budgets = [
+            WithId::from_data(budget.clone()),
+            WithId::from_data(Budget {
+                start_date: NaiveDate::from_str("2021-08-01").unwrap(),
+                end_date: NaiveDate::from_str("2021-08-31").unwrap(),
+                ..budget.clone()
+            }),
+            WithId::from_data(Budget {
+                category: "Rent".to_string(),
+                amount: dec!(0),
+                ..budget
+            }),
+        ];
+        let transactions = [
+            ("2021-07-02", dec!(-120.00), "Food"),
+            ("2021-07-20", dec!(-100.00), "Food"),
+            ("2021-07-21", dec!(20.00), "Food"),
+            ("2021-08-03", dec!(-380.00), "Food"),
+            ("2021-08-03", dec!(-50.00), "Fuel"),
+        ]
+        .map(|(date, amount, category)| Transaction {
+            date: NaiveDate::from_str(date).unwrap(),
+            amount,
+            category: Some(category.to_string()),
+            ..Default::default()
+        });
+
+        let lines = Model::calculate_budget_report(&budgets, &transactions);
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].actual, dec!(200.00));
+        assert_eq!(lines[0].remaining, dec!(100.00));
+        assert_eq!(lines[0].percent_used, Some(dec!(66.67)));
+        assert_eq!(lines[1].carried_over, dec!(100.00));
+        assert_eq!(lines[1].planned, dec!(400.00));
+        assert_eq!(lines[1].remaining, dec!(20.00));
+        assert_eq!(lines[1].percent_used, Some(dec!(95.00)));
+        assert_eq!(lines[2].actual, dec!(0));
+        assert_eq!(lines[2].percent_used, None);
+    }
+
+    #[sqlx::test]
+    async fn overlapping_budget(pool: SqlitePool) -> Result<(), error::Error> {
+        let budget = Budget {
+            category: "Food".to_string(),
+            start_date: NaiveDate::from_str("2021-07-01").unwrap(),
+            end_date: NaiveDate::from_str("2021-07-31").unwrap(),
+            amount: dec!(300.00),
+            rollover: false,
+        };
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let first = Model::create_budget(budget.clone(), sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::create_budget(
+            Budget {
+                start_date: NaiveDate::from_str("2021-07-31").unwrap(),
+                end_date: NaiveDate::from_str("2021-08-30").unwrap(),
+                ..budget.clone()
+            },
+            sqlite_store,
+        )
+        .await;
+        assert!(matches!(result, Err(error::Error::OverlappingBudget(id)) if id == first.id));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::create_budget(
+            Budget {
+                category: "Rent".to_string(),
+                ..budget
+            },
+            sqlite_store,
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[test]
+    fn forecast() {
+        let account_id = uuid::Uuid::new_v4();
+        let today = NaiveDate::from_str("2021-07-31").unwrap();
+        let history = [
+            ("2021-07-01", dec!(-50.00), None, TransactionKind::Regular),
+            ("2021-07-10", dec!(-10.00), None, TransactionKind::Regular),
+            ("2021-07-15", dec!(-500.00), None, TransactionKind::Transfer),
+            (
+                "2021-07-20",
+                dec!(-70.00),
+                Some(uuid::Uuid::new_v4()),
+                TransactionKind::Regular,
+            ),
+            ("2021-06-01", dec!(-900.00), None, TransactionKind::Regular),
+        ]
+        .map(|(date, amount, schedule_id, kind)| Transaction {
+            date: NaiveDate::from_str(date).unwrap(),
+            amount,
+            schedule_id,
+            kind,
+            category: Some("Food".to_string()),
+            ..Default::default()
+        });
+        let schedules = [Schedule {
+            account_id: Some(account_id),
+            amount: dec!(-70.00),
+            memo: "phone".to_string(),
+            category: None,
+            frequency: Frequency::Weekly,
+            interval: 1,
+            day_of_month: None,
+            start_date: NaiveDate::from_str("2021-07-20").unwrap(),
+            end_date: None,
+            count: None,
+        }];
+        let options = ForecastOptions {
+            days: 5,
+            lookback_days: 30,
+            threshold: dec!(0),
+        };
+
+        let forecast = Model::calculate_forecast(
+            account_id,
+            dec!(75.00),
+            today,
+            &options,
+            &history,
+            &schedules,
+        )
+        .unwrap();
+
+        assert_eq!(forecast.averages.len(), 1);
+        assert_eq!(forecast.averages[0].daily_amount, dec!(-2));
+        assert_eq!(forecast.series.len(), 6);
+        assert_eq!(forecast.series[1].balance, dec!(73.00));
+        assert_eq!(forecast.series[3].balance, dec!(-1.00));
+        assert_eq!(
+            forecast.below_threshold_on,
+            Some(NaiveDate::from_str("2021-08-03").unwrap())
+        );
+        assert_eq!(forecast.series[5].balance, dec!(-5.00));
+    }
+
+    #[test]
+    fn forecast_bounds() {
+        let account_id = uuid::Uuid::new_v4();
+        let today = NaiveDate::from_str("2021-07-31").unwrap();
+        let forecast = |today, days, lookback_days| {
+            let options = ForecastOptions {
+                days,
+                lookback_days,
+                threshold: dec!(0),
+            };
+            Model::calculate_forecast(account_id, dec!(0), today, &options, [], &[])
+        };
+
+        assert!(forecast(
+            today,
+            Model::MAX_FORECAST_DAYS,
+            Model::MAX_FORECAST_LOOKBACK_DAYS
+        )
+        .is_ok());
+        for (today, days, lookback_days) in [
+            (today, 30, 0),
+            (today, Model::MAX_FORECAST_DAYS + 1, 30),
+            (today, u32::MAX, 30),
+            (today, 30, Model::MAX_FORECAST_LOOKBACK_DAYS + 1),
+            (today, 30, u32::MAX),
+            (NaiveDate::MIN, 30, 30),
+            (NaiveDate::MAX, 30, 30),
+        ] {
+            assert!(matches!(
+                forecast(today, days, lookback_days),
+                Err(error::Error::InvalidForecast)
+            ));
+        }
+    }
+
+    #[sqlx::test]
+    async fn account_statement(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let checking = Model::create_account(checking_account(), sqlite_store).await?;
+
+        // The ids sort opposite to the order of insertion, which orders the
+        // transactions of a same day.
+        let transactions = [
+            ("2021-07-20", dec!(-12.13), "third"),
+            ("2021-07-12", dec!(87.32), "first"),
+            ("2021-07-20", dec!(-5), "fourth"),
+            ("2021-07-15", dec!(10.015), "second"),
+            ("2020-12-31", dec!(-40.00), "before opening"),
+        ]
+        .map(|(date, amount, memo)| Transaction {
+            date: NaiveDate::from_str(date).unwrap(),
+            amount,
+            memo: memo.to_string(),
+            account_id: Some(checking.id),
+            ..Default::default()
+        });
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        sqlite_store
+            .create_transactions(
+                Uuid::new_v4(),
+                transactions
+                    .iter()
+                    .zip((1..=5).rev())
+                    .map(|(data, id)| WithId {
+                        id: Uuid::from_u128(id),
+                        data,
+                    }),
+            )
+            .await?;
+        sqlite_store.commit().await?;
+
+        let statement = |after, limit| {
+            let pool = pool.clone();
+            async move {
+                let tx = pool.begin().await?;
+                Model::get_account_statement(
+                    checking.id,
+                    after,
+                    limit,
+                    SqliteStore::from_sqlite_transaction(tx),
+                )
+                .await
+            }
+        };
+        let lines = statement(None, 2).await?;
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].transaction.data.memo, "first");
+        assert_eq!(lines[0].balance, dec!(187.32));
+        assert_eq!(lines[1].transaction.data.memo, "second");
+        assert_eq!(lines[1].balance, dec!(197.335));
+
+        let lines = statement(Some(lines[1].transaction.id), 2).await?;
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].transaction.data.memo, "third");
+        assert_eq!(lines[0].balance, dec!(185.205));
+        assert_eq!(lines[1].transaction.data.memo, "fourth");
+        assert_eq!(lines[1].balance, dec!(180.205));
+        assert!(lines[0].transaction.id > lines[1].transaction.id);
+
+        let lines = statement(Some(lines[0].transaction.id), 0).await?;
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].transaction.data.memo, "fourth");
+        assert_eq!(lines[0].balance, dec!(180.205));
+
+        let lines = statement(Some(lines[0].transaction.id), 2).await?;
+
+        assert!(lines.is_empty());
+
+        let result = statement(Some(Uuid::new_v4()), 2).await;
+
+        assert!(matches!(result, Err(error::Error::TransactionNotFound(_))));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn account_statement_checkpoints(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let checking = Model::create_account(checking_account(), sqlite_store).await?;
+
+        // Too many digits for the integers of SQLite, but not for Decimal;
+        // stored as text, as the store binds amounts as floats.
+        let large = dec!(1234567890123456789012.345);
+        let commit = |transactions: Vec<(&'static str, Decimal)>| {
+            let pool = pool.clone();
+            async move {
+                let tx = pool.begin().await?;
+                Model::commit_account_transactions(
+                    checking.id,
+                    transactions
+                        .into_iter()
+                        .map(|(date, amount)| Transaction {
+                            date: NaiveDate::from_str(date).unwrap(),
+                            amount,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    SqliteStore::from_sqlite_transaction(tx),
+                )
+                .await
+            }
+        };
+        commit(vec![
+            ("2021-07-12", dec!(0)),
+            ("2021-07-13", dec!(0)),
+            ("2021-07-14", dec!(0.001)),
+            ("2021-07-15", dec!(1)),
+        ])
+        .await?;
+        sqlx::query("UPDATE transactions SET amount = ? WHERE amount = '0.0'")
+            .bind(large.to_string())
+            .execute(&pool)
+            .await?;
+        let statement = |after| {
+            let pool = pool.clone();
+            async move {
+                let tx = pool.begin().await?;
+                Model::get_account_statement(
+                    checking.id,
+                    after,
+                    1,
+                    SqliteStore::from_sqlite_transaction(tx),
+                )
+                .await
+            }
+        };
+        let checkpoints = || async {
+            let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM account_checkpoints")
+                .fetch_one(&pool)
+                .await?;
+            Ok::<_, error::Error>(count)
+        };
+
+        let mut lines = statement(None).await?;
+        let mut balances = vec![];
+        while let Some(line) = lines.pop() {
+            balances.push(line.balance);
+            lines = statement(Some(line.transaction.id)).await?;
+        }
+        let expected = dec!(100) + large + large;
+        assert_eq!(
+            vec![
+                dec!(100) + large,
+                expected,
+                expected + dec!(0.001),
+                expected + dec!(1.001)
+            ],
+            balances
+        );
+        assert_eq!(4, checkpoints().await?);
+
+        commit(vec![("2021-07-13", dec!(-2.5))]).await?;
+        assert_eq!(2, checkpoints().await?);
+
+        let lines = statement(None).await?;
+        let lines = statement(Some(lines[0].transaction.id)).await?;
+        let lines = statement(Some(lines[0].transaction.id)).await?;
+        let lines = statement(Some(lines[0].transaction.id)).await?;
+        assert_eq!(expected - dec!(2.5) + dec!(0.001), lines[0].balance);
+        Ok(())
+    }
+
+    #[test]
+    fn comparison() {
+        let [previous, current] = [
+            [
+                ("2020-07-02", dec!(100.00), "Sales"),
+                ("2020-07-03", dec!(-40.00), "Food"),
+            ],
+            [
+                ("2021-07-02", dec!(150.00), "Sales"),
+                ("2021-07-03", dec!(-30.00), "Fuel"),
+            ],
+        ]
+        .map(|transactions| {
+            transactions.map(|(date, amount, category)| Transaction {
+                date: NaiveDate::from_str(date).unwrap(),
+                amount,
+                category: Some(category.to_string()),
+                ..Default::default()
+            })
+        });
+
+        let report = Model::calculate_comparison(&previous, &current);
+
+        assert_eq!(report.total.net_revenue.absolute, dec!(60.00));
+        assert_eq!(report.total.net_revenue.percent, Some(dec!(100.00)));
+        assert_eq!(report.total.expenses.percent, Some(dec!(-25.00)));
+        assert_eq!(report.categories.len(), 3);
+        assert_eq!(report.categories[0].category.as_deref(), Some("Food"));
+        assert_eq!(report.categories[0].comparison.current, Report::new());
+        assert_eq!(report.categories[1].category.as_deref(), Some("Fuel"));
+        assert_eq!(report.categories[1].comparison.expenses.percent, None);
+        assert_eq!(
+            report.categories[2].comparison.gross_revenue.percent,
+            Some(dec!(50.00))
+        );
+    }
+
+    #[test]
+    fn payee_ranking() {
+        let transactions = [
+            ("Shell Oil 08/20 card xxxx4532", dec!(-40.00)),
+            ("SHELL OIL 08/27 CARD XXXX4532", dec!(-35.00)),
+            ("Chevron #0042", dec!(-50.00)),
+            ("347 Woodrow", dec!(1200.00)),
+            ("Invoice A1B2C3 347 Woodrow", dec!(300.00)),
+            ("Acme Ltd", dec!(200.00)),
+        ]
+        .map(|(memo, amount)| Transaction {
+            date: NaiveDate::from_str("2021-07-12").unwrap(),
+            amount,
+            memo: memo.to_string(),
+            ..Default::default()
+        });
+        let aliases = [
+            PayeeAlias {
+                memo: "chevron".to_string(),
+                payee: "Fuel".to_string(),
+            },
+            PayeeAlias {
+                memo: "shell oil card".to_string(),
+                payee: "Fuel".to_string(),
+            },
+        ];
+
+        let ranking = Model::calculate_payee_ranking(&transactions, &aliases);
+
+        assert_eq!(ranking.expenses.len(), 1);
+        assert_eq!(ranking.expenses[0].payee, "Fuel");
+        assert_eq!(ranking.expenses[0].total, dec!(125.00));
+        assert_eq!(ranking.income.len(), 3);
+        assert_eq!(ranking.income[0].payee, "347 woodrow");
+        assert_eq!(ranking.income[0].total, dec!(1200.00));
+        assert_eq!(ranking.income[1].payee, "invoice 347 woodrow");
+        assert_eq!(ranking.income[2].payee, "acme ltd");
+    }
+
+    #[test]
+    fn tax_report() {
+        let transactions = [
+            ("2021-02-10", dec!(8000.00), None),
+            ("2021-03-01", dec!(-1000.00), Some("Supplies")),
+            ("2021-03-02", dec!(-500.00), Some("Personal")),
+            ("2021-05-10", dec!(6000.00), None),
+            ("2021-11-30", dec!(-2000.00), Some("Supplies")),
+        ]
+        .map(|(date, amount, category)| Transaction {
+            date: NaiveDate::from_str(date).unwrap(),
+            amount,
+            category: category.map(ToString::to_string),
+            ..Default::default()
+        });
+        let config = TaxConfig {
+            rates: TaxRates::Brackets(vec![
+                TaxBracket {
+                    threshold: dec!(0),
+                    rate: dec!(0.10),
+                },
+                TaxBracket {
+                    threshold: dec!(10000),
+                    rate: dec!(0.20),
+                },
+            ]),
+            deductible_categories: vec!["Supplies".to_string()],
+        };
+
+        let report = Model::calculate_tax_report(2021, &transactions, &config).unwrap();
+
+        let quarters = &report.quarters;
+        assert_eq!(quarters.len(), 4);
+        assert_eq!(quarters[0].deductible_expenses, dec!(1000.00));
+        assert_eq!(quarters[0].taxable_income, dec!(7000.00));
+        assert_eq!(quarters[0].tax, dec!(700.00));
+        assert_eq!(quarters[1].cumulative_taxable_income, dec!(13000.00));
+        assert_eq!(quarters[1].tax, dec!(900.00));
+        assert_eq!(quarters[2].tax, dec!(0));
+        assert_eq!(
+            quarters[3].end_date,
+            NaiveDate::from_str("2021-12-31").unwrap()
+        );
+        assert_eq!(quarters[3].taxable_income, dec!(-2000.00));
+        assert_eq!(quarters[3].tax, dec!(0));
+        assert_eq!(quarters[3].cumulative_tax, dec!(1200.00));
+
+        let last_year = NaiveDate::MAX.year();
+        assert!(matches!(
+            Model::calculate_tax_report(last_year, &transactions, &config),
+            Err(error::Error::InvalidYear(year)) if year == last_year
+        ));
+    }
+
+    #[test]
+    fn vat_return() {
+        let date = NaiveDate::from_str("2023-04-10").unwrap();
+        let transactions = [
+            (dec!(120.00), Some("STD"), TransactionKind::Regular),
+            (dec!(-60.00), Some("STD"), TransactionKind::Regular),
+            (dec!(-10.50), Some("RED"), TransactionKind::Regular),
+            (dec!(-99.00), Some("OLD"), TransactionKind::Regular),
+            (dec!(-500.00), Some("STD"), TransactionKind::Transfer),
+            (dec!(-25.00), None, TransactionKind::Regular),
+        ]
+        .map(|(amount, tax_code, kind)| WithId {
+            id: Uuid::new_v4(),
+            data: Transaction {
+                date,
+                amount,
+                tax_code: tax_code.map(ToString::to_string),
+                kind,
+                ..Default::default()
+            },
+        });
+        let config = VatConfig {
+            codes: [("STD", dec!(0.20)), ("RED", dec!(0.05))]
+                .map(|(code, rate)| VatCode {
+                    code: code.to_owned(),
+                    rates: vec![VatRate {
+                        rate,
+                        from: None,
+                        to: None,
+                    }],
+                })
+                .to_vec(),
+        };
+
+        let vat_return = Model::calculate_vat_return(date, date, &transactions, &config);
+
+        assert_eq!(vat_return.lines.len(), 2);
+        assert_eq!(vat_return.lines[0].tax_code, "RED");
+        assert_eq!(vat_return.lines[0].purchases_net, dec!(10.00));
+        assert_eq!(vat_return.lines[0].input_tax, dec!(0.50));
+        assert_eq!(vat_return.lines[1].sales_net, dec!(100.00));
+        assert_eq!(vat_return.lines[1].output_tax, dec!(20.00));
+        assert_eq!(vat_return.lines[1].input_tax, dec!(10.00));
+        assert_eq!(vat_return.output_tax, dec!(20.00));
+        assert_eq!(vat_return.input_tax, dec!(10.50));
+        assert_eq!(vat_return.net_tax, dec!(9.50));
+        assert_eq!(vat_return.unrated, vec![transactions[3].id]);
+    }
+
+    #[test]
+    fn mileage_report() {
+        let trips = [
+            ("2021-12-30", dec!(10.5)),
+            ("2022-01-03", dec!(20)),
+            ("2022-02-01", dec!(7)),
+            ("2024-01-02", dec!(3)),
+        ]
+        .map(|(date, distance)| {
+            WithId::from_data(Trip {
+                date: NaiveDate::from_str(date).unwrap(),
+                start_odometer: None,
+                end_odometer: None,
+                distance,
+                purpose: "delivery".to_string(),
+                transaction_id: None,
+            })
+        });
+        let transactions = [
+            (dec!(-18.77), "Fuel", None),
+            (dec!(-27.50), "Repairs", None),
+            (dec!(-40.00), "SHELL OIL", Some("fuel")),
+            (dec!(-12.00), "Fuel", Some("Personal")),
+            (dec!(35.00), "Fuel", None),
+        ]
+        .map(|(amount, memo, category)| Transaction {
+            date: NaiveDate::from_str("2022-01-03").unwrap(),
+            amount,
+            memo: memo.to_string(),
+            category: category.map(ToString::to_string),
+            ..Default::default()
+        });
+        let config = MileageConfig {
+            rates: [(2021, dec!(0.56)), (2022, dec!(0.585))].into(),
+            vehicle_expenses: vec!["Fuel".to_string(), "Repairs".to_string()],
+        };
+
+        let report = Model::calculate_mileage_report(&trips, &transactions, &config);
+
+        assert_eq!(report.trips, 4);
+        assert_eq!(report.distance, dec!(40.5));
+        assert_eq!(report.years.len(), 2);
+        assert_eq!(report.years[0].deductible, dec!(5.88));
+        assert_eq!(report.years[1].distance, dec!(27));
+        assert_eq!(report.years[1].deductible, dec!(15.80));
+        assert_eq!(report.deductible, dec!(21.68));
+        assert_eq!(report.unrated, vec![trips[3].id]);
+        assert_eq!(report.vehicle_expenses.expenses, dec!(86.27));
+        assert_eq!(report.expenses.len(), 2);
+        assert_eq!(report.expenses[0].category.as_deref(), Some("Fuel"));
+        assert_eq!(report.expenses[0].report.expenses, dec!(58.77));
+    }
+
+    #[sqlx::test]
+    async fn trips(pool: SqlitePool) -> Result<(), error::Error> {
+        let csv = [
+            "# date, start, end, distance, purpose, transaction",
+            "2020-07-04, 1200, 1212.5, , 347 Woodrow",
+            "2020-07-06, , , 8, 219 Pleasant",
+            "2020-07-07, 1300, 1290, , backwards",
+        ]
+        .join("\n");
+        let trips: Vec<Trip> = CSVReader::read_trips_from_csv_bytes(csv.as_bytes())
+            .collect()
+            .await;
+        assert_eq!(trips.len(), 2);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let mut trips = Model::create_trips(trips, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let unlinked = Trip {
+            transaction_id: Some(Uuid::new_v4()),
+            ..trips[1].data.clone()
+        };
+        let result = Model::create_trips(vec![unlinked], sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::TransactionNotFound(_))));
+
+        let transactions = [dec!(120.00), dec!(-18.77)].map(|amount| Transaction {
+            date: NaiveDate::from_str("2020-07-04").unwrap(),
+            amount,
+            ..Default::default()
+        });
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_transactions(&transactions, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let mut stored = sqlite_store.get_transactions().await?;
+        stored.sort_by_key(|x| x.data.amount);
+        drop(sqlite_store);
+
+        let linked = |transaction_id| Trip {
+            transaction_id: Some(transaction_id),
+            ..trips[1].data.clone()
+        };
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::create_trips(vec![linked(stored[0].id)], sqlite_store).await;
+        assert!(matches!(
+            result,
+            Err(error::Error::TripTransactionNotIncome(id)) if id == stored[0].id
+        ));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let income = Model::create_trips(vec![linked(stored[1].id)], sqlite_store).await?;
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::delete_trip(income[0].id, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::delete_trip(trips[1].id, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::delete_trip(trips[1].id, sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::TripNotFound(_))));
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let stored = sqlite_store.get_trips(None, None).await?;
+        trips.truncate(1);
+        assert_eq!(stored, trips);
+        assert_eq!(stored[0].data.distance, dec!(12.5));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn attachments(pool: SqlitePool) -> Result<(), error::Error> {
+        let transactions =
+            [dec!(-18.77), dec!(-120.00), dec!(-250.00), dec!(400.00)].map(|amount| Transaction {
+                date: NaiveDate::from_str("2020-07-01").unwrap(),
+                amount,
+                ..Default::default()
+            });
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_transactions(&transactions, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let mut stored = sqlite_store.get_transactions_in_period(None, None).await?;
+        stored.sort_by_key(|x| x.data.amount);
+        drop(sqlite_store);
+
+        let config = Config::default();
+        let receipt = b"%PDF-1.7 receipt".to_vec();
+        for file_name in ["receipt.pdf", "copy.pdf"] {
+            let tx = pool.begin().await?;
+            let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+            Model::add_attachment(
+                stored[0].id,
+                file_name,
+                receipt.clone(),
+                &config,
+                sqlite_store,
+            )
+            .await?;
+        }
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::add_attachment(
+            stored[1].id,
+            "page.html",
+            b"<html>".to_vec(),
+            &config,
+            sqlite_store,
+        )
+        .await;
+        assert!(matches!(result, Err(error::Error::UnsupportedAttachment)));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let large = [b"%PDF-".as_slice(), &vec![0; config.max_attachment_size()]].concat();
+        let result =
+            Model::add_attachment(stored[1].id, "large.pdf", large, &config, sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::AttachmentTooLarge(_))));
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let attachments = sqlite_store.get_attachments(stored[0].id).await?;
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].data.hash, attachments[1].data.hash);
+        assert_eq!(attachments[1].data.file_name, "copy.pdf");
+
+        let (attachment, content) =
+            Model::get_attachment_file(attachments[0].id, &mut sqlite_store).await?;
+        assert_eq!(attachment.mime_type(), "application/pdf");
+        assert_eq!(content, receipt);
+
+        let missing = Model::get_missing_receipts(dec!(100), None, None, &mut sqlite_store).await?;
+        assert_eq!(missing.transactions.len(), 1);
+        assert_eq!(missing.total, dec!(120.00));
+
+        let missing = Model::get_missing_receipts(dec!(0), None, None, &mut sqlite_store).await?;
+        assert_eq!(missing.transactions.len(), 2);
+        assert_eq!(missing.total, dec!(138.77));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn invoices(pool: SqlitePool) -> Result<(), error::Error> {
+        let date = |x| NaiveDate::from_str(x).unwrap();
+        let transactions = [dec!(40.00), dec!(35.00), dec!(-18.77)].map(|amount| Transaction {
+            date: date("2020-07-10"),
+            amount,
+            ..Default::default()
+        });
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_transactions(&transactions, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let mut stored = sqlite_store.get_transactions_in_period(None, None).await?;
+        stored.sort_by_key(|x| x.data.amount);
+        drop(sqlite_store);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let customer = Model::create_customer(
+            Customer {
+                name: "Woodrow".to_string(),
+                email: None,
+            },
+            sqlite_store,
+        )
+        .await?;
+
+        let mut ids = vec![];
+        for (number, issue_date, due_date) in [
+            ("2020-001", "2020-04-01", "2020-05-01"),
+            ("2020-002", "2020-07-01", "2020-07-31"),
+        ] {
+            let tx = pool.begin().await?;
+            let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+            let invoice = Invoice {
+                customer_id: customer.id,
+                number: number.to_string(),
+                issue_date: date(issue_date),
+                due_date: date(due_date),
+                status: InvoiceStatus::Sent,
+                lines: vec![InvoiceLine {
+                    description: "delivery".to_string(),
+                    quantity: dec!(3),
+                    unit_price: dec!(25.00),
+                }],
+            };
+            let summary = Model::create_invoice(invoice, date("2020-07-10"), sqlite_store).await?;
+            assert_eq!(summary.status, InvoiceStatus::Draft);
+            assert_eq!(summary.total, dec!(75.00));
+            ids.push(summary.invoice.id);
+        }
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result =
+            Model::pay_invoice(ids[0], stored[2].id, None, date("2020-07-10"), sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::InvoiceNotSent(_))));
+
+        for id in &ids {
+            let tx = pool.begin().await?;
+            let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+            Model::send_invoice(*id, date("2020-07-10"), sqlite_store).await?;
+        }
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result =
+            Model::pay_invoice(ids[0], stored[0].id, None, date("2020-07-10"), sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::InvalidPayment(_))));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let summary = Model::pay_invoice(
+            ids[0],
+            stored[2].id,
+            Some(dec!(30.00)),
+            date("2020-07-10"),
+            sqlite_store,
+        )
+        .await?;
+        assert_eq!(summary.outstanding, dec!(45.00));
+        assert_eq!(summary.status, InvoiceStatus::Overdue);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let summary =
+            Model::pay_invoice(ids[1], stored[2].id, None, date("2020-07-10"), sqlite_store)
+                .await?;
+        assert_eq!(summary.paid, dec!(10.00));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result =
+            Model::pay_invoice(ids[1], stored[2].id, None, date("2020-07-10"), sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::InvalidPayment(_))));
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let aging = Model::get_receivables_aging(date("2020-08-15"), &mut sqlite_store).await?;
+        assert_eq!(aging.lines.len(), 1);
+        assert_eq!(aging.lines[0].name, "Woodrow");
+        assert_eq!(aging.total.days_0_30, dec!(65.00));
+        assert_eq!(aging.total.days_61_90, dec!(0));
+        assert_eq!(aging.total.days_over_90, dec!(45.00));
+        assert_eq!(aging.total.total, dec!(110.00));
+
+        let aging = Model::get_receivables_aging(date("2020-07-05"), &mut sqlite_store).await?;
+        assert_eq!(aging.total.days_0_30, dec!(75.00));
+        assert_eq!(aging.total.days_61_90, dec!(75.00));
+        assert_eq!(aging.total.total, dec!(150.00));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bills(pool: SqlitePool) -> Result<(), error::Error> {
+        let date = |x| NaiveDate::from_str(x).unwrap();
+        let today = date("2020-07-10");
+        let transactions = [dec!(-50.00), dec!(40.00)].map(|amount| Transaction {
+            date: today,
+            amount,
+            ..Default::default()
+        });
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        Model::commit_transactions(&transactions, sqlite_store).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let mut stored = sqlite_store.get_transactions_in_period(None, None).await?;
+        stored.sort_by_key(|x| x.data.amount);
+        drop(sqlite_store);
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let vendor = Model::create_vendor(
+            Vendor {
+                name: "Garage".to_string(),
+                email: None,
+            },
+            sqlite_store,
+        )
+        .await?;
+
+        let mut ids = vec![];
+        for (number, issue_date, due_date) in [
+            ("A-17", "2020-03-01", "2020-03-31"),
+            ("A-18", "2020-07-01", "2020-07-20"),
+            ("A-19", "2020-07-05", "2020-09-01"),
+        ] {
+            let tx = pool.begin().await?;
+            let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+            let bill = Bill {
+                vendor_id: vendor.id,
+                number: number.to_string(),
+                issue_date: date(issue_date),
+                due_date: date(due_date),
+                amount: dec!(80.00),
+                memo: "repairs".to_string(),
+            };
+            ids.push(Model::create_bill(bill, today, sqlite_store).await?.bill.id);
+        }
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let result = Model::pay_bill(ids[0], stored[1].id, None, today, sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::InvalidPayment(_))));
+
+        let tx = pool.begin().await?;
+        let sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let summary = Model::pay_bill(ids[0], stored[0].id, None, today, sqlite_store).await?;
+        assert_eq!(summary.paid, dec!(50.00));
+        assert_eq!(summary.outstanding, dec!(30.00));
+        assert_eq!(summary.status, BillStatus::Overdue);
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let upcoming = Model::get_upcoming_bills(today, 30, &mut sqlite_store).await?;
+        assert_eq!(upcoming.len(), 1);
+        assert_eq!(upcoming[0].bill.id, ids[1]);
+        let result = Model::get_upcoming_bills(today, u64::MAX, &mut sqlite_store).await;
+        assert!(matches!(result, Err(error::Error::InvalidUpcomingDays(_))));
+
+        let aging = Model::get_payables_aging(today, &mut sqlite_store).await?;
+        assert_eq!(aging.lines.len(), 1);
+        assert_eq!(aging.total.days_0_30, dec!(160.00));
+        assert_eq!(aging.total.days_over_90, dec!(30.00));
+
+        let aging = Model::get_payables_aging(date("2020-07-09"), &mut sqlite_store).await?;
+        assert_eq!(aging.total.days_0_30, dec!(160.00));
+        assert_eq!(aging.total.days_over_90, dec!(80.00));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn projections(pool: SqlitePool) -> Result<(), error::Error> {
+        let transactions = [
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "salary".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-07-13").unwrap(),
+                amount: dec!(-12.13),
+                memo: "groceries".to_string(),
+                ..Default::default()
+            },
+        ];
+        let tx = pool.begin().await?;
+        Model::commit_transactions(&transactions, SqliteStore::from_sqlite_transaction(tx)).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let stored = sqlite_store.get_transactions().await?;
+        drop(sqlite_store);
+        let id_of = |memo: &str| stored.iter().find(|x| x.data.memo == memo).unwrap().id;
+        let (salary, groceries) = (id_of("salary"), id_of("groceries"));
+
+        let tx = pool.begin().await?;
+        Model::set_category(
+            groceries,
+            Some("Food".to_owned()),
+            SqliteStore::from_sqlite_transaction(tx),
+        )
+        .await?;
+        let tx = pool.begin().await?;
+        Model::delete_transaction(salary, SqliteStore::from_sqlite_transaction(tx)).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let events = sqlite_store.get_events(0).await?;
+        assert_eq!(
+            vec![
+                "TransactionImported",
+                "TransactionImported",
+                "CategoryAssigned",
+                "TransactionDeleted"
+            ],
+            events.iter().map(|x| x.event.kind()).collect::<Vec<_>>()
+        );
+        assert_eq!(groceries, events[2].event.transaction_id());
+        let expected = sqlite_store.get_reports_with_ids().await?;
+        assert_eq!(
+            expected
+                .iter()
+                .map(|x| (x.id, x.data))
+                .collect::<BTreeMap<_, _>>(),
+            Model::project_reports(&events)?
+        );
+
+        let tampered = WithId {
+            id: expected[0].id,
+            data: Report::default(),
+        };
+        sqlite_store.update_report(&tampered).await?;
+        let run = Model::catch_up_projections(sqlite_store).await?;
+        assert_eq!((events.last().unwrap().sequence, 0), (run.from, run.events));
+
+        let tx = pool.begin().await?;
+        let run = Model::replay_projections(SqliteStore::from_sqlite_transaction(tx)).await?;
+        assert_eq!((4, 1), (run.events, run.reports));
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        assert_eq!(expected, sqlite_store.get_reports_with_ids().await?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn project_events_without_import() {
+        let report_id = Some(Uuid::new_v4());
+        let transaction_id = Uuid::new_v4();
+        let record = |sequence, event| EventRecord {
+            sequence,
+            recorded_at: Utc::now(),
+            report_id,
+            event,
+        };
+        let assigned = record(
+            2,
+            Event::CategoryAssigned {
+                transaction_id,
+                category: Some("Food".to_owned()),
+            },
+        );
+        let result = Model::project_reports([&assigned]);
+        assert!(matches!(
+            result,
+            Err(error::Error::EventWithoutTransaction(2, id)) if id == transaction_id
+        ));
+
+        let imported = record(
+            1,
+            Event::TransactionImported {
+                transaction_id,
+                transaction: Transaction {
+                    amount: dec!(-12.13),
+                    ..Default::default()
+                },
+            },
+        );
+        let deleted = record(2, Event::TransactionDeleted { transaction_id });
+        let edited = record(
+            3,
+            Event::TransactionEdited {
+                transaction_id,
+                transaction: Transaction::default(),
+            },
+        );
+        let result = Model::project_reports([&imported, &deleted, &edited]);
+        assert!(matches!(
+            result,
+            Err(error::Error::EventWithoutTransaction(3, _))
+        ));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn project_transactions_stored_before_events(
+        pool: SqlitePool,
+    ) -> Result<(), error::Error> {
+        const EVENTS_VERSION: i64 = 20_240_212_090_000;
+        const IMPORT_EVENTS_VERSION: i64 = 20_240_304_090_000;
+        let report_id = Uuid::new_v4();
+        let (salary, groceries, deleted) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        apply_migrations(&pool, ..EVENTS_VERSION).await?;
+        sqlx::query("INSERT INTO report (id, gross_revenue, expenses) VALUES (?, '0', '0')")
+            .bind(report_id.to_string())
+            .execute(&pool)
+            .await?;
+        sqlx::query(
+            "INSERT INTO transactions (id, date, amount, memo, report_id, valid_to)
+            VALUES (?, '2021-07-12', '87.32', 'salary', ?, NULL),
+                (?, '2021-07-13', '-12.13', 'groceries', ?, NULL),
+                (?, '2021-07-14', '-5.00', 'deleted', ?, '2021-08-01T00:00:00.000000Z')",
+        )
+        .bind(salary.to_string())
+        .bind(report_id.to_string())
+        .bind(groceries.to_string())
+        .bind(report_id.to_string())
+        .bind(deleted.to_string())
+        .bind(report_id.to_string())
+        .execute(&pool)
+        .await?;
+        apply_migrations(&pool, EVENTS_VERSION..IMPORT_EVENTS_VERSION).await?;
+        sqlx::query("UPDATE transactions SET category = 'Food' WHERE id = ?")
+            .bind(groceries.to_string())
+            .execute(&pool)
+            .await?;
+        sqlx::query(
+            "INSERT INTO events (recorded_at, kind, transaction_id, report_id, data)
+            VALUES ('2021-08-02T00:00:00.000000Z', 'CategoryAssigned', ?, ?, ?)",
+        )
+        .bind(groceries.to_string())
+        .bind(report_id.to_string())
+        .bind(format!(
+            r#"{{"type":"CategoryAssigned","transaction_id":"{groceries}","category":"Food"}}"#
+        ))
+        .execute(&pool)
+        .await?;
+        apply_migrations(&pool, IMPORT_EVENTS_VERSION..).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let events = sqlite_store.get_events(0).await?;
+        assert_eq!(
+            vec![
+                "TransactionImported",
+                "TransactionImported",
+                "TransactionImported",
+                "TransactionDeleted",
+                "CategoryAssigned"
+            ],
+            events.iter().map(|x| x.event.kind()).collect::<Vec<_>>()
+        );
+        assert_eq!(deleted, events[3].event.transaction_id());
+        assert_eq!(groceries, events[4].event.transaction_id());
+
+        let run = Model::catch_up_projections(sqlite_store).await?;
+        assert_eq!((0, 5, 1), (run.from, run.events, run.reports));
+        let tx = pool.begin().await?;
+        let check = Model::check_reports(false, SqliteStore::from_sqlite_transaction(tx)).await?;
+        assert!(check.mismatches.is_empty());
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let reports = sqlite_store.get_reports_with_ids().await?;
+        assert_eq!(dec!(87.32), reports[0].data.gross_revenue);
+        assert_eq!(dec!(12.13), reports[0].data.expenses);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn change_feed(pool: SqlitePool) -> Result<(), error::Error> {
+        let transactions = [
+            Transaction {
+                date: NaiveDate::from_str("2021-07-12").unwrap(),
+                amount: dec!(87.32),
+                memo: "salary".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                date: NaiveDate::from_str("2021-07-13").unwrap(),
+                amount: dec!(-12.13),
+                memo: "groceries".to_string(),
+                ..Default::default()
+            },
+        ];
+        let tx = pool.begin().await?;
+        Model::commit_transactions(&transactions, SqliteStore::from_sqlite_transaction(tx)).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let stored = sqlite_store.get_transactions().await?;
+        let first = Model::get_changes(0, 100, &mut sqlite_store).await?;
+        drop(sqlite_store);
+        let salary = stored.iter().find(|x| x.data.memo == "salary").unwrap().id;
+
+        let tx = pool.begin().await?;
+        Model::delete_transaction(salary, SqliteStore::from_sqlite_transaction(tx)).await?;
+
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let feed = Model::get_changes(0, 100, &mut sqlite_store).await?;
+        assert!(!feed.has_more);
+        assert_eq!(first.changes, feed.changes[..first.changes.len()]);
+        assert!(feed.changes.windows(2).all(|x| x[0].cursor < x[1].cursor));
+        assert!(feed
+            .changes
+            .iter()
+            .all(|x| x.entity == "transaction" || x.entity == "report"));
+        assert_eq!(
+            vec![
+                ("transaction", AuditAction::Create),
+                ("transaction", AuditAction::Create),
+                ("report", AuditAction::Create),
+            ],
+            first
+                .changes
+                .iter()
+                .map(|x| (x.entity.as_str(), x.action))
+                .collect::<Vec<_>>()
+        );
+        let deleted = feed
+            .changes
+            .iter()
+            .find(|x| x.action == AuditAction::Delete)
+            .unwrap();
+        assert_eq!(
+            ("transaction", salary.to_string(), None),
+            (
+                deleted.entity.as_str(),
+                deleted.entity_id.clone(),
+                deleted.data.clone()
+            )
+        );
+
+        let mut paged = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let page = Model::get_changes(cursor, 2, &mut sqlite_store).await?;
+            assert!(page.changes.len() <= 2);
+            paged.extend(page.changes);
+            cursor = page.cursor;
+            if !page.has_more {
+                break;
+            }
+        }
+        assert_eq!(feed.changes, paged);
+        assert_eq!(feed.cursor, cursor);
+        assert_eq!(
+            feed,
+            Model::get_changes(0, u64::MAX, &mut sqlite_store).await?
+        );
+        let first = Model::get_changes(0, 0, &mut sqlite_store).await?;
+        assert_eq!(feed.changes[..1], first.changes);
+        assert_eq!(
+            (feed.changes[0].cursor, true),
+            (first.cursor, first.has_more)
+        );
+
+        let empty = Model::get_changes(cursor, 2, &mut sqlite_store).await?;
+        assert_eq!(
+            (Vec::new(), cursor, false),
+            (empty.changes, empty.cursor, empty.has_more)
+        );
 
         Ok(())
     }
@@ -1,128 +1,3424 @@
-use sea_query::{Iden, Query, SqliteQueryBuilder};
-use sea_query_binder::SqlxBinder;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use rust_decimal::Decimal;
+use sea_query::{
+    Cond, Expr, Iden, OnConflict, Order, Query, SelectStatement, SimpleExpr, SqliteQueryBuilder,
+};
+use sea_query_binder::{SqlxBinder, SqlxValues};
+use serde::Serialize;
+use serde_json::json;
 use sqlx::Sqlite;
 use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entity::{
+        self, Account, Attachment, AuditAction, AuditContext, AuditEntry, AuditFilter, Batch, Bill,
+        Budget, Customer, Event, EventRecord, ExchangeRate, FeedChange, Invoice, InvoiceStatus,
+        JournalEntry, LineOfInvoice, PayeeAlias, Payment, PaymentOfBill, PaymentOfInvoice,
+        PeriodClosing, PostingOfEntry, Reconciliation, ReconciliationStatus, Schedule, Split,
+        SplitOfTransaction, Transaction, TransactionVersion, Trip, Vendor, WithId,
+    },
+    error::Error,
+};
+
+#[derive(Iden)]
+enum Report {
+    Table,
+    Id,
+    GrossRevenue,
+    Expenses,
+}
+
+#[derive(Iden)]
+enum Transactions {
+    Table,
+    Id,
+    Date,
+    Amount,
+    Memo,
+    AccountId,
+    Kind,
+    TransferId,
+    ReportId,
+    Category,
+    Currency,
+    Cleared,
+    ReconciliationId,
+    ScheduleId,
+    TaxCode,
+    ValidFrom,
+    ValidTo,
+}
+
+impl Transactions {
+    /// Leaves out deleted transactions.
+    fn is_live() -> SimpleExpr {
+        Expr::col(Transactions::ValidTo).is_null()
+    }
+
+    /// The ids of the transactions dated on or before `date`.
+    fn ids_through(date: NaiveDate) -> SelectStatement {
+        Query::select()
+            .column(Transactions::Id)
+            .from(Transactions::Table)
+            .and_where(Expr::col(Transactions::Date).lte(date.to_string()))
+            .take()
+    }
+
+    fn columns() -> [Transactions; 13] {
+        [
+            Transactions::Id,
+            Transactions::Date,
+            Transactions::Amount,
+            Transactions::Memo,
+            Transactions::AccountId,
+            Transactions::Kind,
+            Transactions::TransferId,
+            Transactions::Category,
+            Transactions::Currency,
+            Transactions::Cleared,
+            Transactions::ReconciliationId,
+            Transactions::ScheduleId,
+            Transactions::TaxCode,
+        ]
+    }
+
+    fn values(id: &Uuid, data: &Transaction) -> [SimpleExpr; 13] {
+        [
+            id.to_string().into(),
+            data.date.to_string().into(),
+            data.amount.into(),
+            data.memo.clone().into(),
+            data.account_id.map(|x| x.to_string()).into(),
+            data.kind.as_str().into(),
+            data.transfer_id.map(|x| x.to_string()).into(),
+            data.category.clone().into(),
+            data.currency.clone().into(),
+            data.cleared.into(),
+            data.reconciliation_id.map(|x| x.to_string()).into(),
+            data.schedule_id.map(|x| x.to_string()).into(),
+            data.tax_code.clone().into(),
+        ]
+    }
+}
+
+#[derive(Iden)]
+enum TransactionVersions {
+    Table,
+    Id,
+    TransactionId,
+    ValidFrom,
+    ValidTo,
+    Data,
+}
+
+#[derive(Iden)]
+enum ExchangeRates {
+    Table,
+    Date,
+    Base,
+    Quote,
+    Rate,
+}
+
+#[derive(Iden)]
+enum TransactionSplits {
+    Table,
+    Id,
+    TransactionId,
+    Position,
+    Amount,
+    Category,
+    Memo,
+}
+
+#[derive(Iden)]
+enum Accounts {
+    Table,
+    Id,
+    Name,
+    Kind,
+    OpeningBalance,
+    OpeningDate,
+    Currency,
+}
+
+#[derive(Iden)]
+enum Reconciliations {
+    Table,
+    Id,
+    AccountId,
+    StatementDate,
+    ClosingBalance,
+    Status,
+}
+
+#[derive(Iden)]
+enum Schedules {
+    Table,
+    Id,
+    AccountId,
+    Amount,
+    Memo,
+    Category,
+    Frequency,
+    Interval,
+    DayOfMonth,
+    StartDate,
+    EndDate,
+    Count,
+}
+
+impl Schedules {
+    fn columns() -> [Schedules; 11] {
+        [
+            Schedules::Id,
+            Schedules::AccountId,
+            Schedules::Amount,
+            Schedules::Memo,
+            Schedules::Category,
+            Schedules::Frequency,
+            Schedules::Interval,
+            Schedules::DayOfMonth,
+            Schedules::StartDate,
+            Schedules::EndDate,
+            Schedules::Count,
+        ]
+    }
+}
+
+#[derive(Iden)]
+enum Trips {
+    Table,
+    Id,
+    Date,
+    StartOdometer,
+    EndOdometer,
+    Distance,
+    Purpose,
+    TransactionId,
+}
+
+impl Trips {
+    fn columns() -> [Trips; 7] {
+        [
+            Trips::Id,
+            Trips::Date,
+            Trips::StartOdometer,
+            Trips::EndOdometer,
+            Trips::Distance,
+            Trips::Purpose,
+            Trips::TransactionId,
+        ]
+    }
+
+    fn values(id: &Uuid, data: &Trip) -> [SimpleExpr; 7] {
+        [
+            id.to_string().into(),
+            data.date.to_string().into(),
+            data.start_odometer.into(),
+            data.end_odometer.into(),
+            data.distance.into(),
+            data.purpose.clone().into(),
+            data.transaction_id.map(|x| x.to_string()).into(),
+        ]
+    }
+}
+
+#[derive(Iden)]
+enum AttachmentContents {
+    Table,
+    Hash,
+    Content,
+}
+
+#[derive(Iden)]
+enum Attachments {
+    Table,
+    Id,
+    TransactionId,
+    FileName,
+    MimeType,
+    Size,
+    Hash,
+    UploadedAt,
+}
+
+impl Attachments {
+    fn columns() -> [Attachments; 7] {
+        [
+            Attachments::Id,
+            Attachments::TransactionId,
+            Attachments::FileName,
+            Attachments::MimeType,
+            Attachments::Size,
+            Attachments::Hash,
+            Attachments::UploadedAt,
+        ]
+    }
+}
+
+#[derive(Iden)]
+enum Customers {
+    Table,
+    Id,
+    Name,
+    Email,
+}
+
+#[derive(Iden)]
+enum Invoices {
+    Table,
+    Id,
+    CustomerId,
+    Number,
+    IssueDate,
+    DueDate,
+    Status,
+}
+
+impl Invoices {
+    fn columns() -> [Invoices; 6] {
+        [
+            Invoices::Id,
+            Invoices::CustomerId,
+            Invoices::Number,
+            Invoices::IssueDate,
+            Invoices::DueDate,
+            Invoices::Status,
+        ]
+    }
+}
+
+#[derive(Iden)]
+enum InvoiceLines {
+    Table,
+    Id,
+    InvoiceId,
+    Position,
+    Description,
+    Quantity,
+    UnitPrice,
+}
+
+#[derive(Iden)]
+enum InvoicePayments {
+    Table,
+    Id,
+    InvoiceId,
+    TransactionId,
+    Amount,
+}
+
+#[derive(Iden)]
+enum Vendors {
+    Table,
+    Id,
+    Name,
+    Email,
+}
+
+#[derive(Iden)]
+enum Bills {
+    Table,
+    Id,
+    VendorId,
+    Number,
+    IssueDate,
+    DueDate,
+    Amount,
+    Memo,
+}
+
+impl Bills {
+    fn columns() -> [Bills; 7] {
+        [
+            Bills::Id,
+            Bills::VendorId,
+            Bills::Number,
+            Bills::IssueDate,
+            Bills::DueDate,
+            Bills::Amount,
+            Bills::Memo,
+        ]
+    }
+}
+
+#[derive(Iden)]
+enum BillPayments {
+    Table,
+    Id,
+    BillId,
+    TransactionId,
+    Amount,
+}
+
+#[derive(Iden)]
+enum Budgets {
+    Table,
+    Id,
+    Category,
+    StartDate,
+    EndDate,
+    Amount,
+    Rollover,
+}
+
+#[derive(Iden)]
+enum PayeeAliases {
+    Table,
+    Memo,
+    Payee,
+}
+
+#[derive(Iden)]
+enum PeriodClosings {
+    Table,
+    Id,
+    ClosedThrough,
+    Reason,
+    RecordedAt,
+}
+
+#[derive(Iden)]
+enum JournalEntries {
+    Table,
+    Id,
+    Date,
+    Memo,
+    CreatedAt,
+    Source,
+}
+
+#[derive(Iden)]
+enum Postings {
+    Table,
+    Id,
+    JournalEntryId,
+    AccountId,
+    Amount,
+}
+
+#[derive(Iden)]
+enum Batches {
+    Table,
+    Sequence,
+    ReportId,
+    PreviousHash,
+    Hash,
+    RecordedAt,
+}
+
+impl Batches {
+    fn columns() -> [Batches; 5] {
+        [
+            Batches::Sequence,
+            Batches::ReportId,
+            Batches::PreviousHash,
+            Batches::Hash,
+            Batches::RecordedAt,
+        ]
+    }
+}
+
+#[derive(Iden)]
+enum Events {
+    Table,
+    Sequence,
+    RecordedAt,
+    Kind,
+    TransactionId,
+    ReportId,
+    Data,
+}
+
+#[derive(Iden)]
+enum AccountCheckpoints {
+    Table,
+    AccountId,
+    OpeningDate,
+    Date,
+    Total,
+}
+
+#[derive(Iden)]
+enum ProjectionCursors {
+    Table,
+    Name,
+    Sequence,
+}
+
+#[derive(Iden)]
+enum AuditLog {
+    Table,
+    Sequence,
+    RecordedAt,
+    Actor,
+    RequestId,
+    Entity,
+    EntityId,
+    Action,
+    Before,
+    After,
+}
+
+impl AuditLog {
+    fn columns() -> [AuditLog; 9] {
+        [
+            AuditLog::Sequence,
+            AuditLog::RecordedAt,
+            AuditLog::Actor,
+            AuditLog::RequestId,
+            AuditLog::Entity,
+            AuditLog::EntityId,
+            AuditLog::Action,
+            AuditLog::Before,
+            AuditLog::After,
+        ]
+    }
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Decodes an amount stored as text in the column.
+fn decode_amount(column: &impl Iden, amount: &str) -> Result<Decimal, Error> {
+    amount.parse().map_err(|x| {
+        Error::QueryError(sqlx::Error::ColumnDecode {
+            index: column.to_string(),
+            source: Box::new(x),
+        })
+    })
+}
+
+/// A change of an entity, to be recorded in the audit log.
+struct Change {
+    entity: &'static str,
+    entity_id: String,
+    action: AuditAction,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+}
+
+impl Change {
+    fn new(
+        entity: &'static str,
+        entity_id: &impl ToString,
+        action: AuditAction,
+        before: Option<&impl Serialize>,
+        after: Option<&impl Serialize>,
+    ) -> Self {
+        Self {
+            entity,
+            entity_id: entity_id.to_string(),
+            action,
+            before: before.and_then(|x| serde_json::to_value(x).ok()),
+            after: after.and_then(|x| serde_json::to_value(x).ok()),
+        }
+    }
+
+    fn created(entity: &'static str, entity_id: &impl ToString, after: &impl Serialize) -> Self {
+        Self::new(
+            entity,
+            entity_id,
+            AuditAction::Create,
+            None::<&()>,
+            Some(after),
+        )
+    }
+
+    fn updated(
+        entity: &'static str,
+        entity_id: &impl ToString,
+        before: &impl Serialize,
+        after: &impl Serialize,
+    ) -> Self {
+        Self::new(
+            entity,
+            entity_id,
+            AuditAction::Update,
+            Some(before),
+            Some(after),
+        )
+    }
+
+    fn deleted(entity: &'static str, entity_id: &impl ToString, before: &impl Serialize) -> Self {
+        Self::new(
+            entity,
+            entity_id,
+            AuditAction::Delete,
+            Some(before),
+            None::<&()>,
+        )
+    }
+}
+
+/// What a journal entry was posted for, when it was not recorded by hand. A
+/// posted entry shares the id of its transaction or account.
+#[derive(Debug, Clone, Copy)]
+enum EntrySource {
+    Transaction,
+    OpeningBalance,
+}
+
+impl EntrySource {
+    fn as_str(self) -> &'static str {
+        match self {
+            EntrySource::Transaction => "transaction",
+            EntrySource::OpeningBalance => "opening_balance",
+        }
+    }
+}
+
+/// Every mutation of the store is recorded in the audit log within the same
+/// transaction, on behalf of its audit context.
+#[derive(Debug)]
+pub struct SqliteStore<'a> {
+    transaction: sqlx::Transaction<'a, Sqlite>,
+    context: AuditContext,
+}
+
+impl<'a> SqliteStore<'a> {
+    #[must_use]
+    pub fn from_sqlite_transaction(transaction: sqlx::Transaction<'a, Sqlite>) -> SqliteStore<'a> {
+        SqliteStore {
+            transaction,
+            context: AuditContext::default(),
+        }
+    }
+
+    /// Records the changes made through the store on behalf of the context
+    /// instead of the system.
+    #[must_use]
+    pub fn with_audit_context(self, context: AuditContext) -> SqliteStore<'a> {
+        SqliteStore { context, ..self }
+    }
+
+    async fn audit(&mut self, changes: impl IntoIterator<Item = Change>) -> Result<(), Error> {
+        const CHUNK_SIZE: usize = 500;
+
+        let recorded_at = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+        let changes: Vec<_> = changes.into_iter().collect();
+        for chunk in changes.chunks(CHUNK_SIZE) {
+            let mut query_builder = Query::insert();
+            query_builder
+                .into_table(AuditLog::Table)
+                .columns(AuditLog::columns().into_iter().skip(1));
+            for change in chunk {
+                query_builder.values([
+                    recorded_at.clone().into(),
+                    self.context.actor.clone().into(),
+                    self.context.request_id.clone().into(),
+                    change.entity.into(),
+                    change.entity_id.clone().into(),
+                    change.action.as_str().into(),
+                    change.before.as_ref().map(ToString::to_string).into(),
+                    change.after.as_ref().map(ToString::to_string).into(),
+                ])?;
+            }
+            let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+            sqlx::query_with(&query, values)
+                .execute(&mut *self.transaction)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Appends the events of transactions, along with the uploads of the
+    /// transactions, to the event store.
+    async fn record_events(
+        &mut self,
+        events: impl IntoIterator<Item = Event>,
+    ) -> Result<(), Error> {
+        const CHUNK_SIZE: usize = 500;
+
+        let events: Vec<_> = events.into_iter().collect();
+        for chunk in events.chunks(CHUNK_SIZE) {
+            let ids: Vec<_> = chunk
+                .iter()
+                .map(|x| x.transaction_id().to_string())
+                .collect();
+            let (query, values) = Query::select()
+                .columns([Transactions::Id, Transactions::ReportId])
+                .from(Transactions::Table)
+                .and_where(Expr::col(Transactions::Id).is_in(ids.clone()))
+                .build_sqlx(SqliteQueryBuilder);
+            let report_ids: HashMap<String, Option<String>> = sqlx::query_as_with(&query, values)
+                .fetch_all(&mut *self.transaction)
+                .await?
+                .into_iter()
+                .collect();
+
+            self.insert_events(
+                chunk
+                    .iter()
+                    .zip(ids)
+                    .map(|(event, id)| (event, report_ids.get(&id).cloned().flatten())),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Appends events of transactions of the upload to the event store. Unlike
+    /// the events recorded along with a change, the upload is given rather
+    /// than read from the transactions, which may have been changed, or
+    /// removed, outside of the store.
+    #[instrument(skip(self, events))]
+    pub async fn record_report_events(
+        &mut self,
+        report_id: Uuid,
+        events: &[Event],
+    ) -> Result<(), Error> {
+        const CHUNK_SIZE: usize = 500;
+
+        for chunk in events.chunks(CHUNK_SIZE) {
+            self.insert_events(chunk.iter().map(|x| (x, Some(report_id.to_string()))))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn insert_events<'e>(
+        &mut self,
+        events: impl IntoIterator<Item = (&'e Event, Option<String>)>,
+    ) -> Result<(), Error> {
+        let recorded_at = timestamp(Utc::now());
+        let mut query_builder = Query::insert();
+        query_builder.into_table(Events::Table).columns([
+            Events::RecordedAt,
+            Events::Kind,
+            Events::TransactionId,
+            Events::ReportId,
+            Events::Data,
+        ]);
+        for (event, report_id) in events {
+            query_builder.values([
+                recorded_at.clone().into(),
+                event.kind().into(),
+                event.transaction_id().to_string().into(),
+                report_id.into(),
+                json!(event).to_string().into(),
+            ])?;
+        }
+        let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+        Ok(())
+    }
+
+    /// Events recorded after the given sequence number, in order.
+    #[instrument(skip(self))]
+    pub async fn get_events(&mut self, after: i64) -> Result<Vec<EventRecord>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                Events::Sequence,
+                Events::RecordedAt,
+                Events::ReportId,
+                Events::Data,
+            ])
+            .from(Events::Table)
+            .and_where(Expr::col(Events::Sequence).gt(after))
+            .order_by(Events::Sequence, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, EventRecord, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// Events of the transactions of the uploads, up to the given sequence
+    /// number, in order.
+    #[instrument(skip(self, report_ids))]
+    pub async fn get_report_events(
+        &mut self,
+        report_ids: &[Uuid],
+        until: i64,
+    ) -> Result<Vec<EventRecord>, Error> {
+        const CHUNK_SIZE: usize = 500;
+
+        let mut events = vec![];
+        for chunk in report_ids.chunks(CHUNK_SIZE) {
+            let (query, values) = Query::select()
+                .columns([
+                    Events::Sequence,
+                    Events::RecordedAt,
+                    Events::ReportId,
+                    Events::Data,
+                ])
+                .from(Events::Table)
+                .and_where(Expr::col(Events::ReportId).is_in(chunk.iter().map(ToString::to_string)))
+                .and_where(Expr::col(Events::Sequence).lte(until))
+                .build_sqlx(SqliteQueryBuilder);
+
+            events.extend(
+                sqlx::query_as_with::<_, EventRecord, _>(&query, values)
+                    .fetch_all(&mut *self.transaction)
+                    .await?,
+            );
+        }
+        events.sort_by_key(|x| x.sequence);
+        Ok(events)
+    }
+
+    /// The last event applied by the projection, 0 if it never ran.
+    #[instrument(skip(self))]
+    pub async fn get_projection_cursor(&mut self, name: &str) -> Result<i64, Error> {
+        let (query, values) = Query::select()
+            .column(ProjectionCursors::Sequence)
+            .from(ProjectionCursors::Table)
+            .and_where(Expr::col(ProjectionCursors::Name).eq(name))
+            .build_sqlx(SqliteQueryBuilder);
+
+        let sequence: Option<i64> = sqlx::query_scalar_with(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?;
+        Ok(sequence.unwrap_or_default())
+    }
+
+    /// Moves the cursor of the projection. The cursor is bookkeeping of the
+    /// projection rather than a change of the books, so it is not audited.
+    #[instrument(skip(self))]
+    pub async fn set_projection_cursor(&mut self, name: &str, sequence: i64) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(ProjectionCursors::Table)
+            .columns([ProjectionCursors::Name, ProjectionCursors::Sequence])
+            .values([name.into(), sequence.into()])?
+            .on_conflict(
+                OnConflict::column(ProjectionCursors::Name)
+                    .update_column(ProjectionCursors::Sequence)
+                    .to_owned(),
+            )
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+        Ok(())
+    }
+
+    /// Entries of the audit log matching the filter, in the order they were
+    /// recorded.
+    #[instrument(skip(self))]
+    pub async fn get_audit_entries(
+        &mut self,
+        filter: &AuditFilter,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<AuditEntry>, Error> {
+        let timestamp = |x: DateTime<Utc>| x.to_rfc3339_opts(SecondsFormat::Micros, true);
+        let (query, values) = Query::select()
+            .columns(AuditLog::columns())
+            .from(AuditLog::Table)
+            .and_where_option(
+                filter
+                    .entity
+                    .clone()
+                    .map(|x| Expr::col(AuditLog::Entity).eq(x)),
+            )
+            .and_where_option(
+                filter
+                    .entity_id
+                    .clone()
+                    .map(|x| Expr::col(AuditLog::EntityId).eq(x)),
+            )
+            .and_where_option(
+                filter
+                    .actor
+                    .clone()
+                    .map(|x| Expr::col(AuditLog::Actor).eq(x)),
+            )
+            .and_where_option(
+                filter
+                    .request_id
+                    .clone()
+                    .map(|x| Expr::col(AuditLog::RequestId).eq(x)),
+            )
+            .and_where_option(
+                filter
+                    .from
+                    .map(|x| Expr::col(AuditLog::RecordedAt).gte(timestamp(x))),
+            )
+            .and_where_option(
+                filter
+                    .to
+                    .map(|x| Expr::col(AuditLog::RecordedAt).lte(timestamp(x))),
+            )
+            .order_by(AuditLog::Sequence, Order::Asc)
+            .limit(limit)
+            .offset(offset)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, AuditEntry, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// Lists the changes of transactions and reports recorded after the
+    /// `since` entry of the audit log, in the order they were committed. The
+    /// log is only written by one transaction at a time, so an entry is never
+    /// numbered before one committed earlier.
+    #[instrument(skip(self))]
+    pub async fn get_feed_changes(
+        &mut self,
+        since: i64,
+        limit: u64,
+    ) -> Result<Vec<FeedChange>, Error> {
+        let (query, values) = Query::select()
+            .columns(AuditLog::columns())
+            .from(AuditLog::Table)
+            .and_where(Expr::col(AuditLog::Sequence).gt(since))
+            .and_where(Expr::col(AuditLog::Entity).is_in(["transaction", "report"]))
+            .order_by(AuditLog::Sequence, Order::Asc)
+            .limit(limit)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, AuditEntry, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?
+            .into_iter()
+            .map(FeedChange::from)
+            .collect())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_reports(&mut self) -> Result<Vec<entity::Report>, Error> {
+        let (query, values) = Query::select()
+            .columns([Report::GrossRevenue, Report::Expenses])
+            .from(Report::Table)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, entity::Report, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// The stored report of every upload.
+    #[instrument(skip(self))]
+    pub async fn get_reports_with_ids(&mut self) -> Result<Vec<WithId<entity::Report>>, Error> {
+        let (query, values) = Query::select()
+            .columns([Report::Id, Report::GrossRevenue, Report::Expenses])
+            .from(Report::Table)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(
+            sqlx::query_as_with::<_, WithId<entity::Report>, _>(&query, values)
+                .fetch_all(&mut *self.transaction)
+                .await?,
+        )
+    }
+
+    /// The uploads that transactions were aggregated into.
+    #[instrument(skip(self))]
+    pub async fn get_transaction_report_ids(&mut self) -> Result<HashSet<Uuid>, Error> {
+        let (query, values) = Query::select()
+            .distinct()
+            .column(Transactions::ReportId)
+            .from(Transactions::Table)
+            .and_where(Expr::col(Transactions::ReportId).is_not_null())
+            .build_sqlx(SqliteQueryBuilder);
+
+        let ids: Vec<String> = sqlx::query_scalar_with(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+
+        Ok(ids.iter().filter_map(|x| Uuid::parse_str(x).ok()).collect())
+    }
+
+    /// The currencies of the live transactions of every upload.
+    #[instrument(skip(self))]
+    pub async fn get_report_currencies(&mut self) -> Result<HashMap<Uuid, Vec<String>>, Error> {
+        let (query, values) = Query::select()
+            .distinct()
+            .columns([Transactions::ReportId, Transactions::Currency])
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .and_where(Expr::col(Transactions::ReportId).is_not_null())
+            .build_sqlx(SqliteQueryBuilder);
+
+        let rows: Vec<(String, String)> = sqlx::query_as_with(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+
+        let mut currencies: HashMap<_, Vec<_>> = HashMap::new();
+        for (report_id, currency) in rows {
+            if let Ok(report_id) = Uuid::parse_str(&report_id) {
+                currencies.entry(report_id).or_default().push(currency);
+            }
+        }
+        Ok(currencies)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_report(
+        &mut self,
+        WithId { id, data }: &WithId<entity::Report>,
+    ) -> Result<(), Error> {
+        let report = &data;
+        let (query, values) = Query::insert()
+            .into_table(Report::Table)
+            .columns([Report::Id, Report::GrossRevenue, Report::Expenses])
+            .values([
+                id.to_string().into(),
+                report.gross_revenue.into(),
+                report.expenses.into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("report", id, data)]).await
+    }
+
+    async fn get_report(&mut self, id: Uuid) -> Result<Option<entity::Report>, Error> {
+        let (query, values) = Query::select()
+            .columns([Report::GrossRevenue, Report::Expenses])
+            .from(Report::Table)
+            .and_where(Expr::col(Report::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, entity::Report, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_no_transactions(&mut self) -> Result<usize, Error> {
+        let mut query_builder = Query::select();
+        query_builder
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .columns([
+                Transactions::Id,
+                Transactions::Date,
+                Transactions::Amount,
+                Transactions::Memo,
+            ]);
+
+        let (transactions_query, transactions_values) =
+            query_builder.build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&transactions_query, transactions_values)
+            .fetch_all(&mut *self.transaction)
+            .await
+            .map_err(Error::QueryError)
+            .map(|x| x.len())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn update_report(
+        &mut self,
+        WithId { id, data }: &WithId<entity::Report>,
+    ) -> Result<(), Error> {
+        let before = self.get_report(*id).await?;
+
+        let (query, values) = Query::update()
+            .table(Report::Table)
+            .values([
+                (Report::GrossRevenue, data.gross_revenue.into()),
+                (Report::Expenses, data.expenses.into()),
+            ])
+            .and_where(Expr::col(Report::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([match before {
+            Some(before) => Change::updated("report", id, &before, data),
+            None => Change::created("report", id, data),
+        }])
+        .await
+    }
+
+    /// Inserts the transactions of a single upload, whose aggregate is kept in
+    /// the report with the given id.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PeriodClosed`] if any transaction is dated in a closed
+    /// period.
+    #[instrument(skip(self, transactions))]
+    pub async fn create_transactions(
+        &mut self,
+        report_id: Uuid,
+        transactions: impl IntoIterator<Item = WithId<&Transaction>>,
+    ) -> Result<(), Error> {
+        let transactions: Vec<_> = transactions.into_iter().collect();
+        self.ensure_period_open(transactions.iter().map(|x| x.data.date))
+            .await?;
+
+        let valid_from = timestamp(Utc::now());
+        let mut query_builder = Query::insert();
+        query_builder.into_table(Transactions::Table).columns(
+            Transactions::columns()
+                .into_iter()
+                .chain([Transactions::ReportId, Transactions::ValidFrom]),
+        );
+
+        let mut splits = vec![];
+        let mut changes = Vec::with_capacity(transactions.len());
+        let mut events = Vec::with_capacity(transactions.len());
+        let mut entries = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            changes.push(Change::created(
+                "transaction",
+                &transaction.id,
+                transaction.data,
+            ));
+            events.push(Event::TransactionImported {
+                transaction_id: transaction.id,
+                transaction: transaction.data.clone(),
+            });
+            let mut values = Transactions::values(&transaction.id, transaction.data).to_vec();
+            values.push(report_id.to_string().into());
+            values.push(valid_from.clone().into());
+            query_builder.values(values)?;
+            if !transaction.data.splits.is_empty() {
+                splits.push((transaction.id, &transaction.data.splits));
+            }
+            entries.push(WithId {
+                id: transaction.id,
+                data: JournalEntry::from_transaction(transaction.data),
+            });
+        }
+
+        let (transactions_query, transactions_values) =
+            query_builder.build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&transactions_query, transactions_values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        for (id, splits) in splits {
+            self.replace_transaction_splits(id, splits).await?;
+        }
+        self.post_journal_entries(EntrySource::Transaction, &entries)
+            .await?;
+        self.record_events(events).await?;
+        self.audit(changes).await
+    }
+
+    /// Replaces the splits of a transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PeriodClosed`] if the transaction is dated in a closed
+    /// period.
+    #[instrument(skip(self, splits))]
+    pub async fn set_transaction_splits(
+        &mut self,
+        id: Uuid,
+        splits: &[Split],
+    ) -> Result<(), Error> {
+        let before = self.get_transaction(id).await?;
+        self.ensure_period_open([before.data.date]).await?;
+        self.supersede_transactions(std::slice::from_ref(&before))
+            .await?;
+        self.replace_transaction_splits(id, splits).await?;
+
+        let after = Transaction {
+            splits: splits.to_vec(),
+            ..before.data.clone()
+        };
+        self.record_events([Event::edited(id, &before.data, &after)])
+            .await?;
+        self.audit([Change::updated("transaction", &id, &before.data, &after)])
+            .await
+    }
+
+    async fn replace_transaction_splits(
+        &mut self,
+        id: Uuid,
+        splits: &[Split],
+    ) -> Result<(), Error> {
+        let (query, values) = Query::delete()
+            .from_table(TransactionSplits::Table)
+            .and_where(Expr::col(TransactionSplits::TransactionId).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        if splits.is_empty() {
+            return Ok(());
+        }
+
+        let mut query_builder = Query::insert();
+        query_builder.into_table(TransactionSplits::Table).columns([
+            TransactionSplits::Id,
+            TransactionSplits::TransactionId,
+            TransactionSplits::Position,
+            TransactionSplits::Amount,
+            TransactionSplits::Category,
+            TransactionSplits::Memo,
+        ]);
+        for (position, split) in (0u32..).zip(splits) {
+            query_builder.values([
+                Uuid::new_v4().to_string().into(),
+                id.to_string().into(),
+                position.into(),
+                split.amount.into(),
+                split.category.clone().into(),
+                split.memo.clone().into(),
+            ])?;
+        }
+        let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await
+            .map_err(Error::QueryError)
+            .map(|_| ())
+    }
+
+    async fn fetch_transactions(
+        &mut self,
+        query: &str,
+        values: SqlxValues,
+    ) -> Result<Vec<WithId<Transaction>>, Error> {
+        let mut transactions = sqlx::query_as_with::<_, WithId<Transaction>, _>(query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+        self.attach_splits(&mut transactions).await?;
+
+        Ok(transactions)
+    }
+
+    /// Loads the splits of the transactions, which are stored in their own
+    /// table.
+    async fn attach_splits(
+        &mut self,
+        transactions: &mut [WithId<Transaction>],
+    ) -> Result<(), Error> {
+        const CHUNK_SIZE: usize = 500;
+
+        let positions: HashMap<_, _> = transactions
+            .iter()
+            .enumerate()
+            .map(|(position, transaction)| (transaction.id, position))
+            .collect();
+
+        let chunks: Vec<Vec<_>> = transactions
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| chunk.iter().map(|x| x.id.to_string()).collect())
+            .collect();
+
+        for ids in chunks {
+            let (query, values) = Query::select()
+                .columns([
+                    TransactionSplits::TransactionId,
+                    TransactionSplits::Amount,
+                    TransactionSplits::Category,
+                    TransactionSplits::Memo,
+                ])
+                .from(TransactionSplits::Table)
+                .and_where(Expr::col(TransactionSplits::TransactionId).is_in(ids))
+                .order_by(TransactionSplits::Position, Order::Asc)
+                .build_sqlx(SqliteQueryBuilder);
+
+            let splits = sqlx::query_as_with::<_, SplitOfTransaction, _>(&query, values)
+                .fetch_all(&mut *self.transaction)
+                .await?;
+
+            for SplitOfTransaction {
+                transaction_id,
+                split,
+            } in splits
+            {
+                if let Some(&position) = positions.get(&transaction_id) {
+                    transactions[position].data.splits.push(split);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_transactions(&mut self) -> Result<Vec<WithId<Transaction>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_transactions(&query, values).await
+    }
+
+    /// Transactions dated within the inclusive bounds.
+    #[instrument(skip(self))]
+    pub async fn get_transactions_in_period(
+        &mut self,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<Vec<WithId<Transaction>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .and_where_option(from.map(|x| Expr::col(Transactions::Date).gte(x.to_string())))
+            .and_where_option(to.map(|x| Expr::col(Transactions::Date).lte(x.to_string())))
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_transactions(&query, values).await
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::TransactionNotFound`] if no transaction has the given
+    /// id.
+    #[instrument(skip(self))]
+    pub async fn get_transaction(&mut self, id: Uuid) -> Result<WithId<Transaction>, Error> {
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .and_where(Expr::col(Transactions::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_transactions(&query, values)
+            .await?
+            .pop()
+            .ok_or(Error::TransactionNotFound(id))
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::PeriodClosed`] if the transaction is dated, or would be
+    /// dated, in a closed period.
+    #[instrument(skip(self))]
+    pub async fn update_transaction(
+        &mut self,
+        WithId { id, data }: &WithId<Transaction>,
+    ) -> Result<(), Error> {
+        let before = self.get_transaction(*id).await?;
+        self.ensure_period_open([before.data.date, data.date])
+            .await?;
+
+        self.supersede_transactions(std::slice::from_ref(&before))
+            .await?;
+
+        let (query, values) = Query::update()
+            .table(Transactions::Table)
+            .values(
+                Transactions::columns()
+                    .into_iter()
+                    .zip(Transactions::values(id, data))
+                    .skip(1),
+            )
+            .and_where(Expr::col(Transactions::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.unpost_journal_entry(*id).await?;
+        self.post_journal_entries(
+            EntrySource::Transaction,
+            &[WithId {
+                id: *id,
+                data: JournalEntry::from_transaction(data),
+            }],
+        )
+        .await?;
+        self.record_events([Event::edited(*id, &before.data, data)])
+            .await?;
+        self.audit([Change::updated("transaction", id, &before.data, data)])
+            .await
+    }
+
+    /// Keeps the current versions of the transactions, which are about to be
+    /// changed, and starts their next versions now.
+    async fn supersede_transactions(
+        &mut self,
+        transactions: &[WithId<Transaction>],
+    ) -> Result<(), Error> {
+        const CHUNK_SIZE: usize = 500;
+
+        let now = timestamp(Utc::now());
+        for chunk in transactions.chunks(CHUNK_SIZE) {
+            let ids: Vec<_> = chunk.iter().map(|x| x.id.to_string()).collect();
+            let (query, values) = Query::select()
+                .columns([Transactions::Id, Transactions::ValidFrom])
+                .from(Transactions::Table)
+                .and_where(Expr::col(Transactions::Id).is_in(ids.clone()))
+                .build_sqlx(SqliteQueryBuilder);
+            let valid_from: HashMap<String, Option<String>> = sqlx::query_as_with(&query, values)
+                .fetch_all(&mut *self.transaction)
+                .await?
+                .into_iter()
+                .collect();
+
+            let mut query_builder = Query::insert();
+            query_builder
+                .into_table(TransactionVersions::Table)
+                .columns([
+                    TransactionVersions::Id,
+                    TransactionVersions::TransactionId,
+                    TransactionVersions::ValidFrom,
+                    TransactionVersions::ValidTo,
+                    TransactionVersions::Data,
+                ]);
+            for WithId { id, data } in chunk {
+                query_builder.values([
+                    Uuid::new_v4().to_string().into(),
+                    id.to_string().into(),
+                    valid_from.get(&id.to_string()).cloned().flatten().into(),
+                    now.clone().into(),
+                    json!(data).to_string().into(),
+                ])?;
+            }
+            let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+            sqlx::query_with(&query, values)
+                .execute(&mut *self.transaction)
+                .await?;
+
+            let (query, values) = Query::update()
+                .table(Transactions::Table)
+                .value(Transactions::ValidFrom, now.clone())
+                .and_where(Expr::col(Transactions::Id).is_in(ids))
+                .build_sqlx(SqliteQueryBuilder);
+
+            sqlx::query_with(&query, values)
+                .execute(&mut *self.transaction)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the transaction, which keeps its last version until now and is
+    /// left out of every other query.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TransactionNotFound`] if no live transaction has the
+    /// given id, or [`Error::PeriodClosed`] if it is dated in a closed period.
+    #[instrument(skip(self))]
+    pub async fn delete_transaction(&mut self, id: Uuid) -> Result<(), Error> {
+        let before = self.get_transaction(id).await?;
+        self.ensure_period_open([before.data.date]).await?;
+
+        let (query, values) = Query::update()
+            .table(Transactions::Table)
+            .value(Transactions::ValidTo, timestamp(Utc::now()))
+            .and_where(Expr::col(Transactions::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.unpost_journal_entry(id).await?;
+        self.record_events([Event::TransactionDeleted { transaction_id: id }])
+            .await?;
+        self.audit([Change::deleted("transaction", &id, &before.data)])
+            .await
+    }
+
+    /// Every version of the transaction, deleted or not, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TransactionNotFound`] if no transaction has the given
+    /// id.
+    #[instrument(skip(self))]
+    pub async fn get_transaction_versions(
+        &mut self,
+        id: Uuid,
+    ) -> Result<Vec<TransactionVersion>, Error> {
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .and_where(Expr::col(Transactions::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+        let current = self
+            .fetch_transactions(&query, values)
+            .await?
+            .pop()
+            .ok_or(Error::TransactionNotFound(id))?;
+
+        let (query, values) = Query::select()
+            .columns([Transactions::ValidFrom, Transactions::ValidTo])
+            .from(Transactions::Table)
+            .and_where(Expr::col(Transactions::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+        let (valid_from, valid_to) = sqlx::query_as_with(&query, values)
+            .fetch_one(&mut *self.transaction)
+            .await?;
+
+        let (query, values) = Query::select()
+            .columns([
+                TransactionVersions::TransactionId,
+                TransactionVersions::ValidFrom,
+                TransactionVersions::ValidTo,
+                TransactionVersions::Data,
+            ])
+            .from(TransactionVersions::Table)
+            .and_where(Expr::col(TransactionVersions::TransactionId).eq(id.to_string()))
+            .order_by(TransactionVersions::ValidTo, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+        let mut versions = sqlx::query_as_with::<_, TransactionVersion, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+
+        versions.push(TransactionVersion {
+            transaction_id: id,
+            valid_from,
+            valid_to,
+            transaction: current.data,
+        });
+        Ok(versions)
+    }
+
+    /// The transactions as they were at the given time, dated within the
+    /// inclusive bounds: the versions valid then, of the transactions created
+    /// by then and not yet deleted.
+    #[instrument(skip(self))]
+    pub async fn get_transactions_as_of(
+        &mut self,
+        at: DateTime<Utc>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<Vec<WithId<Transaction>>, Error> {
+        let at = timestamp(at);
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .cond_where(
+                Cond::any()
+                    .add(Expr::col(Transactions::ValidFrom).is_null())
+                    .add(Expr::col(Transactions::ValidFrom).lte(at.clone())),
+            )
+            .cond_where(
+                Cond::any()
+                    .add(Expr::col(Transactions::ValidTo).is_null())
+                    .add(Expr::col(Transactions::ValidTo).gt(at.clone())),
+            )
+            .and_where_option(from.map(|x| Expr::col(Transactions::Date).gte(x.to_string())))
+            .and_where_option(to.map(|x| Expr::col(Transactions::Date).lte(x.to_string())))
+            .build_sqlx(SqliteQueryBuilder);
+        let mut transactions = self.fetch_transactions(&query, values).await?;
+
+        let (query, values) = Query::select()
+            .columns([
+                TransactionVersions::TransactionId,
+                TransactionVersions::ValidFrom,
+                TransactionVersions::ValidTo,
+                TransactionVersions::Data,
+            ])
+            .from(TransactionVersions::Table)
+            .cond_where(
+                Cond::any()
+                    .add(Expr::col(TransactionVersions::ValidFrom).is_null())
+                    .add(Expr::col(TransactionVersions::ValidFrom).lte(at.clone())),
+            )
+            .and_where(Expr::col(TransactionVersions::ValidTo).gt(at))
+            .build_sqlx(SqliteQueryBuilder);
+        let versions = sqlx::query_as_with::<_, TransactionVersion, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+
+        transactions.extend(
+            versions
+                .into_iter()
+                .filter(|x| {
+                    from.is_none_or(|from| x.transaction.date >= from)
+                        && to.is_none_or(|to| x.transaction.date <= to)
+                })
+                .map(|x| WithId {
+                    id: x.transaction_id,
+                    data: x.transaction,
+                }),
+        );
+        Ok(transactions)
+    }
+
+    /// Sets the cleared flag of the transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PeriodClosed`] if any transaction is dated in a closed
+    /// period.
+    #[instrument(skip(self, ids))]
+    pub async fn set_transactions_cleared(
+        &mut self,
+        ids: &[Uuid],
+        cleared: bool,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .and_where(Expr::col(Transactions::Id).is_in(ids.iter().map(ToString::to_string)))
+            .build_sqlx(SqliteQueryBuilder);
+        let before = self.fetch_transactions(&query, values).await?;
+        self.ensure_period_open(before.iter().map(|x| x.data.date))
+            .await?;
+
+        self.supersede_transactions(&before).await?;
+
+        let (query, values) = Query::update()
+            .table(Transactions::Table)
+            .value(Transactions::Cleared, cleared)
+            .and_where(Expr::col(Transactions::Id).is_in(ids.iter().map(ToString::to_string)))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        let after: Vec<_> = before
+            .iter()
+            .map(|x| Transaction {
+                cleared,
+                ..x.data.clone()
+            })
+            .collect();
+        self.record_events(
+            before
+                .iter()
+                .zip(&after)
+                .map(|(x, after)| Event::edited(x.id, &x.data, after)),
+        )
+        .await?;
+        self.audit(
+            before
+                .iter()
+                .zip(&after)
+                .map(|(x, after)| Change::updated("transaction", &x.id, &x.data, after)),
+        )
+        .await
+    }
+
+    /// Assigns the cleared transactions of the account dated up to the
+    /// statement date, and not yet reconciled, to the reconciliation.
+    #[instrument(skip(self))]
+    pub async fn reconcile_cleared_transactions(
+        &mut self,
+        WithId { id, data }: &WithId<Reconciliation>,
+    ) -> Result<(), Error> {
+        let condition = Cond::all()
+            .add(Transactions::is_live())
+            .add(Expr::col(Transactions::AccountId).eq(data.account_id.to_string()))
+            .add(Expr::col(Transactions::Date).lte(data.statement_date.to_string()))
+            .add(Expr::col(Transactions::Cleared).eq(true))
+            .add(Expr::col(Transactions::ReconciliationId).is_null());
+
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .cond_where(condition.clone())
+            .build_sqlx(SqliteQueryBuilder);
+        let before = self.fetch_transactions(&query, values).await?;
+
+        self.supersede_transactions(&before).await?;
+
+        let (query, values) = Query::update()
+            .table(Transactions::Table)
+            .value(Transactions::ReconciliationId, id.to_string())
+            .cond_where(condition)
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        let after: Vec<_> = before
+            .iter()
+            .map(|x| Transaction {
+                reconciliation_id: Some(*id),
+                ..x.data.clone()
+            })
+            .collect();
+        self.record_events(
+            before
+                .iter()
+                .zip(&after)
+                .map(|(x, after)| Event::edited(x.id, &x.data, after)),
+        )
+        .await?;
+        self.audit(
+            before
+                .iter()
+                .zip(&after)
+                .map(|(x, after)| Change::updated("transaction", &x.id, &x.data, after)),
+        )
+        .await
+    }
+
+    /// The report the transaction was aggregated into when it was uploaded.
+    #[instrument(skip(self))]
+    pub async fn get_transaction_report_id(&mut self, id: Uuid) -> Result<Option<Uuid>, Error> {
+        let (query, values) = Query::select()
+            .column(Transactions::ReportId)
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .and_where(Expr::col(Transactions::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        let report_id: Option<Option<String>> = sqlx::query_scalar_with(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?;
+
+        match report_id {
+            None => Err(Error::TransactionNotFound(id)),
+            Some(report_id) => Ok(report_id.and_then(|x| Uuid::parse_str(&x).ok())),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_report_transactions(
+        &mut self,
+        report_id: Uuid,
+    ) -> Result<Vec<WithId<Transaction>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .and_where(Expr::col(Transactions::ReportId).eq(report_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_transactions(&query, values).await
+    }
+
+    /// The transactions committed in the upload, deleted or not.
+    #[instrument(skip(self))]
+    pub async fn get_committed_report_transactions(
+        &mut self,
+        report_id: Uuid,
+    ) -> Result<Vec<WithId<Transaction>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .and_where(Expr::col(Transactions::ReportId).eq(report_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_transactions(&query, values).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_account_transactions(
+        &mut self,
+        account_id: Uuid,
+    ) -> Result<Vec<WithId<Transaction>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Transactions::columns())
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .and_where(Expr::col(Transactions::AccountId).eq(account_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_transactions(&query, values).await
+    }
+
+    /// The position of a transaction of the account, deleted or not, in its
+    /// statement, to resume the statement after: its date and its rowid.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TransactionNotFound`] if the account has no
+    /// transaction with the given id.
+    #[instrument(skip(self))]
+    pub async fn get_account_transaction_position(
+        &mut self,
+        account_id: Uuid,
+        id: Uuid,
+    ) -> Result<(NaiveDate, i64), Error> {
+        let (query, values) = Query::select()
+            .column(Transactions::Date)
+            .expr(Expr::cust("rowid"))
+            .from(Transactions::Table)
+            .and_where(Expr::col(Transactions::Id).eq(id.to_string()))
+            .and_where(Expr::col(Transactions::AccountId).eq(account_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::TransactionNotFound(id))
+    }
+
+    /// The transactions of the account dated from `from`.
+    fn account_statement(account_id: Uuid, from: NaiveDate) -> SelectStatement {
+        Query::select()
+            .from(Transactions::Table)
+            .and_where(Transactions::is_live())
+            .and_where(Expr::col(Transactions::AccountId).eq(account_id.to_string()))
+            .and_where(Expr::col(Transactions::Date).gte(from.to_string()))
+            .take()
+    }
+
+    /// The transactions of a statement after a position, or through it, a
+    /// statement being in order of date and then of insertion.
+    fn statement_position((date, rowid): (NaiveDate, i64), after: bool) -> Cond {
+        let date = date.to_string();
+        let (earlier_or_later, same_day) = if after {
+            (
+                Expr::col(Transactions::Date).gt(date.clone()),
+                Expr::cust_with_values("rowid > ?", [rowid]),
+            )
+        } else {
+            (
+                Expr::col(Transactions::Date).lt(date.clone()),
+                Expr::cust_with_values("rowid <= ?", [rowid]),
+            )
+        };
+        Cond::any().add(earlier_or_later).add(
+            Cond::all()
+                .add(Expr::col(Transactions::Date).eq(date))
+                .add(same_day),
+        )
+    }
+
+    /// A page of the statement of the account from `from`, after the given
+    /// position, if any.
+    #[instrument(skip(self))]
+    pub async fn get_account_transactions_page(
+        &mut self,
+        account_id: Uuid,
+        from: NaiveDate,
+        after: Option<(NaiveDate, i64)>,
+        limit: u64,
+    ) -> Result<Vec<WithId<Transaction>>, Error> {
+        let (query, values) = SqliteStore::account_statement(account_id, from)
+            .columns(Transactions::columns())
+            .cond_where(after.map_or_else(Cond::all, |x| SqliteStore::statement_position(x, true)))
+            .order_by(Transactions::Date, Order::Asc)
+            .order_by_expr(Expr::cust("rowid"), Order::Asc)
+            .limit(limit)
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_transactions(&query, values).await
+    }
+
+    /// The sum of the amounts of the statement of the account from `from`
+    /// through the given position, summed with [`Decimal`]. The sum starts
+    /// from the latest checkpoint of the account on or before the date of the
+    /// position, and records a checkpoint at that date, so that paging
+    /// through the statement only loads the amounts since the previous page.
+    /// A checkpoint is a cache of the transactions rather than a change of
+    /// the books, so it is not audited.
+    #[instrument(skip(self))]
+    pub async fn get_account_total(
+        &mut self,
+        account_id: Uuid,
+        from: NaiveDate,
+        through: (NaiveDate, i64),
+    ) -> Result<Decimal, Error> {
+        let (query, values) = Query::select()
+            .columns([AccountCheckpoints::Date, AccountCheckpoints::Total])
+            .from(AccountCheckpoints::Table)
+            .and_where(Expr::col(AccountCheckpoints::AccountId).eq(account_id.to_string()))
+            .and_where(Expr::col(AccountCheckpoints::OpeningDate).eq(from.to_string()))
+            .and_where(Expr::col(AccountCheckpoints::Date).lte(through.0.to_string()))
+            .order_by(AccountCheckpoints::Date, Order::Desc)
+            .limit(1)
+            .build_sqlx(SqliteQueryBuilder);
+        let checkpoint: Option<(NaiveDate, String)> = sqlx::query_as_with(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?;
+        let (start, mut total) = match checkpoint {
+            Some((date, total)) => (date, decode_amount(&AccountCheckpoints::Total, &total)?),
+            None => (from, Decimal::ZERO),
+        };
+
+        let (query, values) = SqliteStore::account_statement(account_id, start)
+            .columns([Transactions::Date, Transactions::Amount])
+            .cond_where(SqliteStore::statement_position(through, false))
+            .build_sqlx(SqliteQueryBuilder);
+        let amounts: Vec<(NaiveDate, String)> = sqlx::query_as_with(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+        let mut same_day = Decimal::ZERO;
+        for (date, amount) in amounts {
+            let amount = decode_amount(&Transactions::Amount, &amount)?;
+            if date < through.0 {
+                total += amount;
+            } else {
+                same_day += amount;
+            }
+        }
+
+        if start < through.0 {
+            let (query, values) = Query::insert()
+                .into_table(AccountCheckpoints::Table)
+                .columns([
+                    AccountCheckpoints::AccountId,
+                    AccountCheckpoints::OpeningDate,
+                    AccountCheckpoints::Date,
+                    AccountCheckpoints::Total,
+                ])
+                .values([
+                    account_id.to_string().into(),
+                    from.to_string().into(),
+                    through.0.to_string().into(),
+                    total.to_string().into(),
+                ])?
+                .on_conflict(
+                    OnConflict::columns([
+                        AccountCheckpoints::AccountId,
+                        AccountCheckpoints::OpeningDate,
+                        AccountCheckpoints::Date,
+                    ])
+                    .update_column(AccountCheckpoints::Total)
+                    .to_owned(),
+                )
+                .build_sqlx(SqliteQueryBuilder);
+
+            sqlx::query_with(&query, values)
+                .execute(&mut *self.transaction)
+                .await?;
+        }
+
+        Ok(total + same_day)
+    }
+
+    /// Creates the account and posts its opening balance.
+    #[instrument(skip(self))]
+    pub async fn create_account(&mut self, account: &WithId<Account>) -> Result<(), Error> {
+        let WithId { id, data } = account;
+        let (query, values) = Query::insert()
+            .into_table(Accounts::Table)
+            .columns([
+                Accounts::Id,
+                Accounts::Name,
+                Accounts::Kind,
+                Accounts::OpeningBalance,
+                Accounts::OpeningDate,
+                Accounts::Currency,
+            ])
+            .values([
+                id.to_string().into(),
+                data.name.clone().into(),
+                data.kind.as_str().into(),
+                data.opening_balance.into(),
+                data.opening_date.to_string().into(),
+                data.currency.clone().into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        if let Some(entry) = JournalEntry::from_opening_balance(account) {
+            self.post_journal_entries(
+                EntrySource::OpeningBalance,
+                &[WithId {
+                    id: *id,
+                    data: entry,
+                }],
+            )
+            .await?;
+        }
+        self.audit([Change::created("account", id, data)]).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_accounts(&mut self) -> Result<Vec<WithId<Account>>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                Accounts::Id,
+                Accounts::Name,
+                Accounts::Kind,
+                Accounts::OpeningBalance,
+                Accounts::OpeningDate,
+                Accounts::Currency,
+            ])
+            .from(Accounts::Table)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, WithId<Account>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::AccountNotFound`] if no account has the given id.
+    #[instrument(skip(self))]
+    pub async fn get_account(&mut self, account_id: Uuid) -> Result<WithId<Account>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                Accounts::Id,
+                Accounts::Name,
+                Accounts::Kind,
+                Accounts::OpeningBalance,
+                Accounts::OpeningDate,
+                Accounts::Currency,
+            ])
+            .from(Accounts::Table)
+            .and_where(Expr::col(Accounts::Id).eq(account_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with::<_, WithId<Account>, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::AccountNotFound(account_id))
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::PeriodClosed`] if the entry is dated in a closed
+    /// period.
+    #[instrument(skip(self))]
+    pub async fn create_journal_entry(
+        &mut self,
+        WithId { id, data }: &WithId<JournalEntry>,
+    ) -> Result<(), Error> {
+        self.ensure_period_open([data.date]).await?;
+
+        self.insert_journal_entries(
+            None,
+            &[WithId {
+                id: *id,
+                data: data.clone(),
+            }],
+        )
+        .await?;
+
+        self.audit([Change::created("journal_entry", id, data)])
+            .await
+    }
+
+    /// Posts the entries of the transactions or accounts whose ids they
+    /// share. They are derived from what they are posted for, so they are not
+    /// audited themselves.
+    async fn post_journal_entries(
+        &mut self,
+        source: EntrySource,
+        entries: &[WithId<JournalEntry>],
+    ) -> Result<(), Error> {
+        self.insert_journal_entries(Some(source), entries).await
+    }
+
+    /// Removes the entry posted for the transaction or account, if any.
+    async fn unpost_journal_entry(&mut self, id: Uuid) -> Result<(), Error> {
+        let (query, values) = Query::delete()
+            .from_table(Postings::Table)
+            .and_where(Expr::col(Postings::JournalEntryId).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        let (query, values) = Query::delete()
+            .from_table(JournalEntries::Table)
+            .and_where(Expr::col(JournalEntries::Id).eq(id.to_string()))
+            .and_where(Expr::col(JournalEntries::Source).is_not_null())
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+        Ok(())
+    }
+
+    async fn insert_journal_entries(
+        &mut self,
+        source: Option<EntrySource>,
+        entries: &[WithId<JournalEntry>],
+    ) -> Result<(), Error> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let created_at = timestamp(Utc::now());
+        let mut query_builder = Query::insert();
+        query_builder.into_table(JournalEntries::Table).columns([
+            JournalEntries::Id,
+            JournalEntries::Date,
+            JournalEntries::Memo,
+            JournalEntries::CreatedAt,
+            JournalEntries::Source,
+        ]);
+        for WithId { id, data } in entries {
+            query_builder.values([
+                id.to_string().into(),
+                data.date.to_string().into(),
+                data.memo.clone().into(),
+                created_at.clone().into(),
+                source.map(EntrySource::as_str).into(),
+            ])?;
+        }
+        let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        let mut query_builder = Query::insert();
+        query_builder.into_table(Postings::Table).columns([
+            Postings::Id,
+            Postings::JournalEntryId,
+            Postings::AccountId,
+            Postings::Amount,
+        ]);
+        for WithId { id, data } in entries {
+            for posting in &data.postings {
+                query_builder.values([
+                    Uuid::new_v4().to_string().into(),
+                    id.to_string().into(),
+                    posting.account_id.to_string().into(),
+                    posting.amount.into(),
+                ])?;
+            }
+        }
+        let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+        Ok(())
+    }
+
+    /// The journal entries recorded by hand, or only those recorded by the
+    /// given time. Entries recorded before their time was kept are taken as
+    /// recorded since always.
+    #[instrument(skip(self))]
+    pub async fn get_journal_entries(
+        &mut self,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<Vec<WithId<JournalEntry>>, Error> {
+        let entries = Query::select()
+            .from(JournalEntries::Table)
+            .and_where(Expr::col(JournalEntries::Source).is_null())
+            .cond_where(as_of.map_or_else(Cond::all, |at| {
+                Cond::any()
+                    .add(Expr::col(JournalEntries::CreatedAt).is_null())
+                    .add(Expr::col(JournalEntries::CreatedAt).lte(timestamp(at)))
+            }))
+            .take();
+        self.fetch_journal_entries(entries).await
+    }
+
+    /// Every journal entry of the ledger: those posted for the opening
+    /// balances and the transactions, and those recorded by hand.
+    #[instrument(skip(self))]
+    pub async fn get_ledger_entries(&mut self) -> Result<Vec<WithId<JournalEntry>>, Error> {
+        self.fetch_journal_entries(Query::select().from(JournalEntries::Table).take())
+            .await
+    }
+
+    async fn fetch_journal_entries(
+        &mut self,
+        entries: SelectStatement,
+    ) -> Result<Vec<WithId<JournalEntry>>, Error> {
+        let ids = entries.clone().column(JournalEntries::Id).take();
+        let (query, values) = entries
+            .clone()
+            .columns([
+                JournalEntries::Id,
+                JournalEntries::Date,
+                JournalEntries::Memo,
+            ])
+            .build_sqlx(SqliteQueryBuilder);
+
+        let mut entries = sqlx::query_as_with::<_, WithId<JournalEntry>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+
+        let (query, values) = Query::select()
+            .columns([
+                Postings::JournalEntryId,
+                Postings::AccountId,
+                Postings::Amount,
+            ])
+            .from(Postings::Table)
+            .and_where(Expr::col(Postings::JournalEntryId).in_subquery(ids))
+            .build_sqlx(SqliteQueryBuilder);
+
+        let postings = sqlx::query_as_with::<_, PostingOfEntry, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+
+        let positions: HashMap<_, _> = entries
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| (entry.id, position))
+            .collect();
+        for PostingOfEntry {
+            journal_entry_id,
+            posting,
+        } in postings
+        {
+            if let Some(&position) = positions.get(&journal_entry_id) {
+                entries[position].data.postings.push(posting);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_reconciliation(
+        &mut self,
+        WithId { id, data }: &WithId<Reconciliation>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(Reconciliations::Table)
+            .columns([
+                Reconciliations::Id,
+                Reconciliations::AccountId,
+                Reconciliations::StatementDate,
+                Reconciliations::ClosingBalance,
+                Reconciliations::Status,
+            ])
+            .values([
+                id.to_string().into(),
+                data.account_id.to_string().into(),
+                data.statement_date.to_string().into(),
+                data.closing_balance.into(),
+                data.status.as_str().into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("reconciliation", id, data)])
+            .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::ReconciliationNotFound`] if no reconciliation has the
+    /// given id.
+    #[instrument(skip(self))]
+    pub async fn get_reconciliation(&mut self, id: Uuid) -> Result<WithId<Reconciliation>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                Reconciliations::Id,
+                Reconciliations::AccountId,
+                Reconciliations::StatementDate,
+                Reconciliations::ClosingBalance,
+                Reconciliations::Status,
+            ])
+            .from(Reconciliations::Table)
+            .and_where(Expr::col(Reconciliations::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with::<_, WithId<Reconciliation>, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::ReconciliationNotFound(id))
+    }
+
+    /// The open reconciliation of the account, if any.
+    #[instrument(skip(self))]
+    pub async fn get_open_reconciliation(
+        &mut self,
+        account_id: Uuid,
+    ) -> Result<Option<WithId<Reconciliation>>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                Reconciliations::Id,
+                Reconciliations::AccountId,
+                Reconciliations::StatementDate,
+                Reconciliations::ClosingBalance,
+                Reconciliations::Status,
+            ])
+            .from(Reconciliations::Table)
+            .and_where(Expr::col(Reconciliations::AccountId).eq(account_id.to_string()))
+            .and_where(Expr::col(Reconciliations::Status).eq(ReconciliationStatus::Open.as_str()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(
+            sqlx::query_as_with::<_, WithId<Reconciliation>, _>(&query, values)
+                .fetch_optional(&mut *self.transaction)
+                .await?,
+        )
+    }
+
+    #[instrument(skip(self))]
+    pub async fn set_reconciliation_status(
+        &mut self,
+        id: Uuid,
+        status: ReconciliationStatus,
+    ) -> Result<(), Error> {
+        let before = self.get_reconciliation(id).await?;
+
+        let (query, values) = Query::update()
+            .table(Reconciliations::Table)
+            .value(Reconciliations::Status, status.as_str())
+            .and_where(Expr::col(Reconciliations::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::updated(
+            "reconciliation",
+            &id,
+            &before.data,
+            &Reconciliation {
+                status,
+                ..before.data.clone()
+            },
+        )])
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_schedule(
+        &mut self,
+        WithId { id, data }: &WithId<Schedule>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(Schedules::Table)
+            .columns(Schedules::columns())
+            .values([
+                id.to_string().into(),
+                data.account_id.map(|x| x.to_string()).into(),
+                data.amount.into(),
+                data.memo.clone().into(),
+                data.category.clone().into(),
+                data.frequency.as_str().into(),
+                data.interval.into(),
+                data.day_of_month.into(),
+                data.start_date.to_string().into(),
+                data.end_date.map(|x| x.to_string()).into(),
+                data.count.into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("schedule", id, data)]).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_schedules(&mut self) -> Result<Vec<WithId<Schedule>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Schedules::columns())
+            .from(Schedules::Table)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(
+            sqlx::query_as_with::<_, WithId<Schedule>, _>(&query, values)
+                .fetch_all(&mut *self.transaction)
+                .await?,
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::ScheduleNotFound`] if no schedule has the given id.
+    #[instrument(skip(self))]
+    pub async fn get_schedule(&mut self, id: Uuid) -> Result<WithId<Schedule>, Error> {
+        let (query, values) = Query::select()
+            .columns(Schedules::columns())
+            .from(Schedules::Table)
+            .and_where(Expr::col(Schedules::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with::<_, WithId<Schedule>, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::ScheduleNotFound(id))
+    }
+
+    /// Dates of the occurrences of the schedule that are already
+    /// transactions.
+    #[instrument(skip(self))]
+    pub async fn get_schedule_dates(&mut self, schedule_id: Uuid) -> Result<Vec<NaiveDate>, Error> {
+        let (query, values) = Query::select()
+            .column(Transactions::Date)
+            .from(Transactions::Table)
+            .and_where(Expr::col(Transactions::ScheduleId).eq(schedule_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_scalar_with(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_budget(
+        &mut self,
+        WithId { id, data }: &WithId<Budget>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(Budgets::Table)
+            .columns([
+                Budgets::Id,
+                Budgets::Category,
+                Budgets::StartDate,
+                Budgets::EndDate,
+                Budgets::Amount,
+                Budgets::Rollover,
+            ])
+            .values([
+                id.to_string().into(),
+                data.category.clone().into(),
+                data.start_date.to_string().into(),
+                data.end_date.to_string().into(),
+                data.amount.into(),
+                data.rollover.into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("budget", id, data)]).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_budgets(&mut self) -> Result<Vec<WithId<Budget>>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                Budgets::Id,
+                Budgets::Category,
+                Budgets::StartDate,
+                Budgets::EndDate,
+                Budgets::Amount,
+                Budgets::Rollover,
+            ])
+            .from(Budgets::Table)
+            .order_by(Budgets::Category, Order::Asc)
+            .order_by(Budgets::StartDate, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, WithId<Budget>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// Stores the attachment, along with its content unless the same content
+    /// is already stored.
+    #[instrument(skip(self, content))]
+    pub async fn create_attachment(
+        &mut self,
+        WithId { id, data }: &WithId<Attachment>,
+        content: Vec<u8>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(AttachmentContents::Table)
+            .columns([AttachmentContents::Hash, AttachmentContents::Content])
+            .values([data.hash.clone().into(), content.into()])?
+            .on_conflict(
+                OnConflict::column(AttachmentContents::Hash)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        let (query, values) = Query::insert()
+            .into_table(Attachments::Table)
+            .columns(Attachments::columns())
+            .values([
+                id.to_string().into(),
+                data.transaction_id.to_string().into(),
+                data.file_name.clone().into(),
+                data.mime_type.clone().into(),
+                data.size.into(),
+                data.hash.clone().into(),
+                data.uploaded_at
+                    .to_rfc3339_opts(SecondsFormat::Micros, true)
+                    .into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("attachment", id, data)]).await
+    }
+
+    /// The attachments of a transaction, oldest first.
+    #[instrument(skip(self))]
+    pub async fn get_attachments(
+        &mut self,
+        transaction_id: Uuid,
+    ) -> Result<Vec<WithId<Attachment>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Attachments::columns())
+            .from(Attachments::Table)
+            .and_where(Expr::col(Attachments::TransactionId).eq(transaction_id.to_string()))
+            .order_by(Attachments::UploadedAt, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(
+            sqlx::query_as_with::<_, WithId<Attachment>, _>(&query, values)
+                .fetch_all(&mut *self.transaction)
+                .await?,
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::AttachmentNotFound`] if no attachment has the given
+    /// id.
+    #[instrument(skip(self))]
+    pub async fn get_attachment(&mut self, id: Uuid) -> Result<WithId<Attachment>, Error> {
+        let (query, values) = Query::select()
+            .columns(Attachments::columns())
+            .from(Attachments::Table)
+            .and_where(Expr::col(Attachments::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with::<_, WithId<Attachment>, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::AttachmentNotFound(id))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_attachment_content(&mut self, hash: &str) -> Result<Vec<u8>, Error> {
+        let (query, values) = Query::select()
+            .column(AttachmentContents::Content)
+            .from(AttachmentContents::Table)
+            .and_where(Expr::col(AttachmentContents::Hash).eq(hash))
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_scalar_with(&query, values)
+            .fetch_one(&mut *self.transaction)
+            .await?)
+    }
+
+    /// Ids of the transactions with at least one attachment.
+    #[instrument(skip(self))]
+    pub async fn get_attached_transaction_ids(&mut self) -> Result<HashSet<Uuid>, Error> {
+        let (query, values) = Query::select()
+            .distinct()
+            .column(Attachments::TransactionId)
+            .from(Attachments::Table)
+            .build_sqlx(SqliteQueryBuilder);
+
+        let ids: Vec<String> = sqlx::query_scalar_with(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+        Ok(ids.iter().filter_map(|x| Uuid::parse_str(x).ok()).collect())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_customer(
+        &mut self,
+        WithId { id, data }: &WithId<Customer>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(Customers::Table)
+            .columns([Customers::Id, Customers::Name, Customers::Email])
+            .values([
+                id.to_string().into(),
+                data.name.clone().into(),
+                data.email.clone().into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("customer", id, data)]).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_customers(&mut self) -> Result<Vec<WithId<Customer>>, Error> {
+        let (query, values) = Query::select()
+            .columns([Customers::Id, Customers::Name, Customers::Email])
+            .from(Customers::Table)
+            .order_by(Customers::Name, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(
+            sqlx::query_as_with::<_, WithId<Customer>, _>(&query, values)
+                .fetch_all(&mut *self.transaction)
+                .await?,
+        )
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::CustomerNotFound`] if no customer has the given id.
+    #[instrument(skip(self))]
+    pub async fn get_customer(&mut self, id: Uuid) -> Result<WithId<Customer>, Error> {
+        let (query, values) = Query::select()
+            .columns([Customers::Id, Customers::Name, Customers::Email])
+            .from(Customers::Table)
+            .and_where(Expr::col(Customers::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with::<_, WithId<Customer>, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::CustomerNotFound(id))
+    }
+
+    /// Inserts the invoice along with its lines.
+    #[instrument(skip(self))]
+    pub async fn create_invoice(
+        &mut self,
+        WithId { id, data }: &WithId<Invoice>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(Invoices::Table)
+            .columns(Invoices::columns())
+            .values([
+                id.to_string().into(),
+                data.customer_id.to_string().into(),
+                data.number.clone().into(),
+                data.issue_date.to_string().into(),
+                data.due_date.to_string().into(),
+                data.status.as_str().into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        let mut query_builder = Query::insert();
+        query_builder.into_table(InvoiceLines::Table).columns([
+            InvoiceLines::Id,
+            InvoiceLines::InvoiceId,
+            InvoiceLines::Position,
+            InvoiceLines::Description,
+            InvoiceLines::Quantity,
+            InvoiceLines::UnitPrice,
+        ]);
+        for (position, line) in (0u32..).zip(&data.lines) {
+            query_builder.values([
+                Uuid::new_v4().to_string().into(),
+                id.to_string().into(),
+                position.into(),
+                line.description.clone().into(),
+                line.quantity.into(),
+                line.unit_price.into(),
+            ])?;
+        }
+        let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("invoice", id, data)]).await
+    }
+
+    async fn fetch_invoices(
+        &mut self,
+        query: &str,
+        values: SqlxValues,
+    ) -> Result<Vec<WithId<Invoice>>, Error> {
+        let mut invoices = sqlx::query_as_with::<_, WithId<Invoice>, _>(query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+        let positions: HashMap<_, _> = invoices
+            .iter()
+            .enumerate()
+            .map(|(position, invoice)| (invoice.id, position))
+            .collect();
+
+        let (query, values) = Query::select()
+            .columns([
+                InvoiceLines::InvoiceId,
+                InvoiceLines::Description,
+                InvoiceLines::Quantity,
+                InvoiceLines::UnitPrice,
+            ])
+            .from(InvoiceLines::Table)
+            .and_where(
+                Expr::col(InvoiceLines::InvoiceId).is_in(invoices.iter().map(|x| x.id.to_string())),
+            )
+            .order_by(InvoiceLines::Position, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        let lines = sqlx::query_as_with::<_, LineOfInvoice, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+        for LineOfInvoice { invoice_id, line } in lines {
+            if let Some(position) = positions.get(&invoice_id) {
+                invoices[*position].data.lines.push(line);
+            }
+        }
+
+        Ok(invoices)
+    }
+
+    /// Every invoice with its lines, in order of issue date.
+    #[instrument(skip(self))]
+    pub async fn get_invoices(&mut self) -> Result<Vec<WithId<Invoice>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Invoices::columns())
+            .from(Invoices::Table)
+            .order_by(Invoices::IssueDate, Order::Asc)
+            .order_by(Invoices::Number, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_invoices(&query, values).await
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::InvoiceNotFound`] if no invoice has the given id.
+    #[instrument(skip(self))]
+    pub async fn get_invoice(&mut self, id: Uuid) -> Result<WithId<Invoice>, Error> {
+        let (query, values) = Query::select()
+            .columns(Invoices::columns())
+            .from(Invoices::Table)
+            .and_where(Expr::col(Invoices::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        self.fetch_invoices(&query, values)
+            .await?
+            .pop()
+            .ok_or(Error::InvoiceNotFound(id))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn set_invoice_status(
+        &mut self,
+        id: Uuid,
+        status: InvoiceStatus,
+    ) -> Result<(), Error> {
+        let before = self.get_invoice(id).await?;
+
+        let (query, values) = Query::update()
+            .table(Invoices::Table)
+            .value(Invoices::Status, status.as_str())
+            .and_where(Expr::col(Invoices::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::updated(
+            "invoice",
+            &id,
+            &before.data,
+            &Invoice {
+                status,
+                ..before.data.clone()
+            },
+        )])
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_invoice_payment(
+        &mut self,
+        invoice_id: Uuid,
+        WithId { id, data }: &WithId<Payment>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(InvoicePayments::Table)
+            .columns([
+                InvoicePayments::Id,
+                InvoicePayments::InvoiceId,
+                InvoicePayments::TransactionId,
+                InvoicePayments::Amount,
+            ])
+            .values([
+                id.to_string().into(),
+                invoice_id.to_string().into(),
+                data.transaction_id.to_string().into(),
+                data.amount.into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created(
+            "invoice_payment",
+            id,
+            &json!({ "invoice_id": invoice_id, "payment": data }),
+        )])
+        .await
+    }
+
+    /// The payments of every invoice, by invoice id, leaving out those made
+    /// by transactions dated after `through`.
+    #[instrument(skip(self))]
+    pub async fn get_invoice_payments(
+        &mut self,
+        through: Option<NaiveDate>,
+    ) -> Result<HashMap<Uuid, Vec<WithId<Payment>>>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                InvoicePayments::Id,
+                InvoicePayments::InvoiceId,
+                InvoicePayments::TransactionId,
+                InvoicePayments::Amount,
+            ])
+            .from(InvoicePayments::Table)
+            .and_where_option(through.map(|x| {
+                Expr::col(InvoicePayments::TransactionId).in_subquery(Transactions::ids_through(x))
+            }))
+            .build_sqlx(SqliteQueryBuilder);
+
+        let rows = sqlx::query_as_with::<_, PaymentOfInvoice, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+        let mut payments: HashMap<_, Vec<_>> = HashMap::new();
+        for PaymentOfInvoice {
+            invoice_id,
+            payment,
+        } in rows
+        {
+            payments.entry(invoice_id).or_default().push(payment);
+        }
+
+        Ok(payments)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_payments_of_invoice(
+        &mut self,
+        invoice_id: Uuid,
+    ) -> Result<Vec<WithId<Payment>>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                InvoicePayments::Id,
+                InvoicePayments::TransactionId,
+                InvoicePayments::Amount,
+            ])
+            .from(InvoicePayments::Table)
+            .and_where(Expr::col(InvoicePayments::InvoiceId).eq(invoice_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, WithId<Payment>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// The amounts of the transaction already paid to invoices.
+    #[instrument(skip(self))]
+    pub async fn get_invoice_payments_of_transaction(
+        &mut self,
+        transaction_id: Uuid,
+    ) -> Result<Vec<WithId<Payment>>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                InvoicePayments::Id,
+                InvoicePayments::TransactionId,
+                InvoicePayments::Amount,
+            ])
+            .from(InvoicePayments::Table)
+            .and_where(Expr::col(InvoicePayments::TransactionId).eq(transaction_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, WithId<Payment>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_vendor(
+        &mut self,
+        WithId { id, data }: &WithId<Vendor>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(Vendors::Table)
+            .columns([Vendors::Id, Vendors::Name, Vendors::Email])
+            .values([
+                id.to_string().into(),
+                data.name.clone().into(),
+                data.email.clone().into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("vendor", id, data)]).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_vendors(&mut self) -> Result<Vec<WithId<Vendor>>, Error> {
+        let (query, values) = Query::select()
+            .columns([Vendors::Id, Vendors::Name, Vendors::Email])
+            .from(Vendors::Table)
+            .order_by(Vendors::Name, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, WithId<Vendor>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::VendorNotFound`] if no vendor has the given id.
+    #[instrument(skip(self))]
+    pub async fn get_vendor(&mut self, id: Uuid) -> Result<WithId<Vendor>, Error> {
+        let (query, values) = Query::select()
+            .columns([Vendors::Id, Vendors::Name, Vendors::Email])
+            .from(Vendors::Table)
+            .and_where(Expr::col(Vendors::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with::<_, WithId<Vendor>, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::VendorNotFound(id))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_bill(&mut self, WithId { id, data }: &WithId<Bill>) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(Bills::Table)
+            .columns(Bills::columns())
+            .values([
+                id.to_string().into(),
+                data.vendor_id.to_string().into(),
+                data.number.clone().into(),
+                data.issue_date.to_string().into(),
+                data.due_date.to_string().into(),
+                data.amount.into(),
+                data.memo.clone().into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("bill", id, data)]).await
+    }
+
+    /// Every bill, in order of due date.
+    #[instrument(skip(self))]
+    pub async fn get_bills(&mut self) -> Result<Vec<WithId<Bill>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Bills::columns())
+            .from(Bills::Table)
+            .order_by(Bills::DueDate, Order::Asc)
+            .order_by(Bills::Number, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, WithId<Bill>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::BillNotFound`] if no bill has the given id.
+    #[instrument(skip(self))]
+    pub async fn get_bill(&mut self, id: Uuid) -> Result<WithId<Bill>, Error> {
+        let (query, values) = Query::select()
+            .columns(Bills::columns())
+            .from(Bills::Table)
+            .and_where(Expr::col(Bills::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with::<_, WithId<Bill>, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::BillNotFound(id))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_bill_payment(
+        &mut self,
+        bill_id: Uuid,
+        WithId { id, data }: &WithId<Payment>,
+    ) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(BillPayments::Table)
+            .columns([
+                BillPayments::Id,
+                BillPayments::BillId,
+                BillPayments::TransactionId,
+                BillPayments::Amount,
+            ])
+            .values([
+                id.to_string().into(),
+                bill_id.to_string().into(),
+                data.transaction_id.to_string().into(),
+                data.amount.into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
 
-use crate::{
-    entity::{self, Transaction, WithId},
-    error::Error,
-};
+        self.audit([Change::created(
+            "bill_payment",
+            id,
+            &json!({ "bill_id": bill_id, "payment": data }),
+        )])
+        .await
+    }
 
-#[derive(Iden)]
-enum Report {
-    Table,
-    Id,
-    GrossRevenue,
-    Expenses,
-}
+    /// The payments of every bill, by bill id, leaving out those made by
+    /// transactions dated after `through`.
+    #[instrument(skip(self))]
+    pub async fn get_bill_payments(
+        &mut self,
+        through: Option<NaiveDate>,
+    ) -> Result<HashMap<Uuid, Vec<WithId<Payment>>>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                BillPayments::Id,
+                BillPayments::BillId,
+                BillPayments::TransactionId,
+                BillPayments::Amount,
+            ])
+            .from(BillPayments::Table)
+            .and_where_option(through.map(|x| {
+                Expr::col(BillPayments::TransactionId).in_subquery(Transactions::ids_through(x))
+            }))
+            .build_sqlx(SqliteQueryBuilder);
 
-#[derive(Iden)]
-enum Transactions {
-    Table,
-    Id,
-    Date,
-    Amount,
-    Memo,
-}
+        let rows = sqlx::query_as_with::<_, PaymentOfBill, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?;
+        let mut payments: HashMap<_, Vec<_>> = HashMap::new();
+        for PaymentOfBill { bill_id, payment } in rows {
+            payments.entry(bill_id).or_default().push(payment);
+        }
 
-#[derive(Debug)]
-pub struct SqliteStore<'a> {
-    transaction: sqlx::Transaction<'a, Sqlite>,
-}
+        Ok(payments)
+    }
 
-impl<'a> SqliteStore<'a> {
-    #[must_use]
-    pub fn from_sqlite_transaction(transaction: sqlx::Transaction<'a, Sqlite>) -> SqliteStore {
-        SqliteStore { transaction }
+    #[instrument(skip(self))]
+    pub async fn get_payments_of_bill(
+        &mut self,
+        bill_id: Uuid,
+    ) -> Result<Vec<WithId<Payment>>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                BillPayments::Id,
+                BillPayments::TransactionId,
+                BillPayments::Amount,
+            ])
+            .from(BillPayments::Table)
+            .and_where(Expr::col(BillPayments::BillId).eq(bill_id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, WithId<Payment>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
     }
 
+    /// The amounts of the transaction already paid to bills.
     #[instrument(skip(self))]
-    pub async fn get_reports(&mut self) -> Result<Vec<entity::Report>, Error> {
+    pub async fn get_bill_payments_of_transaction(
+        &mut self,
+        transaction_id: Uuid,
+    ) -> Result<Vec<WithId<Payment>>, Error> {
         let (query, values) = Query::select()
-            .columns([Report::GrossRevenue, Report::Expenses])
-            .from(Report::Table)
+            .columns([
+                BillPayments::Id,
+                BillPayments::TransactionId,
+                BillPayments::Amount,
+            ])
+            .from(BillPayments::Table)
+            .and_where(Expr::col(BillPayments::TransactionId).eq(transaction_id.to_string()))
             .build_sqlx(SqliteQueryBuilder);
 
-        Ok(sqlx::query_as_with::<_, entity::Report, _>(&query, values)
+        Ok(sqlx::query_as_with::<_, WithId<Payment>, _>(&query, values)
             .fetch_all(&mut *self.transaction)
             .await?)
     }
 
+    #[instrument(skip(self, trips))]
+    pub async fn create_trips(&mut self, trips: &[WithId<Trip>]) -> Result<(), Error> {
+        if trips.is_empty() {
+            return Ok(());
+        }
+
+        let mut query_builder = Query::insert();
+        query_builder
+            .into_table(Trips::Table)
+            .columns(Trips::columns());
+        for WithId { id, data } in trips {
+            query_builder.values(Trips::values(id, data))?;
+        }
+        let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit(
+            trips
+                .iter()
+                .map(|WithId { id, data }| Change::created("trip", id, data)),
+        )
+        .await
+    }
+
+    /// Trips between two dates, both included, in order of date.
     #[instrument(skip(self))]
-    pub async fn create_report(
+    pub async fn get_trips(
         &mut self,
-        WithId { id, data }: &WithId<entity::Report>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<Vec<WithId<Trip>>, Error> {
+        let (query, values) = Query::select()
+            .columns(Trips::columns())
+            .from(Trips::Table)
+            .and_where_option(from.map(|x| Expr::col(Trips::Date).gte(x.to_string())))
+            .and_where_option(to.map(|x| Expr::col(Trips::Date).lte(x.to_string())))
+            .order_by(Trips::Date, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, WithId<Trip>, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::TripNotFound`] if no trip has the given id.
+    #[instrument(skip(self))]
+    pub async fn get_trip(&mut self, id: Uuid) -> Result<WithId<Trip>, Error> {
+        let (query, values) = Query::select()
+            .columns(Trips::columns())
+            .from(Trips::Table)
+            .and_where(Expr::col(Trips::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_as_with::<_, WithId<Trip>, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?
+            .ok_or(Error::TripNotFound(id))
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::TripNotFound`] if no trip has the given id.
+    #[instrument(skip(self))]
+    pub async fn update_trip(&mut self, WithId { id, data }: &WithId<Trip>) -> Result<(), Error> {
+        let before = self.get_trip(*id).await?;
+
+        let (query, values) = Query::update()
+            .table(Trips::Table)
+            .values(
+                Trips::columns()
+                    .into_iter()
+                    .zip(Trips::values(id, data))
+                    .skip(1),
+            )
+            .and_where(Expr::col(Trips::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::updated("trip", id, &before.data, data)])
+            .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`Error::TripNotFound`] if no trip has the given id.
+    #[instrument(skip(self))]
+    pub async fn delete_trip(&mut self, id: Uuid) -> Result<(), Error> {
+        let before = self.get_trip(id).await?;
+
+        let (query, values) = Query::delete()
+            .from_table(Trips::Table)
+            .and_where(Expr::col(Trips::Id).eq(id.to_string()))
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::deleted("trip", &id, &before.data)])
+            .await
+    }
+
+    /// Inserts the alias, replacing any alias of the same memo.
+    #[instrument(skip(self))]
+    pub async fn upsert_payee_alias(&mut self, alias: &PayeeAlias) -> Result<(), Error> {
+        let (query, values) = Query::select()
+            .columns([PayeeAliases::Memo, PayeeAliases::Payee])
+            .from(PayeeAliases::Table)
+            .and_where(Expr::col(PayeeAliases::Memo).eq(alias.memo.clone()))
+            .build_sqlx(SqliteQueryBuilder);
+        let before = sqlx::query_as_with::<_, PayeeAlias, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?;
+
+        let (query, values) = Query::insert()
+            .into_table(PayeeAliases::Table)
+            .columns([PayeeAliases::Memo, PayeeAliases::Payee])
+            .values([alias.memo.clone().into(), alias.payee.clone().into()])?
+            .on_conflict(
+                OnConflict::column(PayeeAliases::Memo)
+                    .update_column(PayeeAliases::Payee)
+                    .to_owned(),
+            )
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([match before {
+            Some(before) => Change::updated("payee_alias", &alias.memo, &before, alias),
+            None => Change::created("payee_alias", &alias.memo, alias),
+        }])
+        .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_payee_aliases(&mut self) -> Result<Vec<PayeeAlias>, Error> {
+        let (query, values) = Query::select()
+            .columns([PayeeAliases::Memo, PayeeAliases::Payee])
+            .from(PayeeAliases::Table)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, PayeeAlias, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// Fails if any of the dates is on or before the date through which the
+    /// books are closed.
+    async fn ensure_period_open(
+        &mut self,
+        dates: impl IntoIterator<Item = NaiveDate>,
     ) -> Result<(), Error> {
-        let report = &data;
+        let Some(closed_through) = self.get_closed_through().await? else {
+            return Ok(());
+        };
+        match dates.into_iter().filter(|x| *x <= closed_through).min() {
+            Some(date) => Err(Error::PeriodClosed {
+                date,
+                closed_through,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// The date through which the books are currently closed, if any: the
+    /// one of the last closing inserted, whatever the clock said then.
+    #[instrument(skip(self))]
+    pub async fn get_closed_through(&mut self) -> Result<Option<NaiveDate>, Error> {
+        let (query, values) = Query::select()
+            .column(PeriodClosings::ClosedThrough)
+            .from(PeriodClosings::Table)
+            .order_by_expr(Expr::cust("rowid"), Order::Desc)
+            .limit(1)
+            .build_sqlx(SqliteQueryBuilder);
+
+        let closed_through: Option<Option<NaiveDate>> = sqlx::query_scalar_with(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?;
+
+        Ok(closed_through.flatten())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_period_closing(&mut self, closing: &PeriodClosing) -> Result<(), Error> {
+        let id = Uuid::new_v4();
         let (query, values) = Query::insert()
-            .into_table(Report::Table)
-            .columns([Report::Id, Report::GrossRevenue, Report::Expenses])
+            .into_table(PeriodClosings::Table)
+            .columns([
+                PeriodClosings::Id,
+                PeriodClosings::ClosedThrough,
+                PeriodClosings::Reason,
+                PeriodClosings::RecordedAt,
+            ])
             .values([
                 id.to_string().into(),
-                report.gross_revenue.into(),
-                report.expenses.into(),
+                closing.closed_through.map(|x| x.to_string()).into(),
+                closing.reason.clone().into(),
+                closing
+                    .recorded_at
+                    .to_rfc3339_opts(SecondsFormat::Micros, true)
+                    .into(),
             ])?
             .build_sqlx(SqliteQueryBuilder);
 
         sqlx::query_with(&query, values)
             .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("period_closing", &id, closing)])
             .await
-            .map_err(Error::QueryError)
-            .map(|_| ())
     }
 
+    /// Every change of the closing date, in the order they were made.
     #[instrument(skip(self))]
-    async fn get_no_transactions(&mut self) -> Result<usize, Error> {
-        let mut query_builder = Query::select();
-        query_builder.from(Transactions::Table).columns([
-            Transactions::Id,
-            Transactions::Date,
-            Transactions::Amount,
-            Transactions::Memo,
-        ]);
-
-        let (transactions_query, transactions_values) =
-            query_builder.build_sqlx(SqliteQueryBuilder);
+    pub async fn get_period_closings(&mut self) -> Result<Vec<PeriodClosing>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                PeriodClosings::ClosedThrough,
+                PeriodClosings::Reason,
+                PeriodClosings::RecordedAt,
+            ])
+            .from(PeriodClosings::Table)
+            .order_by_expr(Expr::cust("rowid"), Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
 
-        sqlx::query_with(&transactions_query, transactions_values)
+        Ok(sqlx::query_as_with::<_, PeriodClosing, _>(&query, values)
             .fetch_all(&mut *self.transaction)
-            .await
-            .map_err(Error::QueryError)
-            .map(|x| x.len())
+            .await?)
     }
 
-    #[instrument(skip(self, transactions))]
-    pub async fn create_transactions(
-        &mut self,
-        transactions: impl IntoIterator<Item = WithId<&Transaction>>,
-    ) -> Result<(), Error> {
-        let mut query_builder = Query::insert();
-        query_builder.into_table(Transactions::Table).columns([
-            Transactions::Id,
-            Transactions::Date,
-            Transactions::Amount,
-            Transactions::Memo,
-        ]);
+    /// Inserts the rates, replacing any rate already known for the same date
+    /// and pair of currencies.
+    #[instrument(skip(self, rates))]
+    pub async fn upsert_exchange_rates(&mut self, rates: &[ExchangeRate]) -> Result<(), Error> {
+        const CHUNK_SIZE: usize = 1000;
 
-        for transaction in transactions {
-            let id = transaction.id;
-            let data = &transaction.data;
-            query_builder.values([
-                id.to_string().into(),
-                data.date.to_string().into(),
-                data.amount.into(),
-                data.memo.clone().into(),
-            ])?;
+        let from = rates.iter().map(|x| x.date).min();
+        let to = rates.iter().map(|x| x.date).max();
+        let before: HashMap<_, _> = self
+            .get_exchange_rates(from, to)
+            .await?
+            .into_iter()
+            .map(|x| ((x.date, x.base.clone(), x.quote.clone()), x))
+            .collect();
+
+        for chunk in rates.chunks(CHUNK_SIZE) {
+            let mut query_builder = Query::insert();
+            query_builder
+                .into_table(ExchangeRates::Table)
+                .columns([
+                    ExchangeRates::Date,
+                    ExchangeRates::Base,
+                    ExchangeRates::Quote,
+                    ExchangeRates::Rate,
+                ])
+                .on_conflict(
+                    OnConflict::columns([
+                        ExchangeRates::Date,
+                        ExchangeRates::Base,
+                        ExchangeRates::Quote,
+                    ])
+                    .update_column(ExchangeRates::Rate)
+                    .to_owned(),
+                );
+            for rate in chunk {
+                query_builder.values([
+                    rate.date.to_string().into(),
+                    rate.base.clone().into(),
+                    rate.quote.clone().into(),
+                    rate.rate.into(),
+                ])?;
+            }
+            let (query, values) = query_builder.build_sqlx(SqliteQueryBuilder);
+
+            sqlx::query_with(&query, values)
+                .execute(&mut *self.transaction)
+                .await?;
         }
 
-        let (transactions_query, transactions_values) =
-            query_builder.build_sqlx(SqliteQueryBuilder);
+        self.audit(rates.iter().map(|rate| {
+            let id = format!("{}/{}/{}", rate.date, rate.base, rate.quote);
+            match before.get(&(rate.date, rate.base.clone(), rate.quote.clone())) {
+                Some(before) => Change::updated("exchange_rate", &id, before, rate),
+                None => Change::created("exchange_rate", &id, rate),
+            }
+        }))
+        .await
+    }
 
-        sqlx::query_with(&transactions_query, transactions_values)
+    /// Rates dated within the inclusive bounds.
+    #[instrument(skip(self))]
+    pub async fn get_exchange_rates(
+        &mut self,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<Vec<ExchangeRate>, Error> {
+        let (query, values) = Query::select()
+            .columns([
+                ExchangeRates::Date,
+                ExchangeRates::Base,
+                ExchangeRates::Quote,
+                ExchangeRates::Rate,
+            ])
+            .from(ExchangeRates::Table)
+            .and_where_option(from.map(|x| Expr::col(ExchangeRates::Date).gte(x.to_string())))
+            .and_where_option(to.map(|x| Expr::col(ExchangeRates::Date).lte(x.to_string())))
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, ExchangeRate, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    /// Appends the link to the hash chain. Links are numbered, so of two
+    /// uploads chained to the same link only the first is committed.
+    #[instrument(skip(self))]
+    pub async fn create_batch(&mut self, batch: &Batch) -> Result<(), Error> {
+        let (query, values) = Query::insert()
+            .into_table(Batches::Table)
+            .columns(Batches::columns())
+            .values([
+                batch.sequence.into(),
+                batch.report_id.to_string().into(),
+                batch.previous_hash.clone().into(),
+                batch.hash.clone().into(),
+                batch
+                    .recorded_at
+                    .to_rfc3339_opts(SecondsFormat::Micros, true)
+                    .into(),
+            ])?
+            .build_sqlx(SqliteQueryBuilder);
+
+        sqlx::query_with(&query, values)
             .execute(&mut *self.transaction)
+            .await?;
+
+        self.audit([Change::created("batch", &batch.sequence, batch)])
             .await
-            .map_err(Error::QueryError)
-            .map(|_| ())
+    }
+
+    /// The links of the hash chain, in order.
+    #[instrument(skip(self))]
+    pub async fn get_batches(&mut self) -> Result<Vec<Batch>, Error> {
+        let (query, values) = Query::select()
+            .columns(Batches::columns())
+            .from(Batches::Table)
+            .order_by(Batches::Sequence, Order::Asc)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, Batch, _>(&query, values)
+            .fetch_all(&mut *self.transaction)
+            .await?)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_last_batch(&mut self) -> Result<Option<Batch>, Error> {
+        let (query, values) = Query::select()
+            .columns(Batches::columns())
+            .from(Batches::Table)
+            .order_by(Batches::Sequence, Order::Desc)
+            .limit(1)
+            .build_sqlx(SqliteQueryBuilder);
+
+        Ok(sqlx::query_as_with::<_, Batch, _>(&query, values)
+            .fetch_optional(&mut *self.transaction)
+            .await?)
     }
 
     /// # Errors
@@ -132,16 +3428,38 @@ impl<'a> SqliteStore<'a> {
     }
 }
 
+/// Applies the migrations whose version is within the range, in order and
+/// without recording them, so that tests can store data the way it was stored
+/// before the later migrations.
+#[cfg(test)]
+pub(crate) async fn apply_migrations(
+    pool: &sqlx::SqlitePool,
+    versions: impl std::ops::RangeBounds<i64>,
+) -> Result<(), Error> {
+    use sqlx::Executor;
+
+    for migration in sqlx::migrate!().iter() {
+        if versions.contains(&migration.version) {
+            pool.execute(&*migration.sql).await?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
-    use chrono::NaiveDate;
+    use chrono::{Duration, NaiveDate, Utc};
     use rust_decimal_macros::dec;
     use sqlx::SqlitePool;
+    use uuid::Uuid;
 
     use crate::{
-        entity::{Report, Transaction, WithId},
+        entity::{
+            Account, AccountKind, AuditAction, AuditContext, AuditFilter, PeriodClosing, Report,
+            Transaction, WithId,
+        },
         error,
         query::SqliteStore,
     };
@@ -158,6 +3476,11 @@ mod tests {
     }
 
     #[sqlx::test]
+    #[allow(
+        clippy::useless_vec,
+        clippy::let_unit_value,
+        clippy::ignored_unit_patterns
+    )]
     async fn update_database(pool: SqlitePool) -> Result<(), error::Error> {
         let tx = pool.begin().await?;
         let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
@@ -167,16 +3490,18 @@ mod tests {
                 date: NaiveDate::from_str("2021-07-12").unwrap(),
                 amount: dec!(87.32),
                 memo: "first".to_string(),
+                ..Default::default()
             },
             Transaction {
                 date: NaiveDate::from_str("2023-08-20").unwrap(),
                 amount: dec!(-12.13),
                 memo: "second".to_string(),
+                ..Default::default()
             },
         ];
 
         let _ = sqlite_store
-            .create_transactions(transactions.iter().map(WithId::from_data))
+            .create_transactions(Uuid::new_v4(), transactions.iter().map(WithId::from_data))
             .await?;
         let no_transactions = sqlite_store.get_no_transactions().await?;
 
@@ -185,6 +3510,11 @@ mod tests {
     }
 
     #[sqlx::test]
+    #[allow(
+        clippy::clone_on_copy,
+        clippy::let_unit_value,
+        clippy::ignored_unit_patterns
+    )]
     async fn add_report(pool: SqlitePool) -> Result<(), error::Error> {
         let tx = pool.begin().await?;
         let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
@@ -203,4 +3533,127 @@ mod tests {
         assert_eq!(reports[0], expected_report);
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn add_account(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+
+        let account = WithId::from_data(Account {
+            name: "card".to_string(),
+            kind: AccountKind::CreditCard,
+            opening_balance: dec!(-250.40),
+            opening_date: NaiveDate::from_str("2022-03-01").unwrap(),
+            currency: "CAD".to_string(),
+        });
+        sqlite_store.create_account(&account).await?;
+        let accounts = sqlite_store.get_accounts().await?;
+        let from_store = sqlite_store.get_account(account.id).await?;
+
+        assert!(accounts
+            .iter()
+            .any(|x| x.id == account.id && x.data == account.data));
+        assert_eq!(from_store.id, account.id);
+        assert_eq!(from_store.data, account.data);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn audit_log(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx).with_audit_context(
+            AuditContext::new("alice".to_owned(), Some("req-1".to_owned())),
+        );
+
+        let transaction = WithId::from_data(Transaction {
+            date: NaiveDate::from_str("2023-08-20").unwrap(),
+            amount: dec!(-12.13),
+            memo: "groceries".to_string(),
+            ..Default::default()
+        });
+        sqlite_store
+            .create_transactions(
+                Uuid::new_v4(),
+                [WithId {
+                    id: transaction.id,
+                    data: &transaction.data,
+                }],
+            )
+            .await?;
+        sqlite_store
+            .update_transaction(&WithId {
+                id: transaction.id,
+                data: Transaction {
+                    category: Some("Food".to_owned()),
+                    ..transaction.data.clone()
+                },
+            })
+            .await?;
+
+        let entries = sqlite_store
+            .get_audit_entries(
+                &AuditFilter {
+                    entity_id: Some(transaction.id.to_string()),
+                    ..Default::default()
+                },
+                0,
+                10,
+            )
+            .await?;
+
+        assert_eq!(
+            vec![AuditAction::Create, AuditAction::Update],
+            entries.iter().map(|x| x.action).collect::<Vec<_>>()
+        );
+        assert!(entries
+            .iter()
+            .all(|x| x.actor == "alice" && x.request_id.as_deref() == Some("req-1")));
+        assert_eq!(entries[0].after, entries[1].before);
+        assert_eq!(
+            Some(&serde_json::json!("Food")),
+            entries[1].after.as_ref().map(|x| &x["category"])
+        );
+
+        let result = sqlx::query("DELETE FROM audit_log")
+            .execute(&mut *sqlite_store.transaction)
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn closed_through(pool: SqlitePool) -> Result<(), error::Error> {
+        let tx = pool.begin().await?;
+        let mut sqlite_store = SqliteStore::from_sqlite_transaction(tx);
+        let recorded_at = Utc::now();
+        let closings = [
+            (Some("2021-06-30"), recorded_at),
+            (Some("2021-03-31"), recorded_at),
+            (Some("2021-01-31"), recorded_at - Duration::hours(1)),
+        ]
+        .map(|(through, recorded_at)| PeriodClosing {
+            closed_through: through.map(|x| NaiveDate::from_str(x).unwrap()),
+            reason: None,
+            recorded_at,
+        });
+
+        for closing in &closings {
+            sqlite_store.create_period_closing(closing).await?;
+        }
+
+        assert_eq!(
+            Some(NaiveDate::from_str("2021-01-31").unwrap()),
+            sqlite_store.get_closed_through().await?
+        );
+        assert_eq!(
+            closings.map(|x| x.closed_through).to_vec(),
+            sqlite_store
+                .get_period_closings()
+                .await?
+                .into_iter()
+                .map(|x| x.closed_through)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
 }